@@ -0,0 +1,109 @@
+//! The memory taken by a million rows of a result set, stored one `SGDBRowValue` per value as
+//! before or in the buffers of `SGDBColumnValues`.
+//!
+//! Run with `cargo bench --bench result_memory`.
+#![allow(dead_code)]
+
+#[path = "../src/db/mod.rs"]
+mod db;
+#[path = "../src/sql/mod.rs"]
+mod sql;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use chrono::{TimeZone, Utc};
+use sqlx::types::BigDecimal;
+
+use db::sgdb::{SGDBColumnValues, SGDBRowValue};
+
+const ROWS: usize = 1_000_000;
+
+/// Counts the bytes allocated and not freed yet.
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(new_size, Ordering::SeqCst);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// The value of a column of a typical table at `row`: an id, a name, a price, a nullable score,
+/// a date, a flag and a nullable note.
+fn value(column: usize, row: usize) -> SGDBRowValue {
+    match column {
+        0 => SGDBRowValue::Integer(row as i64),
+        1 => SGDBRowValue::Text(format!("customer {}", row)),
+        2 => SGDBRowValue::Decimal(BigDecimal::from_str(&format!("{}.{:02}", row % 1000, row % 100)).unwrap()),
+        3 if row % 10 == 0 => SGDBRowValue::Null,
+        3 => SGDBRowValue::Double(row as f64 / 7.),
+        4 => SGDBRowValue::Timestamp(Utc.timestamp(1_600_000_000 + row as i64, 0)),
+        5 => SGDBRowValue::Boolean(row % 2 == 0),
+        _ if row % 2 == 0 => SGDBRowValue::Null,
+        _ => SGDBRowValue::Text(format!("a note about the order {} of the customer", row)),
+    }
+}
+
+const COLUMNS: usize = 7;
+
+/// The bytes taken by the result built by `build` and the time it took.
+fn measure<T>(name: &str, build: impl FnOnce() -> T) -> T {
+    let before = ALLOCATED.load(Ordering::SeqCst);
+    let start = Instant::now();
+    let res = build();
+    let elapsed = start.elapsed();
+    let bytes = ALLOCATED.load(Ordering::SeqCst) - before;
+
+    println!(
+        "{:<28} {:>8.1} MiB per million rows, built in {:?}",
+        name,
+        bytes as f64 / (1024. * 1024.) * 1_000_000. / ROWS as f64,
+        elapsed
+    );
+
+    res
+}
+
+fn main() {
+    println!("{} rows of {} columns", ROWS, COLUMNS);
+
+    let rows = measure("Vec<SGDBRowValue>", || {
+        (0..COLUMNS)
+            .map(|column| (0..ROWS).map(|row| value(column, row)).collect::<Vec<_>>())
+            .collect::<Vec<_>>()
+    });
+    let start = Instant::now();
+    let mut order = (0..ROWS).collect::<Vec<_>>();
+    order.sort_by(|a, b| rows[1][*a].compare(&rows[1][*b]));
+    println!("{:<28} sorted by the name in {:?}", "", start.elapsed());
+    drop(rows);
+
+    let columns = measure("SGDBColumnValues", || {
+        (0..COLUMNS)
+            .map(|column| (0..ROWS).map(|row| value(column, row)).collect::<SGDBColumnValues>())
+            .collect::<Vec<_>>()
+    });
+    let start = Instant::now();
+    let mut order = (0..ROWS).collect::<Vec<_>>();
+    order.sort_by(|a, b| columns[1].compare(*a, *b));
+    println!("{:<28} sorted by the name in {:?}", "", start.elapsed());
+}
@@ -1,26 +1,109 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 use crate::config::{SqlifeConfig, ConnectionConfig};
 use crate::db::sgdb::{Connection};
-use crate::db::{Message, MessageResponse, SGDBRelay};
+use crate::db::{ConnectionStatus, MessageResponse, Request, Response, SGDBRelay};
+use crate::history::QueryHistory;
+use crate::workspace::Workspaces;
 use crate::ui::setup_style;
 use eframe::egui;
 use eframe::CreationContext;
 use flume::{Receiver, Sender};
-use log::info;
+use log::{error, info, warn};
 
-use crate::ui::views::{run, CurrentView, MessageID, NewConnectionWindow};
+use crate::ui::views::{run, CurrentView, NewConnectionWindow, RequestID, ViewKind};
 
 pub struct AppData {
     pub new_connection_win: NewConnectionWindow,
 
     handle_db: Option<JoinHandle<()>>,
     pub current_connection: Option<usize>,
+    /// The status of the current connection, `None` without connection.
+    pub connection_status: Option<ConnectionStatus>,
 
-    pub tx_sgdb: Option<Sender<Message<MessageID>>>,
-    pub rx_sgdb: Option<Receiver<MessageResponse<MessageID>>>,
+    /// The view to switch to once the current frame is drawn, set by the views themselves.
+    pub next_view: Option<CurrentView>,
+
+    /// The number of statements run in the open transaction of the current connection, `None`
+    /// in autocommit mode.
+    pub transaction: Option<usize>,
+    /// An action waiting for the user to accept rolling back the open transaction.
+    pub confirm_rollback: Option<RollbackAction>,
+
+    pub tx_sgdb: Option<Sender<Request<RequestID>>>,
+    pub rx_sgdb: Option<Receiver<Response<RequestID>>>,
+    rx_status: Option<Receiver<ConnectionStatus>>,
+    /// The sequence number of the last request sent to the current connection.
+    pub request_seq: Cell<u64>,
+    /// The responses received for the views not shown, until they're shown again.
+    pending_responses: Vec<Response<RequestID>>,
+
+    pub history: QueryHistory,
+    /// The query tabs of the connections, except the current one while its view is shown.
+    pub workspaces: Workspaces,
+}
+
+impl AppData {
+    /// Save the changes of the history and the workspaces once they're old enough, or right away
+    /// if `now`.
+    pub fn save_changes(&mut self, now: bool) {
+        self.history.save_changes(now);
+        self.workspaces.save_changes(now);
+    }
+
+    /// Update `connection_status` with the changes sent by the connection thread.
+    pub fn poll_connection_status(&mut self) {
+        if let Some(rx_status) = &self.rx_status {
+            if let Some(status) = rx_status.try_iter().last() {
+                self.connection_status = Some(status);
+            }
+        }
+    }
+
+    /// Take the responses received for `view`, the ones for the other views are kept until
+    /// they're shown.
+    pub fn take_responses(&mut self, view: ViewKind) -> Vec<Response<RequestID>> {
+        let rx_sgdb = match &self.rx_sgdb {
+            Some(rx_sgdb) => rx_sgdb,
+            None => return vec![],
+        };
+
+        let (mut responses, pending) = std::mem::take(&mut self.pending_responses)
+            .into_iter()
+            .partition(|response| response.id.view == view);
+        self.pending_responses = pending;
+
+        for response in rx_sgdb.try_iter() {
+            if let MessageResponse::TransactionResult(open, _) = response.response {
+                self.transaction = if open {
+                    Some(self.transaction.unwrap_or(0))
+                } else {
+                    None
+                };
+            }
+
+            if response.id.view == view {
+                responses.push(response);
+            } else {
+                self.pending_responses.push(response);
+            }
+        }
+
+        responses
+    }
+}
+
+/// How long exiting waits for the connection to close.
+const EXIT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// An action which closes the current connection.
+#[derive(Clone, Copy)]
+pub enum RollbackAction {
+    SwitchConnection(usize),
+    Exit,
 }
 
 pub struct Sqlife {
@@ -30,23 +113,77 @@ pub struct Sqlife {
 }
 
 impl Sqlife {
-    pub fn switch_connection(&mut self, index: usize) {
-        info!("Switching connection..");
+    /// Run `action`, or ask for a confirmation first if a transaction is open.
+    pub fn request(&mut self, action: RollbackAction) {
+        if self.data.transaction.is_some() {
+            self.data.confirm_rollback = Some(action);
+        } else {
+            self.run_action(action);
+        }
+    }
+
+    /// Run `action`, an open transaction is rolled back.
+    pub fn run_action(&mut self, action: RollbackAction) {
+        self.data.confirm_rollback = None;
 
-        if let Some(tx_sgdb) = &self.data.tx_sgdb {
-            tx_sgdb.send(Message::Close).unwrap();
+        match action {
+            RollbackAction::SwitchConnection(index) => self.switch_connection(index),
+            RollbackAction::Exit => {
+                self.stash_view();
+                self.data.save_changes(true);
+
+                // Give the relay a moment to cancel its queries before the process ends
+                if let Some(closing) = self.close_connection() {
+                    let started = Instant::now();
+                    while !closing.is_finished() && started.elapsed() < EXIT_TIMEOUT {
+                        thread::sleep(Duration::from_millis(10));
+                    }
+                }
+                std::process::exit(0);
+            }
         }
+    }
 
-        info!("Dropping DB threads..");
+    /// Close the current connection, returns the thread waiting for the end of its relay.
+    fn close_connection(&mut self) -> Option<JoinHandle<()>> {
+        // The relay stops once its sender is dropped
+        self.data.tx_sgdb = None;
+        self.data.rx_sgdb = None;
+        self.data.rx_status = None;
+        self.data.connection_status = None;
+        self.data.pending_responses.clear();
 
-        if let Some(handle) = self.data.handle_db.take() {
-            handle.join().unwrap();
+        // The stashed query tabs won't get the responses of the connection
+        if let Some(index) = self.data.current_connection {
+            let connection = &self.config.connections[index].name;
+            self.data.workspaces.abandon_requests(connection);
         }
 
-        info!("DB threads dropped");
+        self.data.transaction = None;
+
+        info!("Dropping DB threads..");
+
+        // The relay cancels its queries and rolls back its transaction first, the UI doesn't
+        // wait for it
+        let handle = self.data.handle_db.take()?;
+        Some(thread::spawn(move || {
+            if handle.join().is_err() {
+                warn!("The DB thread panicked");
+            }
+
+            info!("DB threads dropped");
+        }))
+    }
+
+    pub fn switch_connection(&mut self, index: usize) {
+        info!("Switching connection..");
+
+        self.stash_view();
+        self.close_connection();
 
         let (tx_ui, rx_ui) = flume::unbounded();
         let (tx_db, rx_db) = flume::unbounded();
+        let (tx_status, rx_status) = flume::unbounded();
 
         let con: Connection = self.config.connections[index].clone().into();
 
@@ -58,28 +195,64 @@ impl Sqlife {
                 .unwrap();
 
             runtime.block_on(async move {
-                let sgdb = con.acquire_sgdb().await.unwrap();
-                let mut db = SGDBRelay::new(sgdb, tx_db, rx_ui).await;
+                let sgdb = match con.acquire_sgdb().await {
+                    Ok(sgdb) => sgdb,
+                    Err(err) => {
+                        error!("Can't connect to the database: {}", err);
+                        tx_status.send(ConnectionStatus::Failed(format!("{}", err))).ok();
+                        return;
+                    }
+                };
+
+                tx_status.send(ConnectionStatus::Connected).ok();
+
+                let mut db = SGDBRelay::new(sgdb, tx_db, rx_ui, tx_status).await;
                 db.run().await;
             });
         }));
 
         self.data.tx_sgdb = Some(tx_ui);
         self.data.rx_sgdb = Some(rx_db);
+        self.data.rx_status = Some(rx_status);
+        self.data.connection_status = Some(ConnectionStatus::Connecting);
 
         self.data.current_connection = Some(index);
 
-        self.switch_view(CurrentView::DBView(Default::default()));
+        self.switch_to_db_view();
 
         info!("Changing view done");
     }
 
     pub fn switch_view(&mut self, view: CurrentView) {
+        self.stash_view();
         self.view = view;
 
         self.view.init(&mut self.data, &mut self.config);
     }
 
+    /// Switch to the tables view with the query tabs of the current connection.
+    pub fn switch_to_db_view(&mut self) {
+        self.stash_view();
+
+        let data = match self.data.current_connection {
+            Some(index) => self.data.workspaces.take(&self.config.connections[index].name),
+            None => Default::default(),
+        };
+        self.switch_view(CurrentView::DBView(data));
+    }
+
+    /// Keep the query tabs of the current connection when leaving its tables view.
+    fn stash_view(&mut self) {
+        let index = match (&self.view, self.data.current_connection) {
+            (CurrentView::DBView(_), Some(index)) => index,
+            _ => return,
+        };
+
+        if let CurrentView::DBView(data) = std::mem::replace(&mut self.view, CurrentView::HelloView) {
+            self.data.workspaces.store(&self.config.connections[index].name, data);
+        }
+    }
+
     pub fn new(
         cc: &CreationContext<'_>,
         mut config: SqlifeConfig,
@@ -92,8 +265,17 @@ impl Sqlife {
             handle_db: None,
             tx_sgdb: None,
             rx_sgdb: None,
+            rx_status: None,
+            connection_status: None,
+            request_seq: Cell::new(0),
+            pending_responses: vec![],
             new_connection_win: NewConnectionWindow::default(),
-            current_connection: None
+            current_connection: None,
+            next_view: None,
+            transaction: None,
+            confirm_rollback: None,
+            history: QueryHistory::load().unwrap_or_default(),
+            workspaces: Workspaces::load().unwrap_or_default(),
         };
 
         let mut view = CurrentView::HelloView;
@@ -112,9 +294,110 @@ impl Sqlife {
 }
 
 impl eframe::App for Sqlife {
-    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {}
+    fn on_exit_event(&mut self) -> bool {
+        if self.data.transaction.is_some() {
+            self.data.confirm_rollback = Some(RollbackAction::Exit);
+            false
+        } else {
+            true
+        }
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.stash_view();
+        self.data.save_changes(true);
+    }
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         run(self, ctx);
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::db::sgdb::SGDBKind;
+    use crate::db::Message;
+    use crate::ui::views::{MessageID, Requester};
+
+    /// The app connected to an in-memory SQLite database, without a window.
+    pub(crate) fn app() -> Sqlife {
+        let config = SqlifeConfig {
+            connections: vec![ConnectionConfig::new(
+                "test",
+                SGDBKind::Sqlite,
+                ":memory:",
+                "",
+                None,
+            )],
+            ..Default::default()
+        };
+        let data = AppData {
+            handle_db: None,
+            tx_sgdb: None,
+            rx_sgdb: None,
+            rx_status: None,
+            connection_status: None,
+            request_seq: Cell::new(0),
+            pending_responses: vec![],
+            new_connection_win: NewConnectionWindow::default(),
+            current_connection: None,
+            next_view: None,
+            transaction: None,
+            confirm_rollback: None,
+            history: QueryHistory::default(),
+            workspaces: Workspaces::default(),
+        };
+
+        let mut app = Sqlife {
+            view: CurrentView::HelloView,
+            config,
+            data,
+        };
+        app.switch_connection(0);
+
+        app
+    }
+
+    #[test]
+    fn responses_wait_for_their_view_to_be_shown() {
+        let mut app = app();
+
+        // The tables asked for by the tables view, while another view is shown
+        let started = Instant::now();
+        while app.data.pending_responses.is_empty() {
+            assert!(app.data.take_responses(ViewKind::TableStructureView).is_empty());
+            assert!(started.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let responses = app.data.take_responses(ViewKind::DBView);
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].id.component, MessageID::Tables);
+        assert!(matches!(responses[0].response, MessageResponse::TablesResult(Ok(_))));
+        assert!(app.data.pending_responses.is_empty());
+    }
+
+    #[test]
+    fn transaction_state_follows_the_responses() {
+        let mut app = app();
+        let tx = app.data.tx_sgdb.clone().unwrap();
+        let seq = Cell::new(100);
+        let requester = Requester::new(&tx, &seq, ViewKind::DBView);
+        requester.send(MessageID::Transaction, Message::BeginTransaction);
+
+        let started = Instant::now();
+        while app.data.transaction.is_none() {
+            app.data.take_responses(ViewKind::HelloView);
+            assert!(started.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        requester.send(MessageID::Transaction, Message::RollbackTransaction);
+        while app.data.transaction.is_some() {
+            app.data.take_responses(ViewKind::HelloView);
+            assert!(started.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
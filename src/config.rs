@@ -3,15 +3,19 @@ use directories::ProjectDirs;
 use std::{
     fs::{self, File},
     io::Write,
-    path::PathBuf,
+    path::{Path, PathBuf},
+    time::Duration,
 };
 
+use chrono_tz::Tz;
 use indexmap::IndexMap;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::{
     db::sgdb::{Connection, SGDBKind},
+    export::ExportOptions,
     meta::MetaQuery,
+    sql::format::FormatStyle,
 };
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -20,6 +24,9 @@ pub struct ConnectionConfig {
     pub kind: SGDBKind,
     pub uri: String,
     pub schema: String,
+    /// The time zone of the sessions, UTC if not set. The timestamps are shown in it.
+    #[serde(default)]
+    pub time_zone: Option<Tz>,
     pub meta_queries: IndexMap<String, MetaQuery>,
 }
 
@@ -29,12 +36,14 @@ impl ConnectionConfig {
         kind: SGDBKind,
         uri: impl Into<String>,
         schema: impl Into<String>,
+        time_zone: Option<Tz>,
     ) -> Self {
         Self {
             name: name.into(),
             kind,
             uri: uri.into(),
             schema: schema.into(),
+            time_zone,
             meta_queries: IndexMap::new(),
         }
     }
@@ -42,17 +51,23 @@ impl ConnectionConfig {
 
 impl Into<Connection> for ConnectionConfig {
     fn into(self) -> Connection {
-        Connection::new(self.kind, self.uri, self.schema)
+        Connection::new(self.kind, self.uri, self.schema, self.time_zone)
     }
 }
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct SqlifeConfig {
     pub connections: Vec<ConnectionConfig>,
+    /// The style of the `Format` action of the editor.
+    #[serde(default)]
+    pub format: FormatStyle,
+    /// The options of the last export of a result.
+    #[serde(default)]
+    pub export: ExportOptions,
 }
 
 impl SqlifeConfig {
-    fn config_file_path() -> PathBuf {
+    pub fn config_file_path() -> PathBuf {
         Self::project_dir().config_dir().join("config.json")
     }
 
@@ -65,20 +80,38 @@ impl SqlifeConfig {
     }
 
     pub fn load_custom_path(path: impl Into<PathBuf>) -> Result<Self> {
-        let content = std::fs::read_to_string(path.into())?;
-        serde_json::de::from_str(&content).map_err(|err| anyhow::anyhow!(err))
+        load_json(path.into())
     }
 
     pub fn save(&self) -> Result<()> {
-        let content = serde_json::ser::to_string_pretty(self)?;
+        save_json(Self::config_file_path(), self, true)
+    }
+}
 
-        let config_path = Self::config_file_path();
-        fs::create_dir_all(config_path.parent().unwrap())?;
+/// How long the history and the workspaces wait after a change before being saved, the changes
+/// made meanwhile are saved together. They're saved on exit in any case.
+pub const SAVE_DELAY: Duration = Duration::from_secs(5);
 
-        let mut w = File::create(config_path)?;
+/// Read a value from the JSON file at `path`.
+pub fn load_json<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T> {
+    let content = fs::read_to_string(path)?;
+    serde_json::de::from_str(&content).map_err(|err| anyhow::anyhow!(err))
+}
 
-        w.write(content.as_bytes())?;
+/// Write `value` to the JSON file at `path`, indented if `pretty`, creating its directory.
+pub fn save_json<T: Serialize>(path: impl AsRef<Path>, value: &T, pretty: bool) -> Result<()> {
+    let content = if pretty {
+        serde_json::ser::to_string_pretty(value)?
+    } else {
+        serde_json::ser::to_string(value)?
+    };
 
-        Ok(())
-    }
+    let path = path.as_ref();
+    fs::create_dir_all(path.parent().unwrap())?;
+
+    let mut w = File::create(path)?;
+
+    w.write_all(content.as_bytes())?;
+
+    Ok(())
 }
@@ -1,69 +1,828 @@
 pub mod sgdb;
 
-use self::sgdb::{SGDBFetchResult, SGDBTable, SGDB};
+use self::sgdb::{
+    SGDBColumn, SGDBColumnValues, SGDBError, SGDBFetchResult, SGDBRowSink, SGDBRowValue,
+    SGDBScriptResult, SGDBTable, SGDBTableColumn, SGDBTableForeignKey, SGDBTableIndex,
+    SGDBStatement, SGDBTableTrigger, SGDBTransaction, REQUEST_SEQ, SGDB,
+};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use anyhow::{anyhow, Result};
 use flume::{Receiver, Sender};
+use log::{info, warn};
+
+use crate::sql;
 
 #[derive(Debug)]
-pub enum Message<ID> {
+pub enum Message {
     FetchTables,
-    FetchAll(ID, String, Option<Vec<String>>),
-    Close,
+    /// Run the statements of a script.
+    FetchAll(String),
+    /// Run a single statement with its parameters, its delimiters don't split it.
+    FetchStatement(String, Option<Vec<String>>),
+    FetchColumns(String),
+    FetchIndexes(String),
+    FetchForeignKeys(String),
+    FetchTriggers(String),
+    FetchDDL(String),
+    Execute(Vec<SGDBStatement>),
+    /// Stream the rows of a single statement to a sink, like an export file.
+    Stream(String, Box<dyn SGDBRowSink>),
+    /// Send the rows of a single statement back in batches, up to `limit` rows or until `stop`
+    /// is set.
+    FetchStream {
+        statement: String,
+        limit: Option<usize>,
+        stop: Arc<AtomicBool>,
+    },
+    BeginTransaction,
+    CommitTransaction,
+    RollbackTransaction,
+    /// Cancel the running queries of the request with this sequence number.
+    Cancel(u64),
 }
 
 #[derive(Debug)]
-pub enum MessageResponse<ID: Clone> {
-    FetchAllResult(ID, Result<SGDBFetchResult>),
+pub enum MessageResponse {
+    FetchAllResult(SGDBScriptResult),
     TablesResult(Result<Vec<SGDBTable>>),
+    ColumnsResult(String, Result<Vec<SGDBTableColumn>>),
+    IndexesResult(String, Result<Vec<SGDBTableIndex>>),
+    ForeignKeysResult(String, Result<Vec<SGDBTableForeignKey>>),
+    TriggersResult(String, Result<Vec<SGDBTableTrigger>>),
+    DDLResult(String, Result<String>),
+    ExecuteResult(Result<u64>),
+    /// A batch of the rows of a [`Message::FetchStream`], the first one has no rows and is sent
+    /// as soon as the columns are known. The stream ends with a `StreamResult`.
+    StreamRows(SGDBFetchResult),
+    /// The number of rows streamed.
+    StreamResult(Result<u64>),
+    /// Whether a transaction is open after a begin, commit or rollback, and its result.
+    TransactionResult(bool, Result<()>),
+    CancelResult(Result<()>),
+}
+
+impl MessageResponse {
+    /// The error of the response if it comes from the connection to the database.
+    pub fn connection_error(&self) -> Option<String> {
+        let err = match self {
+            MessageResponse::FetchAllResult(res) => {
+                return res
+                    .error
+                    .as_ref()
+                    .filter(|err| err.connection)
+                    .map(|err| err.message.clone())
+            }
+            MessageResponse::TablesResult(Err(err))
+            | MessageResponse::ColumnsResult(_, Err(err))
+            | MessageResponse::IndexesResult(_, Err(err))
+            | MessageResponse::ForeignKeysResult(_, Err(err))
+            | MessageResponse::TriggersResult(_, Err(err))
+            | MessageResponse::DDLResult(_, Err(err))
+            | MessageResponse::ExecuteResult(Err(err))
+            | MessageResponse::StreamResult(Err(err))
+            | MessageResponse::TransactionResult(_, Err(err))
+            | MessageResponse::CancelResult(Err(err)) => err,
+            _ => return None,
+        };
+
+        Some(format!("{}", err)).filter(|_| is_connection_error(err))
+    }
+}
+
+/// The state of the connection to the database, sent by its thread when it changes.
+#[derive(Debug, Clone)]
+pub enum ConnectionStatus {
+    Connecting,
+    Connected,
+    /// The connection was lost, `attempt` reconnections failed so far.
+    Reconnecting { attempt: u32, error: String },
+    /// The database can't be reached, the relay isn't running.
+    Failed(String),
+}
+
+/// The first delay before reconnecting, doubled after each failed attempt.
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// The rows of a stream are sent once there are as many, or once the last batch is as old.
+const BATCH_ROWS: usize = 1000;
+const BATCH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Whether `err` comes from the connection to the database rather than from the query.
+pub fn is_connection_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<sqlx::Error>(),
+            Some(
+                sqlx::Error::Io(_)
+                    | sqlx::Error::Tls(_)
+                    | sqlx::Error::PoolTimedOut
+                    | sqlx::Error::PoolClosed
+            )
+        )
+    })
+}
+
+/// A message sent to the relay, tagged with an id which comes back with its response.
+#[derive(Debug)]
+pub struct Request<ID> {
+    pub id: ID,
+    pub message: Message,
+}
+
+/// The id of a request, a [`Message::Cancel`] refers to a request by its sequence number.
+pub trait RequestSeq {
+    fn seq(&self) -> u64;
+}
+
+#[derive(Debug)]
+pub struct Response<ID> {
+    pub id: ID,
+    pub response: MessageResponse,
+}
+
+pub struct SGDBRelay<ID> {
+    tx: Sender<Response<ID>>,
+    rx: Receiver<Request<ID>>,
+    status: Sender<ConnectionStatus>,
+
+    sgdb: Arc<dyn SGDB>,
+}
+
+/// Sends the responses, the errors of a lost connection are reported to the relay.
+struct Responder<ID> {
+    tx: Sender<Response<ID>>,
+    lost: Sender<String>,
+    /// The number of requests waiting for their response
+    running: Arc<AtomicUsize>,
+}
+
+impl<ID> Clone for Responder<ID> {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+            lost: self.lost.clone(),
+            running: self.running.clone(),
+        }
+    }
+}
+
+impl<ID> Responder<ID> {
+    fn send(&self, id: ID, response: MessageResponse) {
+        if let Some(err) = response.connection_error() {
+            self.lost.send(err).ok();
+        }
+
+        self.running.fetch_sub(1, Ordering::SeqCst);
+        self.tx.send(Response { id, response }).ok();
+    }
+
+    /// Send a part of the response of a request which is still running, returns whether it
+    /// can still be received.
+    fn send_partial(&self, id: ID, response: MessageResponse) -> bool {
+        self.tx.send(Response { id, response }).is_ok()
+    }
+}
+
+/// Ends a stream early, once its limit is reached or it was stopped, with the number of rows sent.
+#[derive(Debug)]
+struct StreamStopped(u64);
+
+impl fmt::Display for StreamStopped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The stream was stopped after {} rows", self.0)
+    }
+}
+
+impl std::error::Error for StreamStopped {}
+
+/// Sends the rows of a [`Message::FetchStream`] in batches of [`BATCH_ROWS`].
+struct BatchSink<ID> {
+    id: ID,
+    responder: Responder<ID>,
+    limit: Option<usize>,
+    stop: Arc<AtomicBool>,
+
+    columns: Vec<SGDBColumn>,
+    /// The values of the rows not sent yet, by column.
+    values: Vec<SGDBColumnValues>,
+    batch_rows: usize,
+    rows: usize,
+    sent_at: Instant,
+}
+
+impl<ID> fmt::Debug for BatchSink<ID> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BatchSink")
+            .field("limit", &self.limit)
+            .field("rows", &self.rows)
+            .finish()
+    }
+}
+
+impl<ID: Clone> BatchSink<ID> {
+    fn new(id: ID, responder: Responder<ID>, limit: Option<usize>, stop: Arc<AtomicBool>) -> Self {
+        Self {
+            id,
+            responder,
+            limit,
+            stop,
+            columns: vec![],
+            values: vec![],
+            batch_rows: 0,
+            rows: 0,
+            sent_at: Instant::now(),
+        }
+    }
+
+    fn send_batch(&mut self) {
+        let data = self
+            .columns
+            .iter()
+            .cloned()
+            .zip(self.values.iter_mut().map(std::mem::take))
+            .collect();
+        let batch = SGDBFetchResult {
+            data,
+            num_rows: self.batch_rows,
+        };
+
+        self.batch_rows = 0;
+        self.sent_at = Instant::now();
+
+        // Nobody receives the rows once the connection is closed
+        let sent = self
+            .responder
+            .send_partial(self.id.clone(), MessageResponse::StreamRows(batch));
+        if !sent {
+            self.stop.store(true, Ordering::SeqCst);
+        }
+    }
 }
 
-pub struct SGDBRelay<ID: Clone> {
-    tx: Sender<MessageResponse<ID>>,
-    rx: Receiver<Message<ID>>,
+impl<ID: Clone + Send> SGDBRowSink for BatchSink<ID> {
+    fn columns(&mut self, columns: &[SGDBColumn]) -> Result<()> {
+        self.columns = columns.to_vec();
+        self.values = vec![SGDBColumnValues::default(); columns.len()];
+        self.send_batch();
+
+        Ok(())
+    }
+
+    fn row(&mut self, row: Vec<SGDBRowValue>) -> Result<()> {
+        for (values, value) in self.values.iter_mut().zip(row) {
+            values.push(value);
+        }
+        self.batch_rows += 1;
+        self.rows += 1;
 
-    sgdb: Box<dyn SGDB>,
+        let stopped = self.stop.load(Ordering::SeqCst)
+            || self.limit.map_or(false, |limit| self.rows >= limit);
+        if stopped || self.batch_rows >= BATCH_ROWS || self.sent_at.elapsed() >= BATCH_INTERVAL {
+            self.send_batch();
+        }
+
+        match stopped {
+            true => Err(StreamStopped(self.rows as u64).into()),
+            false => Ok(()),
+        }
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if self.batch_rows > 0 {
+            self.send_batch();
+        }
+
+        Ok(())
+    }
 }
 
-impl<ID: Clone> SGDBRelay<ID> {
+impl<ID: RequestSeq + Clone + Send + 'static> SGDBRelay<ID> {
     pub async fn new(
         sgdb: Box<dyn SGDB>,
-        tx: Sender<MessageResponse<ID>>,
-        rx: Receiver<Message<ID>>,
+        tx: Sender<Response<ID>>,
+        rx: Receiver<Request<ID>>,
+        status: Sender<ConnectionStatus>,
     ) -> Self {
-        Self { sgdb, tx, rx }
+        Self {
+            sgdb: sgdb.into(),
+            tx,
+            rx,
+            status,
+        }
     }
 
+    /// Process the requests until the sender is dropped.
+    ///
+    /// The requests run concurrently, except the ones of the session: transactions and the
+    /// queries sent while one is open, which run in order on their own task.
     pub async fn run(&mut self) {
-        while let Ok(msg) = self.rx.recv() {
-            match msg {
-                Message::FetchAll(id, query, params) => {
-                    let res = self
-                        .sgdb
-                        .fetch_all(&query, params)
-                        .await
-                        .map(|res| MessageResponse::FetchAllResult(id.clone(), Ok(res)))
-                        .unwrap_or_else(|err| {
-                            MessageResponse::FetchAllResult(id, Err(anyhow!("{}", err)))
-                        });
-
-                    self.tx.send(res).unwrap();
+        let (tx_session, rx_session) = flume::unbounded();
+        let (tx_lost, rx_lost) = flume::unbounded();
+        // The number of requests sent to the session and not processed yet
+        let queued = Arc::new(AtomicUsize::new(0));
+        let running = Arc::new(AtomicUsize::new(0));
+        let reconnecting = Arc::new(AtomicBool::new(false));
+        // Set by the session once a transaction is begun, a query sent before waits in `queued`
+        let in_transaction = Arc::new(AtomicBool::new(false));
+
+        let responder = Responder {
+            tx: self.tx.clone(),
+            lost: tx_lost,
+            running: running.clone(),
+        };
+
+        let session = tokio::spawn(run_session(
+            self.sgdb.clone(),
+            rx_session.clone(),
+            responder.clone(),
+            queued.clone(),
+            in_transaction.clone(),
+        ));
+
+        loop {
+            let Request { id, message } = tokio::select! {
+                request = self.rx.recv_async() => match request {
+                    Ok(request) => request,
+                    Err(_) => break,
+                },
+                Ok(error) = rx_lost.recv_async() => {
+                    if !reconnecting.swap(true, Ordering::SeqCst) {
+                        warn!("The connection to the database was lost: {}", error);
+
+                        let status = ConnectionStatus::Reconnecting { attempt: 0, error };
+                        self.status.send(status).ok();
+
+                        tokio::spawn(reconnect(
+                            self.sgdb.clone(),
+                            self.status.clone(),
+                            reconnecting.clone(),
+                        ));
+                    }
+                    continue;
                 }
-                Message::FetchTables => {
-                    let res = self
-                        .sgdb
-                        .list_tables()
-                        .await
-                        .map(|res| MessageResponse::TablesResult(Ok(res)))
-                        .unwrap_or_else(|err| {
-                            MessageResponse::TablesResult(Err(anyhow!("{}", err)))
-                        });
-
-                    self.tx.send(res).unwrap();
+            };
+
+            let in_session = match &message {
+                Message::BeginTransaction
+                | Message::CommitTransaction
+                | Message::RollbackTransaction => true,
+                // Don't run a query before the end of the transaction it follows
+                Message::FetchAll(_)
+                | Message::FetchStatement(..)
+                | Message::Execute(_)
+                | Message::Stream(..)
+                | Message::FetchStream { .. } => {
+                    in_transaction.load(Ordering::SeqCst) || queued.load(Ordering::SeqCst) > 0
                 }
-                Message::Close => {
-                    break;
+                _ => false,
+            };
+
+            running.fetch_add(1, Ordering::SeqCst);
+
+            let seq = id.seq();
+            // The batches are sent along the way, with the id of the request
+            let message = match message {
+                Message::FetchStream {
+                    statement,
+                    limit,
+                    stop,
+                } => {
+                    let sink = BatchSink::new(id.clone(), responder.clone(), limit, stop);
+                    Message::Stream(statement, Box::new(sink))
                 }
+                message => message,
+            };
+
+            if in_session {
+                queued.fetch_add(1, Ordering::SeqCst);
+                tx_session.send(Request { id, message }).ok();
+                continue;
+            }
+
+            let sgdb = self.sgdb.clone();
+            let responder = responder.clone();
+
+            tokio::spawn(REQUEST_SEQ.scope(seq, async move {
+                let response = process(&*sgdb, &mut None, message).await;
+
+                responder.send(id, response);
+            }));
+        }
+
+        // Don't wait for the end of the running queries to close
+        if running.load(Ordering::SeqCst) > 0 {
+            if let Err(err) = self.sgdb.cancel(None).await {
+                warn!("Can't cancel the running queries: {}", err);
             }
         }
+
+        rx_session.drain();
+        drop(tx_session);
+
+        if let Err(err) = session.await {
+            warn!("The session task failed: {}", err);
+        }
+    }
+}
+
+/// Process the requests of the session in order, the open transaction is rolled back at the end.
+async fn run_session<ID: RequestSeq>(
+    sgdb: Arc<dyn SGDB>,
+    rx: Receiver<Request<ID>>,
+    responder: Responder<ID>,
+    queued: Arc<AtomicUsize>,
+    in_transaction: Arc<AtomicBool>,
+) {
+    let mut transaction = None;
+
+    while let Ok(Request { id, message }) = rx.recv_async().await {
+        let response = REQUEST_SEQ
+            .scope(id.seq(), process(&*sgdb, &mut transaction, message))
+            .await;
+
+        // Before the request is counted out, the queries sent meanwhile follow it
+        in_transaction.store(transaction.is_some(), Ordering::SeqCst);
+        queued.fetch_sub(1, Ordering::SeqCst);
+        responder.send(id, response);
+    }
+
+    if let Some(transaction) = transaction {
+        transaction.rollback().await.ok();
+    }
+}
+
+/// Ping the database with an exponential backoff until it can be reached again.
+async fn reconnect(
+    sgdb: Arc<dyn SGDB>,
+    status: Sender<ConnectionStatus>,
+    reconnecting: Arc<AtomicBool>,
+) {
+    let mut delay = RECONNECT_DELAY;
+    let mut attempt = 0;
+
+    loop {
+        tokio::time::sleep(delay).await;
+
+        match sgdb.ping().await {
+            Ok(()) => break,
+            Err(err) => {
+                attempt += 1;
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+
+                let error = format!("{}", err);
+                status.send(ConnectionStatus::Reconnecting { attempt, error }).ok();
+            }
+        }
+    }
+
+    info!("Reconnected to the database after {} failed attempts", attempt);
+
+    reconnecting.store(false, Ordering::SeqCst);
+    status.send(ConnectionStatus::Connected).ok();
+}
+
+/// Run the `statements` of `query` in the open transaction, or else on a session of the pool.
+async fn fetch_statements(
+    sgdb: &dyn SGDB,
+    transaction: &mut Option<Box<dyn SGDBTransaction>>,
+    query: &str,
+    statements: Vec<sql::Statement<'_>>,
+    params: Option<Vec<String>>,
+) -> SGDBScriptResult {
+    match transaction.as_mut() {
+        Some(transaction) => sgdb::fetch_statements(&mut **transaction, statements, params).await,
+        None => match sgdb.session().await {
+            Ok(mut session) => sgdb::fetch_statements(&mut *session, statements, params).await,
+            Err(err) => SGDBScriptResult {
+                error: Some(SGDBError::new(&err, query)),
+                ..Default::default()
+            },
+        },
+    }
+}
+
+async fn process(
+    sgdb: &dyn SGDB,
+    transaction: &mut Option<Box<dyn SGDBTransaction>>,
+    msg: Message,
+) -> MessageResponse {
+    match msg {
+        Message::FetchAll(script) => {
+            let statements = sql::split_statements(&script);
+            let res = fetch_statements(sgdb, transaction, &script, statements, None).await;
+
+            MessageResponse::FetchAllResult(res)
+        }
+        Message::FetchStatement(statement, params) => {
+            let statements = vec![sql::Statement::whole(&statement)];
+            let res = fetch_statements(sgdb, transaction, &statement, statements, params).await;
+
+            MessageResponse::FetchAllResult(res)
+        }
+        Message::FetchTables => {
+            let res = sgdb.list_tables().await;
+
+            MessageResponse::TablesResult(res)
+        }
+        Message::FetchColumns(table) => {
+            let res = sgdb.list_columns(&table).await;
+
+            MessageResponse::ColumnsResult(table, res)
+        }
+        Message::FetchIndexes(table) => {
+            let res = sgdb.list_indexes(&table).await;
+
+            MessageResponse::IndexesResult(table, res)
+        }
+        Message::FetchForeignKeys(table) => {
+            let res = sgdb.list_foreign_keys(&table).await;
+
+            MessageResponse::ForeignKeysResult(table, res)
+        }
+        Message::FetchTriggers(table) => {
+            let res = sgdb.list_triggers(&table).await;
+
+            MessageResponse::TriggersResult(table, res)
+        }
+        Message::FetchDDL(table) => {
+            let res = sgdb.table_ddl(&table).await;
+
+            MessageResponse::DDLResult(table, res)
+        }
+        Message::Execute(statements) => {
+            let res = match transaction.as_mut() {
+                Some(transaction) => transaction.execute(statements).await,
+                None => sgdb.execute(statements).await,
+            };
+
+            MessageResponse::ExecuteResult(res)
+        }
+        Message::Stream(statement, mut sink) => {
+            let res = match transaction.as_mut() {
+                Some(transaction) => transaction.stream_statement(&statement, &mut *sink).await,
+                None => match sgdb.session().await {
+                    Ok(mut session) => session.stream_statement(&statement, &mut *sink).await,
+                    Err(err) => Err(err),
+                },
+            };
+            // A stream ended early isn't an error
+            let res = match res {
+                Err(err) => match err.downcast_ref::<StreamStopped>() {
+                    Some(StreamStopped(rows)) => Ok(*rows),
+                    None => Err(err),
+                },
+                res => res,
+            };
+
+            MessageResponse::StreamResult(res)
+        }
+        Message::BeginTransaction => {
+            let res = if transaction.is_some() {
+                Err(anyhow!("A transaction is already open"))
+            } else {
+                sgdb.begin().await.map(|begun| *transaction = Some(begun))
+            };
+
+            MessageResponse::TransactionResult(transaction.is_some(), res)
+        }
+        Message::CommitTransaction => {
+            let res = match transaction.take() {
+                Some(transaction) => transaction.commit().await,
+                None => Err(anyhow!("No transaction is open")),
+            };
+
+            MessageResponse::TransactionResult(false, res)
+        }
+        Message::RollbackTransaction => {
+            let res = match transaction.take() {
+                Some(transaction) => transaction.rollback().await,
+                None => Err(anyhow!("No transaction is open")),
+            };
+
+            MessageResponse::TransactionResult(false, res)
+        }
+        Message::Cancel(seq) => MessageResponse::CancelResult(sgdb.cancel(Some(seq)).await),
+        // The relay sends the stream to a sink, see `SGDBRelay::run`
+        Message::FetchStream { .. } => {
+            MessageResponse::StreamResult(Err(anyhow!("The rows can only be streamed by the relay")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sgdb::{Connection, SGDBColumnType, SGDBKind};
+
+    impl RequestSeq for u64 {
+        fn seq(&self) -> u64 {
+            *self
+        }
+    }
+
+    type Relay = (Sender<Request<u64>>, Receiver<Response<u64>>);
+
+    /// A relay on an in-memory SQLite database, running on its own thread like in the app.
+    fn relay() -> Relay {
+        let (tx_ui, rx_ui) = flume::unbounded();
+        let (tx_db, rx_db) = flume::unbounded();
+
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+
+            runtime.block_on(async move {
+                let connection =
+                    Connection::new(SGDBKind::Sqlite, ":memory:".into(), String::new(), None);
+                let sgdb = connection.acquire_sgdb().await.unwrap();
+
+                SGDBRelay::new(sgdb, tx_db, rx_ui, flume::unbounded().0)
+                    .await
+                    .run()
+                    .await;
+            });
+        });
+
+        (tx_ui, rx_db)
+    }
+
+    /// Send `message` and wait for its response, the other ones received meanwhile are dropped.
+    fn request((tx, rx): &Relay, id: u64, message: Message) -> MessageResponse {
+        tx.send(Request { id, message }).unwrap();
+
+        loop {
+            let response = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+            if response.id == id {
+                return response.response;
+            }
+        }
+    }
+
+    fn fetch_all(relay: &Relay, id: u64, script: &str) -> SGDBScriptResult {
+        match request(relay, id, Message::FetchAll(script.to_string())) {
+            MessageResponse::FetchAllResult(res) => res,
+            response => panic!("Unexpected response {:?}", response),
+        }
+    }
+
+    #[test]
+    fn fetch_all_runs_the_statements_in_order() {
+        let relay = relay();
+
+        let res = fetch_all(
+            &relay,
+            1,
+            "CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT); INSERT INTO t (name) VALUES ('a'), ('b'); SELECT * FROM t",
+        );
+
+        assert!(res.error.is_none(), "{:?}", res.error);
+        assert_eq!(res.statements.len(), 3);
+        assert_eq!(res.statements[1].output.rows_affected, 2);
+
+        let result_set = &res.statements[2].output.result_sets[0];
+        assert_eq!(result_set.num_rows, 2);
+        let (column, values) = result_set.data.get_index(1).unwrap();
+        assert_eq!(column.name(), "name");
+        assert_eq!(column.r#type(), SGDBColumnType::Text);
+        assert_eq!(values.get(1), SGDBRowValue::Text("b".into()));
+    }
+
+    #[test]
+    fn fetch_all_stops_at_the_failed_statement() {
+        let relay = relay();
+
+        let res = fetch_all(&relay, 1, "SELECT 1; SELECT * FROM missing; SELECT 2");
+
+        assert_eq!(res.statements.len(), 1);
+        assert!(res.error.unwrap().message.contains("missing"));
+    }
+
+    #[test]
+    fn expression_types_come_from_the_first_value_not_null() {
+        let relay = relay();
+
+        let res = fetch_all(&relay, 1, "SELECT NULL AS a, 1 AS b UNION ALL SELECT 2.5, 'x'");
+
+        let data = &res.statements[0].output.result_sets[0].data;
+        let types = data.keys().map(|column| column.r#type()).collect::<Vec<_>>();
+        assert_eq!(types, [SGDBColumnType::Double, SGDBColumnType::Integer]);
+
+        // The values keep their own storage class
+        let b = &data[1];
+        assert_eq!(b.get(0), SGDBRowValue::Integer(1));
+        assert_eq!(b.get(1), SGDBRowValue::Text("x".into()));
+    }
+
+    #[test]
+    fn rollback_discards_the_changes_of_the_transaction() {
+        let relay = relay();
+
+        fetch_all(&relay, 1, "CREATE TABLE t (id INTEGER)");
+        assert!(matches!(
+            request(&relay, 2, Message::BeginTransaction),
+            MessageResponse::TransactionResult(true, Ok(()))
+        ));
+
+        // The queries sent during the transaction run in it
+        fetch_all(&relay, 3, "INSERT INTO t VALUES (1)");
+        let res = fetch_all(&relay, 4, "SELECT count(*) FROM t");
+        assert_eq!(
+            res.statements[0].output.result_sets[0].data[0].get(0),
+            SGDBRowValue::Integer(1)
+        );
+
+        assert!(matches!(
+            request(&relay, 5, Message::RollbackTransaction),
+            MessageResponse::TransactionResult(false, Ok(()))
+        ));
+        let res = fetch_all(&relay, 6, "SELECT count(*) FROM t");
+        assert_eq!(
+            res.statements[0].output.result_sets[0].data[0].get(0),
+            SGDBRowValue::Integer(0)
+        );
+    }
+
+    #[test]
+    fn commit_without_transaction_fails() {
+        let relay = relay();
+
+        assert!(matches!(
+            request(&relay, 1, Message::CommitTransaction),
+            MessageResponse::TransactionResult(false, Err(_))
+        ));
+
+        // The next queries don't wait for a transaction
+        let res = fetch_all(&relay, 2, "SELECT 1");
+        assert!(res.error.is_none());
+    }
+
+    /// Stream the rows of `statement`, returns the batches and the number of rows.
+    fn fetch_stream(
+        (tx, rx): &Relay,
+        statement: &str,
+        limit: Option<usize>,
+    ) -> (Vec<SGDBFetchResult>, u64) {
+        let message = Message::FetchStream {
+            statement: statement.to_string(),
+            limit,
+            stop: Arc::new(AtomicBool::new(false)),
+        };
+        tx.send(Request { id: 1, message }).unwrap();
+
+        let mut batches = vec![];
+        loop {
+            match rx.recv_timeout(Duration::from_secs(10)).unwrap().response {
+                MessageResponse::StreamRows(batch) => batches.push(batch),
+                MessageResponse::StreamResult(res) => return (batches, res.unwrap()),
+                response => panic!("Unexpected response {:?}", response),
+            }
+        }
+    }
+
+    #[test]
+    fn fetch_stream_sends_the_rows_in_batches_up_to_the_limit() {
+        let relay = relay();
+
+        let statement = "WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n WHERE x < 5000) SELECT x FROM n";
+        let (batches, rows) = fetch_stream(&relay, statement, Some(2500));
+
+        assert_eq!(rows, 2500);
+        // The columns come first, without rows
+        assert_eq!(batches[0].num_rows, 0);
+        assert_eq!(batches[0].data.keys().next().unwrap().r#type(), SGDBColumnType::Integer);
+        assert_eq!(batches.iter().map(|batch| batch.num_rows).sum::<usize>(), 2500);
+        assert!(batches.iter().all(|batch| batch.num_rows <= BATCH_ROWS));
+
+        let last = batches.last().unwrap();
+        assert_eq!(last.data[0].get(last.num_rows - 1), SGDBRowValue::Integer(2500));
+    }
+
+    #[test]
+    fn fetch_stream_types_the_expressions_before_sending_the_columns() {
+        let relay = relay();
+
+        let (batches, rows) = fetch_stream(&relay, "SELECT NULL UNION ALL SELECT 'a'", None);
+
+        assert_eq!(rows, 2);
+        assert_eq!(batches[0].data.keys().next().unwrap().r#type(), SGDBColumnType::Text);
+        let values = batches.iter().flat_map(|batch| batch.data[0].iter()).collect::<Vec<_>>();
+        assert_eq!(values, [SGDBRowValue::Null, SGDBRowValue::Text("a".into())]);
+    }
+
+    #[test]
+    fn sqlite_queries_cant_be_cancelled() {
+        let relay = relay();
+
+        assert!(matches!(
+            request(&relay, 1, Message::Cancel(0)),
+            MessageResponse::CancelResult(Err(_))
+        ));
     }
 }
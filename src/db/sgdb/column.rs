@@ -0,0 +1,343 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+use std::time::UNIX_EPOCH;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use sqlx::types::{BigDecimal, Uuid};
+
+use super::SGDBRowValue;
+
+/// The values of a column of a result set, stored by type rather than one [`SGDBRowValue`]
+/// each: the numbers, the dates and the UUIDs in a buffer, the texts, the decimals, the JSON
+/// and the binary values one after another in an arena, and the `NULL`s in a bitmap.
+///
+/// The values read are copied out of the column, see [`SGDBColumnValues::get`].
+#[derive(Debug, Clone, Default)]
+pub struct SGDBColumnValues {
+    buffer: Buffer,
+    nulls: Bitmap,
+}
+
+#[derive(Debug, Clone, Default)]
+enum Buffer {
+    /// Only `NULL`s so far, the first other value sets the type of the column.
+    #[default]
+    Nulls,
+    Boolean(Bitmap),
+    Integer(Vec<i64>),
+    UInteger(Vec<u64>),
+    Double(Vec<f64>),
+    /// The decimals in text, they're parsed when read.
+    Decimal(Arena),
+    DateTime(Vec<NaiveDateTime>),
+    Timestamp(Vec<DateTime<Utc>>),
+    Date(Vec<NaiveDate>),
+    Time(Vec<NaiveTime>),
+    Text(Arena),
+    Binary(Arena),
+    /// The JSON values in text, they're parsed when read.
+    Json(Arena),
+    Uuid(Vec<Uuid>),
+    /// The values of a column of several types, or which couldn't be decoded.
+    Mixed(Vec<SGDBRowValue>),
+}
+
+/// A bit per value.
+#[derive(Debug, Clone, Default)]
+struct Bitmap {
+    words: Vec<u64>,
+    len: usize,
+}
+
+/// Values of variable length stored one after another.
+#[derive(Debug, Clone, Default)]
+struct Arena {
+    bytes: Vec<u8>,
+    /// The end of each value in `bytes`.
+    ends: Vec<usize>,
+}
+
+impl SGDBColumnValues {
+    fn len(&self) -> usize {
+        self.nulls.len
+    }
+
+    pub fn push(&mut self, value: SGDBRowValue) {
+        let null = matches!(value, SGDBRowValue::Null);
+
+        if let (Buffer::Nulls, false) = (&self.buffer, null) {
+            self.buffer = Buffer::of(&value);
+            for _ in 0..self.len() {
+                self.buffer.push_placeholder();
+            }
+        }
+
+        // A value of another type than the previous ones
+        if let Err(value) = self.buffer.push(value) {
+            let mut values = self.iter().collect::<Vec<_>>();
+            values.push(value);
+            self.buffer = Buffer::Mixed(values);
+        }
+
+        self.nulls.push(null);
+    }
+
+    /// The value of `row`, `row` must be less than the length of the column.
+    pub fn get(&self, row: usize) -> SGDBRowValue {
+        if self.nulls.get(row) {
+            return SGDBRowValue::Null;
+        }
+
+        match &self.buffer {
+            Buffer::Nulls => SGDBRowValue::Null,
+            Buffer::Boolean(values) => SGDBRowValue::Boolean(values.get(row)),
+            Buffer::Integer(values) => SGDBRowValue::Integer(values[row]),
+            Buffer::UInteger(values) => SGDBRowValue::UInteger(values[row]),
+            Buffer::Double(values) => SGDBRowValue::Double(values[row]),
+            Buffer::Decimal(values) => match BigDecimal::from_str(values.text(row)) {
+                Ok(value) => SGDBRowValue::Decimal(value),
+                Err(err) => SGDBRowValue::Unknown {
+                    error: format!("{}", err),
+                },
+            },
+            Buffer::DateTime(values) => SGDBRowValue::DateTime(values[row]),
+            Buffer::Timestamp(values) => SGDBRowValue::Timestamp(values[row]),
+            Buffer::Date(values) => SGDBRowValue::Date(values[row]),
+            Buffer::Time(values) => SGDBRowValue::Time(values[row]),
+            Buffer::Text(values) => SGDBRowValue::Text(values.text(row).to_string()),
+            Buffer::Binary(values) => SGDBRowValue::Binary(values.get(row).to_vec()),
+            Buffer::Json(values) => match serde_json::from_slice(values.get(row)) {
+                Ok(value) => SGDBRowValue::Json(value),
+                Err(err) => SGDBRowValue::Unknown {
+                    error: format!("{}", err),
+                },
+            },
+            Buffer::Uuid(values) => SGDBRowValue::Uuid(values[row]),
+            Buffer::Mixed(values) => values[row].clone(),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = SGDBRowValue> + '_ {
+        (0..self.len()).map(|row| self.get(row))
+    }
+
+    /// Compare the values of the rows `a` and `b` like [`SGDBRowValue::compare`], without
+    /// copying them when they have the same type.
+    pub fn compare(&self, a: usize, b: usize) -> Ordering {
+        if !self.nulls.get(a) && !self.nulls.get(b) {
+            match &self.buffer {
+                Buffer::Boolean(values) => return values.get(a).cmp(&values.get(b)),
+                Buffer::Integer(values) => return values[a].cmp(&values[b]),
+                Buffer::UInteger(values) => return values[a].cmp(&values[b]),
+                Buffer::Double(values) => return values[a].total_cmp(&values[b]),
+                Buffer::DateTime(values) => return values[a].cmp(&values[b]),
+                Buffer::Timestamp(values) => return values[a].cmp(&values[b]),
+                Buffer::Date(values) => return values[a].cmp(&values[b]),
+                Buffer::Time(values) => return values[a].cmp(&values[b]),
+                Buffer::Uuid(values) => return values[a].cmp(&values[b]),
+                // The bytes of UTF-8 texts are in the order of their characters
+                Buffer::Text(values) | Buffer::Binary(values) | Buffer::Json(values) => {
+                    return values.get(a).cmp(values.get(b))
+                }
+                Buffer::Nulls | Buffer::Decimal(_) | Buffer::Mixed(_) => {}
+            }
+        }
+
+        self.get(a).compare(&self.get(b))
+    }
+
+    /// Add the values of `other` after the ones of `self`, the buffers of the same type are
+    /// copied as a whole.
+    pub fn append(&mut self, other: SGDBColumnValues) {
+        match (&mut self.buffer, other.buffer) {
+            (Buffer::Nulls, Buffer::Nulls) => {}
+            (Buffer::Boolean(values), Buffer::Boolean(more)) => values.extend(&more),
+            (Buffer::Integer(values), Buffer::Integer(more)) => values.extend(more),
+            (Buffer::UInteger(values), Buffer::UInteger(more)) => values.extend(more),
+            (Buffer::Double(values), Buffer::Double(more)) => values.extend(more),
+            (Buffer::Decimal(values), Buffer::Decimal(more)) => values.extend(more),
+            (Buffer::DateTime(values), Buffer::DateTime(more)) => values.extend(more),
+            (Buffer::Timestamp(values), Buffer::Timestamp(more)) => values.extend(more),
+            (Buffer::Date(values), Buffer::Date(more)) => values.extend(more),
+            (Buffer::Time(values), Buffer::Time(more)) => values.extend(more),
+            (Buffer::Text(values), Buffer::Text(more)) => values.extend(more),
+            (Buffer::Binary(values), Buffer::Binary(more)) => values.extend(more),
+            (Buffer::Json(values), Buffer::Json(more)) => values.extend(more),
+            (Buffer::Uuid(values), Buffer::Uuid(more)) => values.extend(more),
+            (Buffer::Mixed(values), Buffer::Mixed(more)) => values.extend(more),
+            (_, buffer) => {
+                let other = SGDBColumnValues {
+                    buffer,
+                    nulls: other.nulls,
+                };
+                for value in other.iter() {
+                    self.push(value);
+                }
+                return;
+            }
+        }
+
+        self.nulls.extend(&other.nulls);
+    }
+
+    /// Free the memory reserved for the values to come, once they're all pushed.
+    pub fn shrink_to_fit(&mut self) {
+        match &mut self.buffer {
+            Buffer::Nulls => {}
+            Buffer::Boolean(values) => values.words.shrink_to_fit(),
+            Buffer::Integer(values) => values.shrink_to_fit(),
+            Buffer::UInteger(values) => values.shrink_to_fit(),
+            Buffer::Double(values) => values.shrink_to_fit(),
+            Buffer::DateTime(values) => values.shrink_to_fit(),
+            Buffer::Timestamp(values) => values.shrink_to_fit(),
+            Buffer::Date(values) => values.shrink_to_fit(),
+            Buffer::Time(values) => values.shrink_to_fit(),
+            Buffer::Uuid(values) => values.shrink_to_fit(),
+            Buffer::Decimal(values)
+            | Buffer::Text(values)
+            | Buffer::Binary(values)
+            | Buffer::Json(values) => {
+                values.bytes.shrink_to_fit();
+                values.ends.shrink_to_fit();
+            }
+            Buffer::Mixed(values) => values.shrink_to_fit(),
+        }
+        self.nulls.words.shrink_to_fit();
+    }
+}
+
+impl FromIterator<SGDBRowValue> for SGDBColumnValues {
+    fn from_iter<I: IntoIterator<Item = SGDBRowValue>>(values: I) -> Self {
+        let mut column = SGDBColumnValues::default();
+        for value in values {
+            column.push(value);
+        }
+        column.shrink_to_fit();
+
+        column
+    }
+}
+
+impl Buffer {
+    /// An empty buffer for the values of the type of `value`.
+    fn of(value: &SGDBRowValue) -> Self {
+        match value {
+            SGDBRowValue::Null => Buffer::Nulls,
+            SGDBRowValue::Boolean(_) => Buffer::Boolean(Bitmap::default()),
+            SGDBRowValue::Integer(_) => Buffer::Integer(vec![]),
+            SGDBRowValue::UInteger(_) => Buffer::UInteger(vec![]),
+            SGDBRowValue::Double(_) => Buffer::Double(vec![]),
+            SGDBRowValue::Decimal(_) => Buffer::Decimal(Arena::default()),
+            SGDBRowValue::DateTime(_) => Buffer::DateTime(vec![]),
+            SGDBRowValue::Timestamp(_) => Buffer::Timestamp(vec![]),
+            SGDBRowValue::Date(_) => Buffer::Date(vec![]),
+            SGDBRowValue::Time(_) => Buffer::Time(vec![]),
+            SGDBRowValue::Text(_) => Buffer::Text(Arena::default()),
+            SGDBRowValue::Binary(_) => Buffer::Binary(Arena::default()),
+            SGDBRowValue::Json(_) => Buffer::Json(Arena::default()),
+            SGDBRowValue::Uuid(_) => Buffer::Uuid(vec![]),
+            SGDBRowValue::Unknown { .. } => Buffer::Mixed(vec![]),
+        }
+    }
+
+    /// Push `value`, it's given back if it doesn't have the type of the buffer.
+    fn push(&mut self, value: SGDBRowValue) -> Result<(), SGDBRowValue> {
+        match (self, value) {
+            (Buffer::Boolean(values), SGDBRowValue::Boolean(value)) => values.push(value),
+            (Buffer::Integer(values), SGDBRowValue::Integer(value)) => values.push(value),
+            (Buffer::UInteger(values), SGDBRowValue::UInteger(value)) => values.push(value),
+            (Buffer::Double(values), SGDBRowValue::Double(value)) => values.push(value),
+            (Buffer::Decimal(values), SGDBRowValue::Decimal(value)) => {
+                values.push(value.to_string().as_bytes())
+            }
+            (Buffer::DateTime(values), SGDBRowValue::DateTime(value)) => values.push(value),
+            (Buffer::Timestamp(values), SGDBRowValue::Timestamp(value)) => values.push(value),
+            (Buffer::Date(values), SGDBRowValue::Date(value)) => values.push(value),
+            (Buffer::Time(values), SGDBRowValue::Time(value)) => values.push(value),
+            (Buffer::Text(values), SGDBRowValue::Text(value)) => values.push(value.as_bytes()),
+            (Buffer::Binary(values), SGDBRowValue::Binary(value)) => values.push(&value),
+            (Buffer::Json(values), SGDBRowValue::Json(value)) => {
+                values.push(value.to_string().as_bytes())
+            }
+            (Buffer::Uuid(values), SGDBRowValue::Uuid(value)) => values.push(value),
+            (Buffer::Mixed(values), value) => values.push(value),
+            (buffer, SGDBRowValue::Null) => buffer.push_placeholder(),
+            (_, value) => return Err(value),
+        }
+
+        Ok(())
+    }
+
+    /// Take the place of a `NULL`, the bitmap of the column tells it's not a value.
+    fn push_placeholder(&mut self) {
+        match self {
+            Buffer::Nulls => {}
+            Buffer::Boolean(values) => values.push(false),
+            Buffer::Integer(values) => values.push(0),
+            Buffer::UInteger(values) => values.push(0),
+            Buffer::Double(values) => values.push(0.),
+            Buffer::DateTime(values) => values.push(NaiveDateTime::from_timestamp(0, 0)),
+            Buffer::Timestamp(values) => values.push(UNIX_EPOCH.into()),
+            Buffer::Date(values) => values.push(NaiveDate::from_ymd(1970, 1, 1)),
+            Buffer::Time(values) => values.push(NaiveTime::from_hms(0, 0, 0)),
+            Buffer::Uuid(values) => values.push(Uuid::nil()),
+            Buffer::Decimal(values)
+            | Buffer::Text(values)
+            | Buffer::Binary(values)
+            | Buffer::Json(values) => values.push(&[]),
+            Buffer::Mixed(values) => values.push(SGDBRowValue::Null),
+        }
+    }
+}
+
+impl Bitmap {
+    fn push(&mut self, bit: bool) {
+        if self.len % 64 == 0 {
+            self.words.push(0);
+        }
+        if bit {
+            self.words[self.len / 64] |= 1 << (self.len % 64);
+        }
+        self.len += 1;
+    }
+
+    fn get(&self, index: usize) -> bool {
+        self.words[index / 64] >> (index % 64) & 1 == 1
+    }
+
+    fn extend(&mut self, other: &Bitmap) {
+        // The words are copied as a whole when the bits are aligned
+        if self.len % 64 == 0 {
+            self.words.extend(&other.words);
+            self.len += other.len;
+            return;
+        }
+
+        for index in 0..other.len {
+            self.push(other.get(index));
+        }
+    }
+}
+
+impl Arena {
+    fn push(&mut self, value: &[u8]) {
+        self.bytes.extend_from_slice(value);
+        self.ends.push(self.bytes.len());
+    }
+
+    fn get(&self, index: usize) -> &[u8] {
+        let start = index.checked_sub(1).map_or(0, |previous| self.ends[previous]);
+        &self.bytes[start..self.ends[index]]
+    }
+
+    /// The value at `index` of an arena of texts.
+    fn text(&self, index: usize) -> &str {
+        std::str::from_utf8(self.get(index)).unwrap_or_default()
+    }
+
+    fn extend(&mut self, other: Arena) {
+        let base = self.bytes.len();
+        self.bytes.extend(other.bytes);
+        self.ends.extend(other.ends.into_iter().map(|end| base + end));
+    }
+}
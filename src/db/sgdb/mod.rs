@@ -1,24 +1,44 @@
+mod column;
 mod mysql;
+mod postgres;
+mod sqlite;
 
+pub use column::SGDBColumnValues;
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use sqlx::types::BigDecimal;
+use sqlx::ConnectOptions;
+
+use crate::sql;
 
 pub struct Connection {
     kind: SGDBKind,
     uri: String,
     schema: String,
+    /// The time zone of the sessions, UTC if not set. SQLite has none.
+    time_zone: Option<Tz>,
 }
 
 impl Connection {
-    pub fn new(kind: SGDBKind, uri: String, schema: String) -> Self {
-        Connection { kind, uri, schema }
+    pub fn new(kind: SGDBKind, uri: String, schema: String, time_zone: Option<Tz>) -> Self {
+        Connection {
+            kind,
+            uri,
+            schema,
+            time_zone,
+        }
     }
 
     pub fn schema(&self) -> &str {
@@ -29,18 +49,47 @@ impl Connection {
         Ok(match self.kind {
             SGDBKind::Mysql => {
                 let sgdb =
-                    mysql::MySQL::connect(&self.uri, &self.schema)
+                    mysql::MySQL::connect(&self.uri, &self.schema, self.time_zone)
                     .await?;
 
                 Box::new(sgdb) as Box<dyn SGDB>
             }
-            SGDBKind::Postgres => todo!(),
-            SGDBKind::Sqlite => todo!(),
+            SGDBKind::Postgres => {
+                let sgdb = postgres::Postgres::connect(&self.uri, &self.schema, self.time_zone).await?;
+
+                Box::new(sgdb) as Box<dyn SGDB>
+            }
+            SGDBKind::Sqlite => {
+                let sgdb = sqlite::Sqlite::connect(&self.uri).await?;
+
+                Box::new(sgdb) as Box<dyn SGDB>
+            }
         })
     }
 }
 
 
+/// How long the pools wait for a connection before giving up.
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Open a single connection to tell why the server can't be reached, the pools retry until
+/// their timeout and hide it.
+async fn probe<O>(options: &O) -> Result<O::Connection>
+where
+    O: ConnectOptions,
+    O::Connection: Sized,
+{
+    tokio::time::timeout(CONNECT_TIMEOUT, options.connect())
+        .await
+        .map_err(|_| {
+            anyhow!(
+                "The server didn't answer in {} seconds",
+                CONNECT_TIMEOUT.as_secs()
+            )
+        })?
+        .map_err(Into::into)
+}
+
 #[derive(Debug)]
 pub struct SGDBTable {
     pub schema: String,
@@ -53,14 +102,207 @@ pub struct SGDBTable {
     // pub create_time: chrono::NaiveDateTime,
 }
 
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum SGDBColumnKey {
+    None,
+    Primary,
+    Unique,
+    Multiple,
+}
+
+impl SGDBColumnKey {
+    /// Derive the key of a column from the indexes of its table, following the MySQL
+    /// `COLUMN_KEY` semantics: all the columns of the primary key are part of it, only the first
+    /// column of the other indexes is considered.
+    pub fn from_indexes(column: &str, indexes: &[SGDBTableIndex]) -> Self {
+        let primary = indexes
+            .iter()
+            .any(|index| index.primary && index.columns.iter().any(|col| col == column));
+        if primary {
+            return SGDBColumnKey::Primary;
+        }
+
+        let leading = indexes
+            .iter()
+            .filter(|index| index.columns.first().map(String::as_str) == Some(column));
+
+        let mut key = SGDBColumnKey::None;
+        for index in leading {
+            if index.unique && index.columns.len() == 1 {
+                key = SGDBColumnKey::Unique;
+            } else if key == SGDBColumnKey::None {
+                key = SGDBColumnKey::Multiple;
+            }
+        }
+
+        key
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SGDBTableColumn {
+    pub name: String,
+    /// The full type as declared, e.g. `varchar(255)` or `int(10) unsigned`.
+    pub column_type: String,
+    pub r#type: SGDBColumnType,
+    pub nullable: bool,
+    pub default: Option<String>,
+    pub key: SGDBColumnKey,
+    pub auto_increment: bool,
+    pub extra: String,
+    pub comment: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SGDBTableIndex {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+    pub primary: bool,
+    pub index_type: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SGDBTableForeignKey {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub referenced_schema: String,
+    pub referenced_table: String,
+    pub referenced_columns: Vec<String>,
+    pub on_update: String,
+    pub on_delete: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SGDBTableTrigger {
+    pub name: String,
+    /// `BEFORE`, `AFTER` or `INSTEAD OF`.
+    pub timing: String,
+    /// `INSERT`, `UPDATE`, `DELETE`, or several of them for Postgres.
+    pub event: String,
+    pub statement: String,
+}
+
 #[async_trait]
 pub trait SGDB: Send + Sync {
-    async fn fetch_all(&self, query: &str, params: Option<Vec<String>>) -> Result<SGDBFetchResult>;
+    /// A connection of the pool to run the statements of a script, see [`fetch_statements`].
+    async fn session(&self) -> Result<Box<dyn SGDBSession>>;
 
     async fn list_tables(&self) -> Result<Vec<SGDBTable>>;
+
+    async fn list_columns(&self, table: &str) -> Result<Vec<SGDBTableColumn>>;
+
+    async fn list_indexes(&self, table: &str) -> Result<Vec<SGDBTableIndex>>;
+
+    async fn list_foreign_keys(&self, table: &str) -> Result<Vec<SGDBTableForeignKey>>;
+
+    async fn list_triggers(&self, table: &str) -> Result<Vec<SGDBTableTrigger>>;
+
+    /// Execute the statements in a single transaction and return the number of affected rows.
+    async fn execute(&self, statements: Vec<SGDBStatement>) -> Result<u64>;
+
+    /// The statement creating the table or view, like `SHOW CREATE TABLE`.
+    async fn table_ddl(&self, table: &str) -> Result<String>;
+
+    /// Open a transaction on a dedicated connection.
+    async fn begin(&self) -> Result<Box<dyn SGDBTransaction>>;
+
+    /// Cancel the queries of the request `seq` being fetched, or all of them, they end with an
+    /// error.
+    async fn cancel(&self, seq: Option<u64>) -> Result<()>;
+
+    /// Check that the database can be reached, the pool connects again if needed.
+    async fn ping(&self) -> Result<()>;
+}
+
+tokio::task_local! {
+    /// The sequence number of the request run by the task, see [`RunningQueries::track`].
+    pub static REQUEST_SEQ: u64;
+}
+
+/// The server side ids of the connections running a query, with the request which runs it,
+/// shared by a pool and its transactions to cancel them.
+#[derive(Default, Clone)]
+pub struct RunningQueries(Arc<Mutex<HashMap<u64, Option<u64>>>>);
+
+impl RunningQueries {
+    /// Track the connection `id` for the request of the task until the returned guard is dropped.
+    pub fn track(&self, id: u64) -> RunningQuery {
+        let seq = REQUEST_SEQ.try_with(|seq| *seq).ok();
+        self.0.lock().unwrap().insert(id, seq);
+
+        RunningQuery {
+            queries: self.clone(),
+            id,
+        }
+    }
+
+    /// The connections running a query of the request `seq`, or all of them.
+    pub fn ids(&self, seq: Option<u64>) -> Vec<u64> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, owner)| seq.is_none() || **owner == seq)
+            .map(|(id, _)| *id)
+            .collect()
+    }
 }
 
-#[derive(Default, Serialize, Deserialize, Clone, Copy)]
+pub struct RunningQuery {
+    queries: RunningQueries,
+    id: u64,
+}
+
+impl Drop for RunningQuery {
+    fn drop(&mut self) {
+        self.queries.0.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// A connection running statements one after the other, the state of the session (variables,
+/// temporary tables..) is kept between them.
+#[async_trait]
+pub trait SGDBSession: Send {
+    /// Run a single statement, a procedure call may return several result sets.
+    async fn fetch_statement(
+        &mut self,
+        statement: &str,
+        params: Option<Vec<String>>,
+    ) -> Result<SGDBStatementOutput>;
+
+    /// Run a single statement and pass the rows of its first result set to `sink` as they're
+    /// received rather than keeping them, returns the number of rows.
+    async fn stream_statement(
+        &mut self,
+        statement: &str,
+        sink: &mut dyn SGDBRowSink,
+    ) -> Result<u64>;
+}
+
+/// Receives the rows of a streamed statement, see [`SGDBSession::stream_statement`].
+pub trait SGDBRowSink: Send + std::fmt::Debug {
+    /// The columns of the result set, before its first row.
+    fn columns(&mut self, columns: &[SGDBColumn]) -> Result<()>;
+
+    fn row(&mut self, row: Vec<SGDBRowValue>) -> Result<()>;
+
+    /// Called once every row was received, even if there was none.
+    fn finish(&mut self) -> Result<()>;
+}
+
+/// A transaction open across calls, it's rolled back if dropped without being committed.
+#[async_trait]
+pub trait SGDBTransaction: SGDBSession {
+    /// Execute the statements in a savepoint and return the number of affected rows.
+    async fn execute(&mut self, statements: Vec<SGDBStatement>) -> Result<u64>;
+
+    async fn commit(self: Box<Self>) -> Result<()>;
+
+    async fn rollback(self: Box<Self>) -> Result<()>;
+}
+
+#[derive(Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
 pub enum SGDBKind {
     #[default]
     Mysql,
@@ -68,7 +310,134 @@ pub enum SGDBKind {
     Sqlite,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+impl SGDBKind {
+    pub fn all() -> impl ExactSizeIterator<Item = Self> {
+        [Self::Mysql, Self::Postgres, Self::Sqlite].iter().copied()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Mysql => "MySQL",
+            Self::Postgres => "PostgreSQL",
+            Self::Sqlite => "SQLite",
+        }
+    }
+
+    /// Quote a table or column name with the identifier quote of the dialect.
+    pub fn quote_identifier(&self, ident: &str) -> String {
+        match self {
+            Self::Mysql => format!("`{}`", ident.replace('`', "``")),
+            Self::Postgres | Self::Sqlite => format!("\"{}\"", ident.replace('"', "\"\"")),
+        }
+    }
+
+    /// Quote a possibly schema qualified table name, e.g. `schema.table`.
+    pub fn quote_path(&self, path: &str) -> String {
+        path.split('.')
+            .map(|ident| self.quote_identifier(ident))
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    /// The SQL literal of a value, e.g. for the statements of an export.
+    pub fn literal(&self, value: &SGDBRowValue) -> String {
+        match (self, value) {
+            (_, SGDBRowValue::Null | SGDBRowValue::Unknown { .. }) => "NULL".to_string(),
+            (Self::Mysql, SGDBRowValue::Text(text)) => {
+                format!("'{}'", text.replace('\\', "\\\\").replace('\'', "''"))
+            }
+            // The E'' syntax makes the escaping independent of standard_conforming_strings
+            (Self::Postgres, SGDBRowValue::Text(text)) => {
+                format!("E'{}'", text.replace('\\', "\\\\").replace('\'', "''"))
+            }
+            (Self::Sqlite, SGDBRowValue::Text(text)) => format!("'{}'", text.replace('\'', "''")),
+            (Self::Sqlite, SGDBRowValue::Boolean(value)) => format!("{}", *value as u8),
+            (_, SGDBRowValue::Boolean(value)) => {
+                if *value { "TRUE" } else { "FALSE" }.to_string()
+            }
+            (_, SGDBRowValue::Integer(value)) => format!("{}", value),
+            (_, SGDBRowValue::UInteger(value)) => format!("{}", value),
+            (_, SGDBRowValue::Double(value)) if value.is_finite() => format!("{:?}", value),
+            (Self::Postgres, SGDBRowValue::Double(value)) => {
+                format!("'{}'::float8", non_finite_name(*value))
+            }
+            // SQLite reads a literal out of the range of a double as an infinity
+            (Self::Sqlite, SGDBRowValue::Double(value)) if value.is_infinite() => {
+                if *value > 0. { "9e999" } else { "-9e999" }.to_string()
+            }
+            // The value is named in a comment, see `has_literal`
+            (_, SGDBRowValue::Double(value)) => format!("NULL /* {} */", non_finite_name(*value)),
+            (_, SGDBRowValue::Decimal(value)) => format!("{}", value),
+            (_, SGDBRowValue::DateTime(value)) => {
+                format!("'{}'", value.format("%Y-%m-%d %H:%M:%S%.f"))
+            }
+            (Self::Postgres, SGDBRowValue::Timestamp(value)) => {
+                format!("'{}'", value.format("%Y-%m-%d %H:%M:%S%.f+00"))
+            }
+            // A MySQL `TIMESTAMP` literal is in the time zone of the session
+            (Self::Mysql, SGDBRowValue::Timestamp(value)) => format!(
+                "CONVERT_TZ('{}', '+00:00', @@session.time_zone)",
+                value.format("%Y-%m-%d %H:%M:%S%.f")
+            ),
+            (Self::Sqlite, SGDBRowValue::Timestamp(value)) => {
+                format!("'{}'", value.format("%Y-%m-%d %H:%M:%S%.f"))
+            }
+            (_, SGDBRowValue::Date(value)) => format!("'{}'", value.format("%Y-%m-%d")),
+            (_, SGDBRowValue::Time(value)) => format!("'{}'", value.format("%H:%M:%S%.f")),
+            (_, SGDBRowValue::Json(_) | SGDBRowValue::Uuid(_)) => {
+                self.literal(&SGDBRowValue::Text(value.text().unwrap_or_default()))
+            }
+            (Self::Postgres, SGDBRowValue::Binary(bytes)) => {
+                format!("decode('{}', 'hex')", hex(bytes))
+            }
+            (_, SGDBRowValue::Binary(bytes)) => format!("X'{}'", hex(bytes)),
+        }
+    }
+
+    /// Whether [`SGDBKind::literal`] writes `value` as it is. MySQL has no NaN nor infinities and
+    /// SQLite stores NaN as `NULL`, these values are written as `NULL /* NaN */`.
+    pub fn has_literal(&self, value: &SGDBRowValue) -> bool {
+        match (self, value) {
+            (Self::Mysql, SGDBRowValue::Double(value)) => value.is_finite(),
+            (Self::Sqlite, SGDBRowValue::Double(value)) => !value.is_nan(),
+            _ => true,
+        }
+    }
+
+    /// `SELECT * FROM table ORDER BY <order>`, limited to the page of `(limit, offset)` if given.
+    pub fn select_rows(&self, table: &str, order: &[String], page: Option<(usize, u64)>) -> String {
+        let mut query = format!("SELECT * FROM {}", self.quote_path(table));
+
+        if !order.is_empty() {
+            let columns = order
+                .iter()
+                .map(|col| self.quote_identifier(col))
+                .collect::<Vec<_>>();
+            query += &format!(" ORDER BY {}", columns.join(", "));
+        }
+
+        if let Some((limit, offset)) = page {
+            query += &format!(" LIMIT {} OFFSET {}", limit, offset);
+        }
+
+        query
+    }
+
+    /// `SELECT COUNT(*) FROM table`.
+    pub fn count_rows(&self, table: &str) -> String {
+        format!("SELECT COUNT(*) FROM {}", self.quote_path(table))
+    }
+
+    /// The bind parameter placeholder for the 1-based `index`.
+    pub fn placeholder(&self, index: usize) -> String {
+        match self {
+            Self::Mysql | Self::Sqlite => "?".to_string(),
+            Self::Postgres => format!("${}", index),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SGDBColumn {
     name: String,
     ordinal: usize,
@@ -102,12 +471,40 @@ pub enum SGDBColumnType {
     UInteger,
     Double,
     Decimal,
+    /// A date and time without a time zone, a wall clock time.
     DateTime,
+    /// An instant, a date and time with a time zone.
+    Timestamp,
+    Date,
+    Time,
     Binary,
+    Json,
+    Uuid,
     Unknown,
 }
 
-#[derive(Debug)]
+impl SGDBColumnType {
+    /// A neutral value of the type, used to initialize new values.
+    pub fn default_value(&self) -> SGDBRowValue {
+        match self {
+            Self::Text | Self::Unknown => SGDBRowValue::Text(String::new()),
+            Self::Boolean => SGDBRowValue::Boolean(false),
+            Self::Integer => SGDBRowValue::Integer(0),
+            Self::UInteger => SGDBRowValue::UInteger(0),
+            Self::Double => SGDBRowValue::Double(0.),
+            Self::Decimal => SGDBRowValue::Decimal(0.into()),
+            Self::DateTime => SGDBRowValue::DateTime(Local::now().naive_local()),
+            Self::Timestamp => SGDBRowValue::Timestamp(Utc::now()),
+            Self::Date => SGDBRowValue::Date(Utc::today().naive_utc()),
+            Self::Time => SGDBRowValue::Time(NaiveTime::from_hms(0, 0, 0)),
+            Self::Binary => SGDBRowValue::Binary(vec![]),
+            Self::Json => SGDBRowValue::Json(serde_json::Value::Null),
+            Self::Uuid => SGDBRowValue::Uuid(sqlx::types::Uuid::nil()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum SGDBRowValue {
     Text(String),
     Boolean(bool),
@@ -115,14 +512,507 @@ pub enum SGDBRowValue {
     UInteger(u64),
     Double(f64),
     Decimal(BigDecimal),
-    DateTime(chrono::DateTime<Utc>),
+    DateTime(NaiveDateTime),
+    Timestamp(chrono::DateTime<Utc>),
+    Date(NaiveDate),
+    Time(NaiveTime),
     Binary(Vec<u8>),
+    Json(serde_json::Value),
+    Uuid(sqlx::types::Uuid),
     Null,
     Unknown { error: String },
 }
 
+impl SGDBRowValue {
+    /// The text of a value, `None` for `NULL`. A value which couldn't be decoded is `NULL` too.
+    ///
+    /// The timestamps are in RFC 3339 in UTC, the other dates and times like
+    /// `2022-07-31 12:00:00`. The binary values are in hexadecimal, prefixed by `0x`, the JSON
+    /// values are compact.
+    pub fn text(&self) -> Option<String> {
+        Some(match self {
+            Self::Text(text) => text.clone(),
+            Self::Boolean(value) => format!("{}", value),
+            Self::Integer(value) => format!("{}", value),
+            Self::UInteger(value) => format!("{}", value),
+            Self::Double(value) => format!("{:?}", value),
+            Self::Decimal(value) => format!("{}", value),
+            Self::DateTime(value) => format!("{}", value.format("%Y-%m-%d %H:%M:%S%.f")),
+            Self::Timestamp(value) => value.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            Self::Date(value) => format!("{}", value.format("%Y-%m-%d")),
+            Self::Time(value) => format!("{}", value.format("%H:%M:%S%.f")),
+            Self::Binary(bytes) => format!("0x{}", hex(bytes)),
+            Self::Json(value) => value.to_string(),
+            Self::Uuid(value) => format!("{}", value.to_hyphenated()),
+            Self::Null | Self::Unknown { .. } => return None,
+        })
+    }
+
+    /// Compare two values of a column, `NULL` first. The numbers are compared by value whatever
+    /// their type, the values of other different types by the order of their type.
+    pub fn compare(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Text(a), Self::Text(b)) => a.cmp(b),
+            (Self::Boolean(a), Self::Boolean(b)) => a.cmp(b),
+            (Self::Integer(a), Self::Integer(b)) => a.cmp(b),
+            (Self::UInteger(a), Self::UInteger(b)) => a.cmp(b),
+            (Self::Double(a), Self::Double(b)) => a.total_cmp(b),
+            (Self::Decimal(a), Self::Decimal(b)) => a.cmp(b),
+            (Self::DateTime(a), Self::DateTime(b)) => a.cmp(b),
+            (Self::Timestamp(a), Self::Timestamp(b)) => a.cmp(b),
+            (Self::Date(a), Self::Date(b)) => a.cmp(b),
+            (Self::Time(a), Self::Time(b)) => a.cmp(b),
+            (Self::Binary(a), Self::Binary(b)) => a.cmp(b),
+            (Self::Json(a), Self::Json(b)) => a.to_string().cmp(&b.to_string()),
+            (Self::Uuid(a), Self::Uuid(b)) => a.cmp(b),
+            // The doubles may not be finite, the other numbers are compared exactly
+            (Self::Double(_), _) | (_, Self::Double(_)) => match (self.float(), other.float()) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                _ => self.rank().cmp(&other.rank()),
+            },
+            _ => match (self.decimal(), other.decimal()) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                _ => self.rank().cmp(&other.rank()),
+            },
+        }
+    }
+
+    /// The value of the type of `self` written in `text`, `None` if it can't be parsed. The
+    /// numbers are parsed as decimals whatever their type.
+    ///
+    /// The timestamps without an offset are in UTC, the binary values in hexadecimal.
+    pub fn parse_like(&self, text: &str) -> Option<Self> {
+        let text = text.trim();
+
+        Some(match self {
+            Self::Text(_) => Self::Text(text.to_string()),
+            Self::Boolean(_) => match text.to_lowercase().as_str() {
+                "true" | "t" | "1" => Self::Boolean(true),
+                "false" | "f" | "0" => Self::Boolean(false),
+                _ => return None,
+            },
+            Self::Integer(_) | Self::UInteger(_) | Self::Double(_) | Self::Decimal(_) => {
+                Self::Decimal(text.parse().ok()?)
+            }
+            Self::DateTime(_) => Self::DateTime(parse_naive_datetime(text)?),
+            Self::Timestamp(_) => {
+                let timestamp = chrono::DateTime::parse_from_rfc3339(text)
+                    .map(|timestamp| timestamp.with_timezone(&Utc))
+                    .ok()
+                    .or_else(|| Some(Utc.from_utc_datetime(&parse_naive_datetime(text)?)))?;
+                Self::Timestamp(timestamp)
+            }
+            Self::Date(_) => Self::Date(NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?),
+            Self::Time(_) => Self::Time(NaiveTime::parse_from_str(text, "%H:%M:%S%.f").ok()?),
+            Self::Json(_) => Self::Json(serde_json::from_str(text).ok()?),
+            Self::Uuid(_) => Self::Uuid(text.parse().ok()?),
+            Self::Binary(_) => {
+                let digits = text.strip_prefix("0x").unwrap_or(text);
+                if digits.len() % 2 != 0 || !digits.is_ascii() {
+                    return None;
+                }
+
+                let bytes = (0..digits.len())
+                    .step_by(2)
+                    .map(|index| u8::from_str_radix(&digits[index..index + 2], 16).ok())
+                    .collect::<Option<_>>()?;
+                Self::Binary(bytes)
+            }
+            Self::Null | Self::Unknown { .. } => return None,
+        })
+    }
+
+    fn decimal(&self) -> Option<BigDecimal> {
+        match self {
+            Self::Integer(value) => Some(BigDecimal::from(*value)),
+            Self::UInteger(value) => Some(BigDecimal::from(*value)),
+            Self::Decimal(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    fn float(&self) -> Option<f64> {
+        match self {
+            Self::Double(value) => Some(*value),
+            Self::Integer(value) => Some(*value as f64),
+            Self::UInteger(value) => Some(*value as f64),
+            Self::Decimal(value) => value.to_string().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// The order of the types of values, the numbers have the same.
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Null | Self::Unknown { .. } => 0,
+            Self::Boolean(_) => 1,
+            Self::Integer(_) | Self::UInteger(_) | Self::Double(_) | Self::Decimal(_) => 2,
+            Self::Text(_) | Self::Uuid(_) => 3,
+            Self::Date(_) => 4,
+            Self::DateTime(_) => 5,
+            Self::Timestamp(_) => 6,
+            Self::Time(_) => 7,
+            Self::Binary(_) => 8,
+            Self::Json(_) => 9,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct SGDBFetchResult {
-    pub data: IndexMap<SGDBColumn, Vec<SGDBRowValue>>,
+    pub data: IndexMap<SGDBColumn, SGDBColumnValues>,
     pub num_rows: usize,
 }
+
+/// What a statement returned.
+#[derive(Debug, Default)]
+pub struct SGDBStatementOutput {
+    pub result_sets: Vec<SGDBFetchResult>,
+    pub rows_affected: u64,
+    /// The id generated by an insert, reported by MySQL and SQLite.
+    pub last_insert_id: Option<u64>,
+}
+
+/// The result of a statement of a script.
+#[derive(Debug)]
+pub struct SGDBStatementResult {
+    pub query: String,
+    /// The byte offset of the statement in the script.
+    pub start: usize,
+    pub duration: Duration,
+    pub output: SGDBStatementOutput,
+}
+
+/// The results of the statements of a script, run in order until one fails.
+#[derive(Debug, Default)]
+pub struct SGDBScriptResult {
+    pub statements: Vec<SGDBStatementResult>,
+    /// The error of the statement which stopped the script, its position is in the script.
+    pub error: Option<SGDBError>,
+}
+
+/// Run `statements` in order on `session`, until one fails. The `params` are bound to the
+/// first statement.
+pub async fn fetch_statements<S>(
+    session: &mut S,
+    statements: Vec<sql::Statement<'_>>,
+    mut params: Option<Vec<String>>,
+) -> SGDBScriptResult
+where
+    S: SGDBSession + ?Sized,
+{
+    let mut result = SGDBScriptResult::default();
+
+    for statement in statements {
+        let started = Instant::now();
+
+        match session.fetch_statement(statement.text, params.take()).await {
+            Ok(output) => result.statements.push(SGDBStatementResult {
+                query: statement.text.to_string(),
+                start: statement.start,
+                duration: started.elapsed(),
+                output,
+            }),
+            Err(err) => {
+                let mut err = SGDBError::new(&err, statement.text);
+                err.position = err.position.map(|position| statement.start + position);
+
+                result.error = Some(err);
+                break;
+            }
+        }
+    }
+
+    result
+}
+
+/// An error returned for a query, with the details reported by the database.
+#[derive(Debug, Clone, Default)]
+pub struct SGDBError {
+    pub message: String,
+    /// The error code of the backend, like `1064` for MySQL or the extended result code for
+    /// SQLite.
+    pub code: Option<String>,
+    /// The SQLSTATE code, reported by MySQL and Postgres.
+    pub sqlstate: Option<String>,
+    /// The byte offset of the error in the query, when the database reports one.
+    pub position: Option<usize>,
+    /// The error comes from the connection to the database rather than from the query.
+    pub connection: bool,
+}
+
+impl SGDBError {
+    /// The details of `err`, returned for `query`.
+    pub fn new(err: &anyhow::Error, query: &str) -> Self {
+        let database_error = err.chain().find_map(|cause| match cause.downcast_ref() {
+            Some(sqlx::Error::Database(err)) => Some(err),
+            _ => None,
+        });
+
+        let err = match database_error {
+            Some(err) => err,
+            None => {
+                return SGDBError {
+                    message: format!("{}", err),
+                    connection: crate::db::is_connection_error(err),
+                    ..Default::default()
+                }
+            }
+        };
+
+        postgres::error_details(&**err, query)
+            .or_else(|| mysql::error_details(&**err, query))
+            .or_else(|| sqlite::error_details(&**err, query))
+            .unwrap_or_else(|| SGDBError {
+                message: err.message().to_string(),
+                code: err.code().map(|code| code.into_owned()),
+                ..Default::default()
+            })
+    }
+}
+
+impl From<String> for SGDBError {
+    fn from(message: String) -> Self {
+        SGDBError {
+            message,
+            ..Default::default()
+        }
+    }
+}
+
+impl std::fmt::Display for SGDBError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for SGDBError {}
+
+/// A date and time like `2022-07-31 12:00:00` or in ISO 8601, a date alone is at midnight.
+fn parse_naive_datetime(text: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f"))
+        .or_else(|_| NaiveDate::parse_from_str(text, "%Y-%m-%d").map(|date| date.and_hms(0, 0, 0)))
+        .ok()
+}
+
+/// The lowercase hexadecimal digits of `bytes`.
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// `NaN`, `Infinity` or `-Infinity`, as Postgres spells them.
+fn non_finite_name(value: f64) -> &'static str {
+    if value.is_nan() {
+        "NaN"
+    } else if value > 0. {
+        "Infinity"
+    } else {
+        "-Infinity"
+    }
+}
+
+/// A parameterized statement, `params` are bound in order to the placeholders of `query`.
+#[derive(Debug, Clone)]
+pub struct SGDBStatement {
+    pub query: String,
+    pub params: Vec<SGDBRowValue>,
+}
+
+impl SGDBStatement {
+    /// `UPDATE table SET <set> WHERE <key>`, the parameters are typed after the `columns` of the
+    /// table.
+    pub fn update(
+        kind: SGDBKind,
+        table: &str,
+        columns: &[SGDBTableColumn],
+        set: Vec<(String, SGDBRowValue)>,
+        key: Vec<(String, SGDBRowValue)>,
+    ) -> Self {
+        let mut params = Vec::new();
+
+        let assignments = set
+            .into_iter()
+            .map(|(col, value)| {
+                let value = Self::value(kind, columns, &col, value, &mut params);
+                format!("{} = {}", kind.quote_identifier(&col), value)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let conditions = Self::conditions(kind, columns, key, &mut params);
+
+        Self {
+            query: format!(
+                "UPDATE {} SET {} WHERE {}",
+                kind.quote_path(table),
+                assignments,
+                conditions
+            ),
+            params,
+        }
+    }
+
+    /// `INSERT INTO table (<columns>) VALUES (<values>)`, the columns left out take their
+    /// default value.
+    pub fn insert(
+        kind: SGDBKind,
+        table: &str,
+        columns: &[SGDBTableColumn],
+        values: Vec<(String, SGDBRowValue)>,
+    ) -> Self {
+        let table = kind.quote_path(table);
+
+        if values.is_empty() {
+            let query = match kind {
+                SGDBKind::Mysql => format!("INSERT INTO {} () VALUES ()", table),
+                SGDBKind::Postgres | SGDBKind::Sqlite => {
+                    format!("INSERT INTO {} DEFAULT VALUES", table)
+                }
+            };
+
+            return Self {
+                query,
+                params: vec![],
+            };
+        }
+
+        let mut params = Vec::new();
+        let (columns, values): (Vec<_>, Vec<_>) = values
+            .into_iter()
+            .map(|(col, value)| {
+                let value = Self::value(kind, columns, &col, value, &mut params);
+                (kind.quote_identifier(&col), value)
+            })
+            .unzip();
+
+        Self {
+            query: format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                table,
+                columns.join(", "),
+                values.join(", ")
+            ),
+            params,
+        }
+    }
+
+    /// `DELETE FROM table WHERE <key>`.
+    pub fn delete(
+        kind: SGDBKind,
+        table: &str,
+        columns: &[SGDBTableColumn],
+        key: Vec<(String, SGDBRowValue)>,
+    ) -> Self {
+        let mut params = Vec::new();
+        let conditions = Self::conditions(kind, columns, key, &mut params);
+
+        Self {
+            query: format!(
+                "DELETE FROM {} WHERE {}",
+                kind.quote_path(table),
+                conditions
+            ),
+            params,
+        }
+    }
+
+    fn conditions(
+        kind: SGDBKind,
+        columns: &[SGDBTableColumn],
+        key: Vec<(String, SGDBRowValue)>,
+        params: &mut Vec<SGDBRowValue>,
+    ) -> String {
+        key.into_iter()
+            .map(|(col, value)| {
+                if let SGDBRowValue::Null = value {
+                    format!("{} IS NULL", kind.quote_identifier(&col))
+                } else {
+                    let value = Self::value(kind, columns, &col, value, params);
+                    format!("{} = {}", kind.quote_identifier(&col), value)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" AND ")
+    }
+
+    /// The SQL of the value of the column `col`, a placeholder bound to it unless it's `NULL`,
+    /// which is inlined to avoid having to type the parameter.
+    ///
+    /// Postgres assigns neither a `text` parameter to an enum column, which is fetched as text,
+    /// nor a `jsonb` one to a `json` column: text and JSON parameters are cast to the type of
+    /// the column.
+    fn value(
+        kind: SGDBKind,
+        columns: &[SGDBTableColumn],
+        col: &str,
+        value: SGDBRowValue,
+        params: &mut Vec<SGDBRowValue>,
+    ) -> String {
+        if let SGDBRowValue::Null = value {
+            return "NULL".to_string();
+        }
+
+        let column_type = columns
+            .iter()
+            .find(|column| column.name == col)
+            .map(|column| column.column_type.as_str());
+        let cast = match (kind, &value, column_type) {
+            (SGDBKind::Postgres, SGDBRowValue::Text(_) | SGDBRowValue::Json(_), Some(column_type)) => {
+                format!("::{}", column_type)
+            }
+            _ => String::new(),
+        };
+
+        params.push(value);
+        format!("{}{}", kind.placeholder(params.len()), cast)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, column_type: &str, r#type: SGDBColumnType) -> SGDBTableColumn {
+        SGDBTableColumn {
+            name: name.to_string(),
+            column_type: column_type.to_string(),
+            r#type,
+            nullable: true,
+            default: None,
+            key: SGDBColumnKey::None,
+            auto_increment: false,
+            extra: String::new(),
+            comment: String::new(),
+        }
+    }
+
+    #[test]
+    fn update_binds_the_values() {
+        let columns = [
+            column("id", "integer", SGDBColumnType::Integer),
+            column("mood", "mood", SGDBColumnType::Text),
+            column("data", "jsonb", SGDBColumnType::Json),
+        ];
+        let set = vec![
+            ("mood".to_string(), SGDBRowValue::Text("it's".to_string())),
+            ("data".to_string(), SGDBRowValue::Json(serde_json::json!({ "a": 1 }))),
+            ("note".to_string(), SGDBRowValue::Null),
+        ];
+        let key = vec![("id".to_string(), SGDBRowValue::Integer(1))];
+
+        let statement = SGDBStatement::update(SGDBKind::Postgres, "t", &columns, set.clone(), key.clone());
+        assert_eq!(
+            statement.query,
+            r#"UPDATE "t" SET "mood" = $1::mood, "data" = $2::jsonb, "note" = NULL WHERE "id" = $3"#
+        );
+        assert_eq!(statement.params.len(), 3);
+
+        let statement = SGDBStatement::update(SGDBKind::Mysql, "t", &columns, set, key);
+        assert_eq!(
+            statement.query,
+            "UPDATE `t` SET `mood` = ?, `data` = ?, `note` = NULL WHERE `id` = ?"
+        );
+        assert_eq!(statement.params.len(), 3);
+    }
+}
@@ -1,25 +1,89 @@
-use anyhow::{anyhow, bail, Result};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, bail, Context, Result};
 use async_trait::async_trait;
+use chrono::{NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
 
+use futures_util::TryStreamExt;
 use indexmap::IndexMap;
 use log::info;
 use sqlx::{
-    mysql::{MySqlColumn, MySqlRow, MySqlValueRef},
-    Column, Decode, MySql, MySqlPool, Row, Type, TypeInfo, ValueRef,
+    mysql::{
+        MySqlArguments, MySqlColumn, MySqlConnectOptions, MySqlConnection, MySqlDatabaseError,
+        MySqlPoolOptions, MySqlRow, MySqlValueRef,
+    },
+    error::DatabaseError,
+    pool::PoolConnection,
+    query::Query,
+    Column, Connection, Decode, Either, Executor, MySql, MySqlPool, Row,
+    Transaction, Type, TypeInfo, ValueRef,
 };
 
-use super::{SGDBColumn, SGDBColumnType, SGDBFetchResult, SGDBRowValue, SGDBTable, SGDB};
+use super::{
+    probe, RunningQueries, CONNECT_TIMEOUT, SGDBColumn, SGDBColumnKey, SGDBColumnType,
+    SGDBColumnValues, SGDBError, SGDBFetchResult, SGDBRowSink, SGDBRowValue, SGDBSession,
+    SGDBStatementOutput, SGDBTable, SGDBKind, SGDBStatement, SGDBTableColumn, SGDBTableForeignKey,
+    SGDBTableIndex, SGDBTableTrigger, SGDBTransaction, SGDB,
+};
 
 pub struct MySQL {
     pool: MySqlPool,
-    schema: String
+    schema: String,
+    /// The time zone of the sessions, the `TIMESTAMP` values are sent and received in it.
+    time_zone: Tz,
+    running: RunningQueries,
+    idle: IdleSessions,
 }
 
 impl MySQL {
-    pub async fn connect(uri: &str,  schema: impl Into<String>) -> Result<Self> {
+    pub async fn connect(
+        uri: &str,
+        schema: impl Into<String>,
+        time_zone: Option<Tz>,
+    ) -> Result<Self> {
         let schema = schema.into();
-        let pool = MySqlPool::connect(&format!("{}/{}", uri, schema)).await?;
-        Ok(MySQL { pool, schema })
+        let options = MySqlConnectOptions::from_str(&format!("{}/{}", uri, schema))?;
+
+        // sqlx starts the sessions in UTC, by its offset which doesn't need the time zone tables
+        let time_zone = time_zone.unwrap_or(Tz::UTC);
+        let set_time_zone = match time_zone {
+            Tz::UTC | Tz::Etc__UTC => None,
+            time_zone => Some(format!("SET time_zone = '{}'", time_zone.name())),
+        };
+
+        let mut conn = probe(&options).await?;
+        if let Some(set_time_zone) = &set_time_zone {
+            conn.execute(set_time_zone.as_str()).await.with_context(|| {
+                format!(
+                    "The time zone {} is unknown to the server, are its time zone tables loaded?",
+                    time_zone.name()
+                )
+            })?;
+        }
+        conn.close().await?;
+
+        let pool = MySqlPoolOptions::new()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .after_connect(move |conn| {
+                let set_time_zone = set_time_zone.clone();
+                Box::pin(async move {
+                    if let Some(set_time_zone) = set_time_zone {
+                        conn.execute(set_time_zone.as_str()).await?;
+                    }
+                    Ok(())
+                })
+            })
+            .connect_with(options)
+            .await?;
+        Ok(MySQL {
+            pool,
+            schema,
+            time_zone,
+            running: RunningQueries::default(),
+            idle: IdleSessions::default(),
+        })
     }
 
     pub async fn list_databases(&self) -> Result<Vec<String>> {
@@ -32,6 +96,65 @@ impl MySQL {
     }
 }
 
+/// The details of a MySQL error, its position is found from the `near '..' at line N` part of
+/// the syntax errors.
+pub(super) fn error_details(err: &dyn DatabaseError, query: &str) -> Option<SGDBError> {
+    let err = err.try_downcast_ref::<MySqlDatabaseError>()?;
+
+    Some(SGDBError {
+        message: err.message().to_string(),
+        code: Some(err.number().to_string()),
+        sqlstate: err.code().map(String::from),
+        position: error_position(err.message(), query),
+        connection: false,
+    })
+}
+
+fn error_position(message: &str, query: &str) -> Option<usize> {
+    let (_, near) = message.split_once("near '")?;
+    let (snippet, line) = near.rsplit_once("' at line ")?;
+    let line = line.trim().parse::<usize>().ok()?;
+
+    let line_start = query
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum::<usize>();
+
+    if snippet.is_empty() {
+        // The query ended too early
+        return Some(query.trim_end().len());
+    }
+
+    query
+        .get(line_start..)?
+        .find(snippet)
+        .map(|offset| line_start + offset)
+}
+
+fn bind_value<'q>(
+    query: Query<'q, MySql, MySqlArguments>,
+    value: SGDBRowValue,
+    time_zone: Tz,
+) -> Query<'q, MySql, MySqlArguments> {
+    match value {
+        SGDBRowValue::Text(v) => query.bind(v),
+        SGDBRowValue::Boolean(v) => query.bind(v),
+        SGDBRowValue::Integer(v) => query.bind(v),
+        SGDBRowValue::UInteger(v) => query.bind(v),
+        SGDBRowValue::Double(v) => query.bind(v),
+        SGDBRowValue::Decimal(v) => query.bind(v),
+        SGDBRowValue::DateTime(v) => query.bind(v),
+        SGDBRowValue::Timestamp(v) => query.bind(v.with_timezone(&time_zone).naive_local()),
+        SGDBRowValue::Date(v) => query.bind(v),
+        SGDBRowValue::Time(v) => query.bind(v),
+        SGDBRowValue::Binary(v) => query.bind(v),
+        SGDBRowValue::Json(v) => query.bind(v),
+        SGDBRowValue::Uuid(v) => query.bind(v.to_hyphenated().to_string()),
+        SGDBRowValue::Null | SGDBRowValue::Unknown { .. } => query.bind(None::<String>),
+    }
+}
+
 fn decode<'r, T>(value: MySqlValueRef<'r>) -> Result<T>
 where
     T: Decode<'r, MySql> + Type<MySql>,
@@ -47,92 +170,480 @@ where
     Ok(T::decode(value).map_err(|source| anyhow!("Invalid column type encountered"))?)
 }
 
-fn map_column_value(col: &SGDBColumn, row: &MySqlRow) -> Result<SGDBRowValue> {
-    let value = row.try_get_raw(col.ordinal())?;
+/// Decode `value` whatever its column type, for the types sqlx decodes but doesn't report
+/// compatible, e.g. the bytes of a `GEOMETRY`.
+fn decode_unchecked<'r, T>(value: MySqlValueRef<'r>) -> Result<T>
+where
+    T: Decode<'r, MySql>,
+{
+    T::decode(value).map_err(|err| anyhow!("{}", err))
+}
 
-    Ok(if value.is_null() {
-        SGDBRowValue::Null
-    } else {
-        match col.r#type {
-            SGDBColumnType::Text => SGDBRowValue::Text(decode(value)?),
-            SGDBColumnType::Boolean => SGDBRowValue::Boolean(decode(value)?),
-            SGDBColumnType::Integer => SGDBRowValue::Integer(decode(value)?),
-            SGDBColumnType::UInteger => SGDBRowValue::UInteger(decode(value)?),
-            SGDBColumnType::Double => SGDBRowValue::Double(decode(value)?),
-            SGDBColumnType::Decimal => SGDBRowValue::Decimal(decode(value)?),
-            SGDBColumnType::DateTime => SGDBRowValue::DateTime(decode(value)?),
-            SGDBColumnType::Binary => SGDBRowValue::Binary(decode(value)?),
-            SGDBColumnType::Unknown => SGDBRowValue::Unknown {
-                error: "Unknown column type".to_string(),
-            },
+/// A `TIME`, which is a time of day or an interval of up to 838 hours, possibly negative. The
+/// intervals which aren't a time of day are kept in text.
+fn decode_time(value: MySqlValueRef<'_>) -> Result<SGDBRowValue> {
+    time_value(decode_unchecked(value)?)
+}
+
+fn time_value(bytes: &[u8]) -> Result<SGDBRowValue> {
+    // The text protocol sends `[-]HHH:MM:SS[.ffffff]`, the binary one the length of the value
+    // (0, 8 or 12), the sign, the days, the hours, minutes, seconds and microseconds
+    let text = match bytes {
+        [b'-' | b'0'..=b'9', ..] => std::str::from_utf8(bytes)?.to_string(),
+        [] | [0] => "00:00:00".to_string(),
+        [8 | 12, negative, rest @ ..] if rest.len() >= 7 => {
+            let days = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]);
+            let micros = match rest.get(7..11) {
+                Some(&[a, b, c, d]) => format!(".{:06}", u32::from_le_bytes([a, b, c, d])),
+                _ => String::new(),
+            };
+            format!(
+                "{}{:02}:{:02}:{:02}{}",
+                if *negative == 1 { "-" } else { "" },
+                days * 24 + rest[4] as u32,
+                rest[5],
+                rest[6],
+                micros
+            )
         }
+        _ => bail!("Invalid TIME value"),
+    };
+
+    Ok(match NaiveTime::parse_from_str(&text, "%H:%M:%S%.f") {
+        Ok(time) => SGDBRowValue::Time(time),
+        Err(_) => SGDBRowValue::Text(text),
     })
 }
 
-fn map_column(col: &MySqlColumn) -> SGDBColumn {
-    info!("{}", col.type_info().name());
+/// A `BIT(n)`, sent as big-endian bytes.
+fn bit_value(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |bits, byte| bits << 8 | *byte as u64)
+}
+
+fn map_column_value(col: &SGDBColumn, row: &MySqlRow, time_zone: Tz) -> Result<SGDBRowValue> {
+    let value = row.try_get_raw(col.ordinal())?;
+
+    if value.is_null() {
+        return Ok(SGDBRowValue::Null);
+    }
+
+    // YEAR and BIT share their `SGDBColumnType` with the unsigned integers but not their
+    // encoding, and sqlx only decodes them as unsigned integers when they're flagged so.
+    let type_name = value.type_info().name().to_string();
+
+    Ok(match (col.r#type, type_name.as_str()) {
+        (_, "YEAR") => SGDBRowValue::UInteger(decode_unchecked::<u16>(value)?.into()),
+        // The bits are sent as big-endian bytes whatever the protocol
+        (_, "BIT") => SGDBRowValue::UInteger(bit_value(decode_unchecked(value)?)),
+        (SGDBColumnType::Text, _) => SGDBRowValue::Text(decode(value)?),
+        (SGDBColumnType::Boolean, _) => SGDBRowValue::Boolean(decode(value)?),
+        (SGDBColumnType::Integer, _) => SGDBRowValue::Integer(decode(value)?),
+        (SGDBColumnType::UInteger, _) => SGDBRowValue::UInteger(decode(value)?),
+        (SGDBColumnType::Double, _) => SGDBRowValue::Double(decode(value)?),
+        (SGDBColumnType::Decimal, _) => SGDBRowValue::Decimal(decode(value)?),
+        (SGDBColumnType::DateTime, _) => SGDBRowValue::DateTime(decode(value)?),
+        // A `TIMESTAMP` is an instant sent in the time zone of the session, the earliest one of
+        // a time repeated when the clocks go back
+        (SGDBColumnType::Timestamp, _) => {
+            let local = decode::<NaiveDateTime>(value)?;
+            let timestamp = time_zone
+                .from_local_datetime(&local)
+                .earliest()
+                .ok_or_else(|| anyhow!("{} doesn't exist in {}", local, time_zone.name()))?;
+
+            SGDBRowValue::Timestamp(timestamp.with_timezone(&Utc))
+        }
+        (SGDBColumnType::Date, _) => SGDBRowValue::Date(decode(value)?),
+        (SGDBColumnType::Time, _) => decode_time(value)?,
+        (SGDBColumnType::Binary, _) => SGDBRowValue::Binary(decode_unchecked(value)?),
+        (SGDBColumnType::Json, _) => {
+            SGDBRowValue::Json(serde_json::from_slice(decode_unchecked(value)?)?)
+        }
+        (SGDBColumnType::Uuid, _) => SGDBRowValue::Uuid(decode_unchecked::<&str>(value)?.parse()?),
+        (SGDBColumnType::Unknown, _) => SGDBRowValue::Unknown {
+            error: format!("Unknown column type {}", type_name),
+        },
+    })
+}
 
-    let r#type = match col.type_info().name() {
+fn map_type_name(name: &str) -> SGDBColumnType {
+    match name {
         "BOOLEAN" => SGDBColumnType::Boolean,
         "TINYINT UNSIGNED" | "SMALLINT UNSIGNED" | "INT UNSIGNED" | "MEDIUMINT UNSIGNED"
-        | "BIGINT UNSIGNED" => SGDBColumnType::UInteger,
+        | "BIGINT UNSIGNED" | "YEAR" | "YEAR UNSIGNED" | "BIT" | "BIT UNSIGNED" => {
+            SGDBColumnType::UInteger
+        }
         "TINYINT" | "SMALLINT" | "INT" | "MEDIUMINT" | "BIGINT" => SGDBColumnType::Integer,
         "DECIMAL" => SGDBColumnType::Decimal,
         "FLOAT" | "DOUBLE" => SGDBColumnType::Double,
-        "CHAR" | "VARCHAR" | "TEXT" => SGDBColumnType::Text,
+        // A `SET` column is reported as `CHAR`, the `UUID` of MariaDB only in `INFORMATION_SCHEMA`
+        // and the `NULL` type is the one of `SELECT NULL`
+        "CHAR" | "VARCHAR" | "TINYTEXT" | "TEXT" | "MEDIUMTEXT" | "LONGTEXT" | "ENUM" | "SET"
+        | "UUID" | "NULL" => SGDBColumnType::Text,
+        "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" | "GEOMETRY" => {
+            SGDBColumnType::Binary
+        }
+        // MariaDB stores JSON in `LONGTEXT` columns and reports them so
+        "JSON" => SGDBColumnType::Json,
 
-        "DATE" | "DATETIME" | "TIMESTAMP" => SGDBColumnType::DateTime,
+        "DATE" => SGDBColumnType::Date,
+        "TIME" => SGDBColumnType::Time,
+        "DATETIME" => SGDBColumnType::DateTime,
+        "TIMESTAMP" => SGDBColumnType::Timestamp,
 
         _ => SGDBColumnType::Unknown,
-    };
+    }
+}
+
+fn map_column(col: &MySqlColumn) -> SGDBColumn {
+    info!("{}", col.type_info().name());
 
     SGDBColumn {
         name: col.name().to_string(),
         ordinal: col.ordinal(),
-        r#type,
+        r#type: map_type_name(col.type_info().name()),
     }
 }
 
-#[async_trait]
-impl SGDB for MySQL {
-    async fn fetch_all(&self, query: &str, params: Option<Vec<String>>) -> Result<SGDBFetchResult> {
-        let mut num_rows: usize = 0;
-        let mut res = sqlx::query(query);
+/// Map an `INFORMATION_SCHEMA.COLUMNS` type (e.g. `int` and `int(10) unsigned`) with the
+/// type names used by sqlx for result columns.
+fn map_information_schema_type(data_type: &str, column_type: &str) -> SGDBColumnType {
+    let column_type = column_type.to_lowercase();
+
+    if column_type.starts_with("tinyint(1)") {
+        return SGDBColumnType::Boolean;
+    }
+
+    let name = match data_type.to_uppercase().as_str() {
+        // The spatial types are sent as `GEOMETRY`
+        "POINT" | "LINESTRING" | "POLYGON" | "MULTIPOINT" | "MULTILINESTRING" | "MULTIPOLYGON"
+        | "GEOMETRYCOLLECTION" => "GEOMETRY".to_string(),
+        name => name.to_string(),
+    };
 
-        if let Some(params) = params {
+    // Only the integers are named `UNSIGNED` by sqlx, not `DECIMAL`, `FLOAT` and `DOUBLE`
+    match map_type_name(&name) {
+        SGDBColumnType::Integer if column_type.contains("unsigned") => {
+            map_type_name(&format!("{} UNSIGNED", name))
+        }
+        r#type => r#type,
+    }
+}
+
+fn result_set(rows: Vec<MySqlRow>, time_zone: Tz) -> SGDBFetchResult {
+    let num_rows = rows.len();
+
+    let mut res = rows
+        .into_iter()
+        .enumerate()
+        .fold(IndexMap::new(), |mut map, (index, row)| {
+            if index == 0 {
+                for col in row.columns() {
+                    map.entry(map_column(col)).or_insert_with(SGDBColumnValues::default);
+                }
+            }
+
+            for (col, values) in map.iter_mut() {
+                values.push(map_column_value(col, &row, time_zone).unwrap_or_else(|err| {
+                    SGDBRowValue::Unknown {
+                        error: format!("{}", err),
+                    }
+                }));
+            }
+
+            map
+        });
+
+    for values in res.values_mut() {
+        values.shrink_to_fit();
+    }
+
+    SGDBFetchResult {
+        data: res,
+        num_rows,
+    }
+}
+
+async fn fetch_statement(
+    conn: &mut MySqlConnection,
+    statement: &str,
+    params: Option<Vec<String>>,
+    time_zone: Tz,
+) -> Result<SGDBStatementOutput> {
+    // Without parameters the statement isn't prepared, some like `CREATE PROCEDURE` can't be
+    let mut stream = match params {
+        Some(params) => {
+            let mut query = sqlx::query(statement);
             for param in params {
-                res = res.bind(param);
+                query = query.bind(param);
             }
+            query.fetch_many(conn)
         }
+        None => conn.fetch_many(statement),
+    };
 
-        let res = res.fetch_all(&self.pool)
-            .await?
-            .into_iter()
-            .enumerate()
-            .fold(IndexMap::new(), |mut map, (index, row)| {
-                if index == 0 {
-                    for col in row.columns() {
-                        map.entry(map_column(col)).or_insert(Vec::new());
-                    }
+    let mut output = SGDBStatementOutput::default();
+    let mut rows = vec![];
+
+    // Each result set ends with a result, a `CALL` returns several ones
+    while let Some(next) = stream.try_next().await? {
+        match next {
+            Either::Left(result) => {
+                output.rows_affected += result.rows_affected();
+                if result.last_insert_id() > 0 {
+                    output.last_insert_id = Some(result.last_insert_id());
                 }
 
-                for (col, values) in map.iter_mut() {
-                    values.push(map_column_value(col, &row).unwrap_or_else(|err| {
-                        SGDBRowValue::Unknown {
-                            error: format!("{}", err),
-                        }
-                    }));
+                if !rows.is_empty() {
+                    output.result_sets.push(result_set(std::mem::take(&mut rows), time_zone));
                 }
+            }
+            Either::Right(row) => rows.push(row),
+        }
+    }
 
-                num_rows += 1;
+    if !rows.is_empty() {
+        output.result_sets.push(result_set(rows, time_zone));
+    }
 
-                map
-            });
+    Ok(output)
+}
 
-        Ok(SGDBFetchResult {
-            data: res,
-            num_rows,
-        })
+/// Pass the rows of the first result set of `statement` to `sink`, the other ones are skipped.
+async fn stream_statement(
+    conn: &mut MySqlConnection,
+    statement: &str,
+    sink: &mut dyn SGDBRowSink,
+    time_zone: Tz,
+) -> Result<u64> {
+    let mut stream = conn.fetch_many(statement);
+    let mut columns: Option<Vec<SGDBColumn>> = None;
+    let mut done = false;
+    let mut num_rows = 0;
+
+    // The stream is read to its end, leaving the connection ready for the next statement
+    while let Some(next) = stream.try_next().await? {
+        let row = match next {
+            Either::Left(_) => {
+                done |= columns.is_some();
+                continue;
+            }
+            Either::Right(_) if done => continue,
+            Either::Right(row) => row,
+        };
+
+        let columns = match &mut columns {
+            Some(columns) => columns,
+            None => {
+                let first = row.columns().iter().map(map_column).collect::<Vec<_>>();
+                sink.columns(&first)?;
+                columns.insert(first)
+            }
+        };
+
+        let values = columns
+            .iter()
+            .map(|col| {
+                map_column_value(col, &row, time_zone).unwrap_or_else(|err| SGDBRowValue::Unknown {
+                    error: format!("{}", err),
+                })
+            })
+            .collect();
+
+        sink.row(values)?;
+        num_rows += 1;
+    }
+
+    sink.finish()?;
+
+    Ok(num_rows)
+}
+
+async fn execute(
+    tx: &mut Transaction<'_, MySql>,
+    statements: Vec<SGDBStatement>,
+    time_zone: Tz,
+) -> Result<u64> {
+    let mut rows_affected = 0;
+
+    for statement in statements.iter() {
+        let mut query = sqlx::query(&statement.query);
+        for param in statement.params.iter().cloned() {
+            query = bind_value(query, param, time_zone);
+        }
+
+        rows_affected += query.execute(&mut *tx).await?.rows_affected();
+    }
+
+    Ok(rows_affected)
+}
+
+/// The server side id of a connection, to cancel its query.
+async fn connection_id(conn: &mut MySqlConnection) -> Result<u64> {
+    let id: u64 = sqlx::query_scalar("SELECT CONNECTION_ID()").fetch_one(conn).await?;
+
+    Ok(id)
+}
+
+struct MySqlTransaction {
+    tx: Transaction<'static, MySql>,
+    connection_id: u64,
+    time_zone: Tz,
+    running: RunningQueries,
+}
+
+/// The number of connections of the ended sessions kept, the other ones go back to the pool.
+const MAX_IDLE_SESSIONS: usize = 2;
+
+/// A connection of the pool with its server side id.
+type IdentifiedConnection = (PoolConnection<MySql>, u64);
+
+/// The connections of the ended sessions with their server side id, the next sessions use them
+/// rather than reading the id of a connection of the pool each time.
+#[derive(Default, Clone)]
+struct IdleSessions(Arc<Mutex<Vec<IdentifiedConnection>>>);
+
+impl IdleSessions {
+    /// The connection of an ended session which is still alive, like the pool checks them.
+    async fn take(&self) -> Option<IdentifiedConnection> {
+        loop {
+            let (mut conn, id) = self.0.lock().unwrap().pop()?;
+            if conn.ping().await.is_ok() {
+                return Some((conn, id));
+            }
+        }
+    }
+
+    fn keep(&self, conn: PoolConnection<MySql>, id: u64) {
+        let mut idle = self.0.lock().unwrap();
+        if idle.len() < MAX_IDLE_SESSIONS {
+            idle.push((conn, id));
+        }
+    }
+}
+
+struct MySqlSession {
+    /// Always set, taken back by `drop`.
+    conn: Option<PoolConnection<MySql>>,
+    connection_id: u64,
+    time_zone: Tz,
+    running: RunningQueries,
+    idle: IdleSessions,
+    /// The connection to the server was lost, it isn't kept once the session ends.
+    lost: bool,
+}
+
+impl MySqlSession {
+    fn conn(&mut self) -> &mut MySqlConnection {
+        self.conn.as_mut().expect("The connection is set until the session is dropped")
+    }
+
+    fn check<T>(&mut self, res: Result<T>) -> Result<T> {
+        if let Err(err) = &res {
+            self.lost |= crate::db::is_connection_error(err);
+        }
+
+        res
+    }
+}
+
+impl Drop for MySqlSession {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take().filter(|_| !self.lost) {
+            self.idle.keep(conn, self.connection_id);
+        }
+    }
+}
+
+#[async_trait]
+impl SGDBSession for MySqlSession {
+    async fn fetch_statement(
+        &mut self,
+        statement: &str,
+        params: Option<Vec<String>>,
+    ) -> Result<SGDBStatementOutput> {
+        let _running = self.running.track(self.connection_id);
+        let time_zone = self.time_zone;
+        let res = fetch_statement(self.conn(), statement, params, time_zone).await;
+
+        self.check(res)
+    }
+
+    async fn stream_statement(
+        &mut self,
+        statement: &str,
+        sink: &mut dyn SGDBRowSink,
+    ) -> Result<u64> {
+        let _running = self.running.track(self.connection_id);
+        let time_zone = self.time_zone;
+        let res = stream_statement(self.conn(), statement, sink, time_zone).await;
+
+        self.check(res)
+    }
+}
+
+#[async_trait]
+impl SGDBSession for MySqlTransaction {
+    async fn fetch_statement(
+        &mut self,
+        statement: &str,
+        params: Option<Vec<String>>,
+    ) -> Result<SGDBStatementOutput> {
+        let _running = self.running.track(self.connection_id);
+        fetch_statement(&mut self.tx, statement, params, self.time_zone).await
+    }
+
+    async fn stream_statement(
+        &mut self,
+        statement: &str,
+        sink: &mut dyn SGDBRowSink,
+    ) -> Result<u64> {
+        let _running = self.running.track(self.connection_id);
+        stream_statement(&mut self.tx, statement, sink, self.time_zone).await
+    }
+}
+
+#[async_trait]
+impl SGDBTransaction for MySqlTransaction {
+
+    async fn execute(&mut self, statements: Vec<SGDBStatement>) -> Result<u64> {
+        let mut savepoint = sqlx::Connection::begin(&mut *self.tx).await?;
+        let rows_affected = execute(&mut savepoint, statements, self.time_zone).await?;
+        savepoint.commit().await?;
+
+        Ok(rows_affected)
+    }
+
+    async fn commit(self: Box<Self>) -> Result<()> {
+        Ok(self.tx.commit().await?)
+    }
+
+    async fn rollback(self: Box<Self>) -> Result<()> {
+        Ok(self.tx.rollback().await?)
+    }
+}
+
+#[async_trait]
+impl SGDB for MySQL {
+    async fn session(&self) -> Result<Box<dyn SGDBSession>> {
+        let (conn, connection_id) = match self.idle.take().await {
+            Some(idle) => idle,
+            None => {
+                let mut conn = self.pool.acquire().await?;
+                let connection_id = connection_id(&mut conn).await?;
+
+                (conn, connection_id)
+            }
+        };
+
+        Ok(Box::new(MySqlSession {
+            conn: Some(conn),
+            connection_id,
+            time_zone: self.time_zone,
+            running: self.running.clone(),
+            idle: self.idle.clone(),
+            lost: false,
+        }))
     }
 
     async fn list_tables(&self) -> Result<Vec<super::SGDBTable>> {
@@ -158,4 +669,323 @@ impl SGDB for MySQL {
 
         Ok(tables)
     }
+
+    async fn list_columns(&self, table: &str) -> Result<Vec<SGDBTableColumn>> {
+        let columns = sqlx::query(
+            "SELECT COLUMN_NAME AS name, DATA_TYPE AS data_type, COLUMN_TYPE AS column_type, IS_NULLABLE AS nullable, COLUMN_DEFAULT AS column_default, COLUMN_KEY AS column_key, EXTRA AS extra, COLUMN_COMMENT AS comment FROM INFORMATION_SCHEMA.COLUMNS WHERE TABLE_SCHEMA = ? AND TABLE_NAME = ? ORDER BY ORDINAL_POSITION",
+        )
+        .bind(&self.schema)
+        .bind(table)
+        .try_map(|row: MySqlRow| {
+            let data_type: String = row.try_get("data_type")?;
+            let column_type: String = row.try_get("column_type")?;
+            let nullable: String = row.try_get("nullable")?;
+            let column_key: String = row.try_get("column_key")?;
+            let extra: String = row.try_get("extra")?;
+
+            Ok(SGDBTableColumn {
+                name: row.try_get("name")?,
+                r#type: map_information_schema_type(&data_type, &column_type),
+                column_type,
+                nullable: nullable == "YES",
+                default: row.try_get("column_default")?,
+                key: match column_key.as_str() {
+                    "PRI" => SGDBColumnKey::Primary,
+                    "UNI" => SGDBColumnKey::Unique,
+                    "MUL" => SGDBColumnKey::Multiple,
+                    _ => SGDBColumnKey::None,
+                },
+                auto_increment: extra.contains("auto_increment"),
+                extra,
+                comment: row.try_get("comment")?,
+            })
+        })
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(columns)
+    }
+
+    async fn list_indexes(&self, table: &str) -> Result<Vec<SGDBTableIndex>> {
+        let rows = sqlx::query(
+            "SELECT INDEX_NAME AS name, COLUMN_NAME AS column_name, CAST(NON_UNIQUE AS SIGNED) AS non_unique, INDEX_TYPE AS index_type FROM INFORMATION_SCHEMA.STATISTICS WHERE TABLE_SCHEMA = ? AND TABLE_NAME = ? ORDER BY INDEX_NAME = 'PRIMARY' DESC, INDEX_NAME, SEQ_IN_INDEX",
+        )
+        .bind(&self.schema)
+        .bind(table)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut indexes: IndexMap<String, SGDBTableIndex> = IndexMap::new();
+        for row in rows {
+            let name: String = row.try_get("name")?;
+            // Functional key parts (MySQL 8) have no column name.
+            let column: Option<String> = row.try_get("column_name")?;
+            let non_unique: i64 = row.try_get("non_unique")?;
+
+            let index = indexes
+                .entry(name.clone())
+                .or_insert_with(|| SGDBTableIndex {
+                    primary: name == "PRIMARY",
+                    name,
+                    columns: Vec::new(),
+                    unique: non_unique == 0,
+                    index_type: row.try_get("index_type").unwrap_or_default(),
+                });
+
+            index.columns.push(column.unwrap_or_else(|| "<expression>".to_string()));
+        }
+
+        Ok(indexes.into_values().collect())
+    }
+
+    async fn list_foreign_keys(&self, table: &str) -> Result<Vec<SGDBTableForeignKey>> {
+        let rows = sqlx::query(
+            "SELECT k.CONSTRAINT_NAME AS name, k.COLUMN_NAME AS column_name, k.REFERENCED_TABLE_SCHEMA AS referenced_schema, k.REFERENCED_TABLE_NAME AS referenced_table, k.REFERENCED_COLUMN_NAME AS referenced_column, r.UPDATE_RULE AS on_update, r.DELETE_RULE AS on_delete FROM INFORMATION_SCHEMA.KEY_COLUMN_USAGE k JOIN INFORMATION_SCHEMA.REFERENTIAL_CONSTRAINTS r ON r.CONSTRAINT_SCHEMA = k.CONSTRAINT_SCHEMA AND r.CONSTRAINT_NAME = k.CONSTRAINT_NAME AND r.TABLE_NAME = k.TABLE_NAME WHERE k.TABLE_SCHEMA = ? AND k.TABLE_NAME = ? AND k.REFERENCED_TABLE_NAME IS NOT NULL ORDER BY k.CONSTRAINT_NAME, k.ORDINAL_POSITION",
+        )
+        .bind(&self.schema)
+        .bind(table)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut foreign_keys: IndexMap<String, SGDBTableForeignKey> = IndexMap::new();
+        for row in rows {
+            let name: String = row.try_get("name")?;
+
+            let foreign_key = match foreign_keys.entry(name.clone()) {
+                indexmap::map::Entry::Occupied(entry) => entry.into_mut(),
+                indexmap::map::Entry::Vacant(entry) => entry.insert(SGDBTableForeignKey {
+                    name,
+                    columns: Vec::new(),
+                    referenced_schema: row.try_get("referenced_schema")?,
+                    referenced_table: row.try_get("referenced_table")?,
+                    referenced_columns: Vec::new(),
+                    on_update: row.try_get("on_update")?,
+                    on_delete: row.try_get("on_delete")?,
+                }),
+            };
+
+            foreign_key.columns.push(row.try_get("column_name")?);
+            foreign_key
+                .referenced_columns
+                .push(row.try_get("referenced_column")?);
+        }
+
+        Ok(foreign_keys.into_values().collect())
+    }
+
+    async fn list_triggers(&self, table: &str) -> Result<Vec<SGDBTableTrigger>> {
+        let triggers = sqlx::query(
+            "SELECT TRIGGER_NAME AS name, ACTION_TIMING AS timing, EVENT_MANIPULATION AS event, ACTION_STATEMENT AS statement FROM INFORMATION_SCHEMA.TRIGGERS WHERE EVENT_OBJECT_SCHEMA = ? AND EVENT_OBJECT_TABLE = ? ORDER BY TRIGGER_NAME",
+        )
+        .bind(&self.schema)
+        .bind(table)
+        .try_map(|row: MySqlRow| {
+            Ok(SGDBTableTrigger {
+                name: row.try_get("name")?,
+                timing: row.try_get("timing")?,
+                event: row.try_get("event")?,
+                statement: row.try_get("statement")?,
+            })
+        })
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(triggers)
+    }
+
+    async fn execute(&self, statements: Vec<SGDBStatement>) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let rows_affected = execute(&mut tx, statements, self.time_zone).await?;
+        tx.commit().await?;
+
+        Ok(rows_affected)
+    }
+
+    async fn begin(&self) -> Result<Box<dyn SGDBTransaction>> {
+        let mut tx = self.pool.begin().await?;
+        let connection_id = connection_id(&mut tx).await?;
+
+        Ok(Box::new(MySqlTransaction {
+            tx,
+            connection_id,
+            time_zone: self.time_zone,
+            running: self.running.clone(),
+        }))
+    }
+
+    async fn cancel(&self, seq: Option<u64>) -> Result<()> {
+        // The running connections are busy, the pool gives another one
+        for id in self.running.ids(seq) {
+            sqlx::query(&format!("KILL QUERY {}", id))
+                .execute(&self.pool)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+
+        Ok(())
+    }
+
+    async fn table_ddl(&self, table: &str) -> Result<String> {
+        let row = sqlx::query(&format!(
+            "SHOW CREATE TABLE {}",
+            SGDBKind::Mysql.quote_identifier(table)
+        ))
+        .fetch_one(&self.pool)
+        .await?;
+
+        // Second column is either `Create Table` or `Create View`.
+        Ok(row.try_get(1)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_type_names() {
+        let types = [
+            ("BOOLEAN", SGDBColumnType::Boolean),
+            ("TINYINT", SGDBColumnType::Integer),
+            ("SMALLINT", SGDBColumnType::Integer),
+            ("MEDIUMINT", SGDBColumnType::Integer),
+            ("INT", SGDBColumnType::Integer),
+            ("BIGINT", SGDBColumnType::Integer),
+            ("TINYINT UNSIGNED", SGDBColumnType::UInteger),
+            ("SMALLINT UNSIGNED", SGDBColumnType::UInteger),
+            ("MEDIUMINT UNSIGNED", SGDBColumnType::UInteger),
+            ("INT UNSIGNED", SGDBColumnType::UInteger),
+            ("BIGINT UNSIGNED", SGDBColumnType::UInteger),
+            ("YEAR", SGDBColumnType::UInteger),
+            ("YEAR UNSIGNED", SGDBColumnType::UInteger),
+            ("BIT", SGDBColumnType::UInteger),
+            ("BIT UNSIGNED", SGDBColumnType::UInteger),
+            ("DECIMAL", SGDBColumnType::Decimal),
+            ("FLOAT", SGDBColumnType::Double),
+            ("DOUBLE", SGDBColumnType::Double),
+            ("CHAR", SGDBColumnType::Text),
+            ("VARCHAR", SGDBColumnType::Text),
+            ("TINYTEXT", SGDBColumnType::Text),
+            ("TEXT", SGDBColumnType::Text),
+            ("MEDIUMTEXT", SGDBColumnType::Text),
+            ("LONGTEXT", SGDBColumnType::Text),
+            ("ENUM", SGDBColumnType::Text),
+            ("SET", SGDBColumnType::Text),
+            ("NULL", SGDBColumnType::Text),
+            ("BINARY", SGDBColumnType::Binary),
+            ("VARBINARY", SGDBColumnType::Binary),
+            ("TINYBLOB", SGDBColumnType::Binary),
+            ("BLOB", SGDBColumnType::Binary),
+            ("MEDIUMBLOB", SGDBColumnType::Binary),
+            ("LONGBLOB", SGDBColumnType::Binary),
+            ("GEOMETRY", SGDBColumnType::Binary),
+            ("JSON", SGDBColumnType::Json),
+            ("DATE", SGDBColumnType::Date),
+            ("TIME", SGDBColumnType::Time),
+            ("DATETIME", SGDBColumnType::DateTime),
+            ("TIMESTAMP", SGDBColumnType::Timestamp),
+            ("VECTOR", SGDBColumnType::Unknown),
+        ];
+
+        for (name, r#type) in types {
+            assert_eq!(map_type_name(name), r#type, "{}", name);
+        }
+    }
+
+    #[test]
+    fn map_information_schema_types() {
+        let types = [
+            ("tinyint", "tinyint(1)", SGDBColumnType::Boolean),
+            ("tinyint", "tinyint(1) unsigned", SGDBColumnType::Boolean),
+            ("tinyint", "tinyint(4)", SGDBColumnType::Integer),
+            ("int", "int(10) unsigned", SGDBColumnType::UInteger),
+            ("int", "int(11)", SGDBColumnType::Integer),
+            ("bigint", "bigint unsigned", SGDBColumnType::UInteger),
+            ("year", "year(4)", SGDBColumnType::UInteger),
+            ("bit", "bit(8)", SGDBColumnType::UInteger),
+            ("decimal", "decimal(10,2) unsigned", SGDBColumnType::Decimal),
+            ("double", "double unsigned", SGDBColumnType::Double),
+            ("varchar", "varchar(255)", SGDBColumnType::Text),
+            ("enum", "enum('a','b')", SGDBColumnType::Text),
+            ("set", "set('a','b')", SGDBColumnType::Text),
+            ("longtext", "longtext", SGDBColumnType::Text),
+            ("varbinary", "varbinary(16)", SGDBColumnType::Binary),
+            ("point", "point", SGDBColumnType::Binary),
+            ("linestring", "linestring", SGDBColumnType::Binary),
+            ("polygon", "polygon", SGDBColumnType::Binary),
+            ("multipoint", "multipoint", SGDBColumnType::Binary),
+            ("multilinestring", "multilinestring", SGDBColumnType::Binary),
+            ("multipolygon", "multipolygon", SGDBColumnType::Binary),
+            ("geometrycollection", "geometrycollection", SGDBColumnType::Binary),
+            ("json", "json", SGDBColumnType::Json),
+            ("datetime", "datetime(6)", SGDBColumnType::DateTime),
+            ("timestamp", "timestamp", SGDBColumnType::Timestamp),
+        ];
+
+        for (data_type, column_type, r#type) in types {
+            assert_eq!(
+                map_information_schema_type(data_type, column_type),
+                r#type,
+                "{}",
+                column_type
+            );
+        }
+    }
+
+    fn time(hour: u32, min: u32, sec: u32, micro: u32) -> SGDBRowValue {
+        SGDBRowValue::Time(NaiveTime::from_hms_micro(hour, min, sec, micro))
+    }
+
+    fn text(value: &str) -> SGDBRowValue {
+        SGDBRowValue::Text(value.to_string())
+    }
+
+    #[test]
+    fn decode_text_time() {
+        assert_eq!(time_value(b"12:34:56").unwrap(), time(12, 34, 56, 0));
+        assert_eq!(time_value(b"00:00:00.000250").unwrap(), time(0, 0, 0, 250));
+        assert_eq!(time_value(b"-01:02:03").unwrap(), text("-01:02:03"));
+        assert_eq!(time_value(b"838:59:59").unwrap(), text("838:59:59"));
+    }
+
+    #[test]
+    fn decode_binary_time() {
+        assert_eq!(time_value(&[]).unwrap(), time(0, 0, 0, 0));
+        assert_eq!(time_value(&[0]).unwrap(), time(0, 0, 0, 0));
+        assert_eq!(
+            time_value(&[8, 0, 0, 0, 0, 0, 12, 34, 56]).unwrap(),
+            time(12, 34, 56, 0)
+        );
+        assert_eq!(
+            time_value(&[12, 0, 0, 0, 0, 0, 12, 34, 56, 0x40, 0xe2, 0x01, 0]).unwrap(),
+            time(12, 34, 56, 123456)
+        );
+        assert_eq!(
+            time_value(&[8, 1, 0, 0, 0, 0, 1, 2, 3]).unwrap(),
+            text("-01:02:03")
+        );
+        assert_eq!(
+            time_value(&[8, 0, 34, 0, 0, 0, 22, 59, 59]).unwrap(),
+            text("838:59:59")
+        );
+        assert_eq!(
+            time_value(&[12, 1, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0]).unwrap(),
+            text("-24:00:00.000001")
+        );
+        assert!(time_value(&[8, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn decode_bit() {
+        assert_eq!(bit_value(&[]), 0);
+        assert_eq!(bit_value(&[0b101]), 5);
+        assert_eq!(bit_value(&[0x01, 0x00]), 256);
+        assert_eq!(bit_value(&[0x12, 0x34, 0x56, 0x78]), 0x1234_5678);
+        assert_eq!(bit_value(&[0xff; 8]), u64::MAX);
+    }
 }
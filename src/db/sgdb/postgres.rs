@@ -0,0 +1,835 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use futures_util::TryStreamExt;
+use indexmap::IndexMap;
+use sqlx::{
+    postgres::{
+        PgArguments, PgColumn, PgConnectOptions, PgConnection, PgDatabaseError, PgErrorPosition,
+        PgPoolOptions, PgRow, PgValueRef,
+    },
+    error::DatabaseError,
+    pool::PoolConnection,
+    query::Query,
+    Column, Connection, Decode, Either, Executor, PgPool, Postgres as Pg, Row,
+    Transaction, Type, TypeInfo, ValueRef,
+};
+
+use super::{
+    probe, RunningQueries, CONNECT_TIMEOUT, SGDBColumn, SGDBColumnKey, SGDBColumnType,
+    SGDBColumnValues, SGDBError, SGDBFetchResult, SGDBRowSink, SGDBRowValue, SGDBSession,
+    SGDBStatementOutput, SGDBTable, SGDBKind, SGDBStatement, SGDBTableColumn, SGDBTableForeignKey,
+    SGDBTableIndex, SGDBTableTrigger, SGDBTransaction, SGDB,
+};
+
+pub struct Postgres {
+    pool: PgPool,
+    schema: String,
+    running: RunningQueries,
+}
+
+impl Postgres {
+    /// Unlike MySQL, the database is part of the uri and `schema` is a Postgres schema
+    /// (`public` when empty), used as the connection `search_path`.
+    pub async fn connect(
+        uri: &str,
+        schema: impl Into<String>,
+        time_zone: Option<Tz>,
+    ) -> Result<Self> {
+        let mut schema = schema.into();
+        if schema.is_empty() {
+            schema = "public".to_string();
+        }
+
+        let options = PgConnectOptions::from_str(uri)?.options([("search_path", &schema)]);
+
+        // sqlx starts the sessions in UTC, the `timestamptz` values are decoded as instants
+        // whatever the time zone
+        let set_time_zone =
+            time_zone.map(|time_zone| format!("SET TIME ZONE '{}'", time_zone.name()));
+
+        let mut conn = probe(&options).await?;
+        if let Some(set_time_zone) = &set_time_zone {
+            conn.execute(set_time_zone.as_str()).await?;
+        }
+        conn.close().await?;
+
+        let pool = PgPoolOptions::new()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .after_connect(move |conn| {
+                let set_time_zone = set_time_zone.clone();
+                Box::pin(async move {
+                    if let Some(set_time_zone) = set_time_zone {
+                        conn.execute(set_time_zone.as_str()).await?;
+                    }
+                    Ok(())
+                })
+            })
+            .connect_with(options)
+            .await?;
+
+        Ok(Postgres {
+            pool,
+            schema,
+            running: RunningQueries::default(),
+        })
+    }
+}
+
+/// The details of a Postgres error, its position is in characters from 1.
+pub(super) fn error_details(err: &dyn DatabaseError, query: &str) -> Option<SGDBError> {
+    let err = err.try_downcast_ref::<PgDatabaseError>()?;
+
+    let position = match err.position() {
+        Some(PgErrorPosition::Original(position)) => query
+            .char_indices()
+            .nth(position.saturating_sub(1))
+            .map(|(offset, _)| offset),
+        _ => None,
+    };
+
+    Some(SGDBError {
+        message: err.message().to_string(),
+        code: None,
+        sqlstate: Some(err.code().to_string()),
+        position,
+        connection: false,
+    })
+}
+
+fn bind_value<'q>(
+    query: Query<'q, Pg, PgArguments>,
+    value: SGDBRowValue,
+) -> Query<'q, Pg, PgArguments> {
+    match value {
+        SGDBRowValue::Text(v) => query.bind(v),
+        SGDBRowValue::Boolean(v) => query.bind(v),
+        SGDBRowValue::Integer(v) => query.bind(v),
+        // Postgres has no unsigned integers
+        SGDBRowValue::UInteger(v) => query.bind(v as i64),
+        SGDBRowValue::Double(v) => query.bind(v),
+        SGDBRowValue::Decimal(v) => query.bind(v),
+        SGDBRowValue::DateTime(v) => query.bind(v),
+        SGDBRowValue::Timestamp(v) => query.bind(v),
+        SGDBRowValue::Date(v) => query.bind(v),
+        SGDBRowValue::Time(v) => query.bind(v),
+        SGDBRowValue::Binary(v) => query.bind(v),
+        SGDBRowValue::Json(v) => query.bind(v),
+        SGDBRowValue::Uuid(v) => query.bind(v),
+        SGDBRowValue::Null | SGDBRowValue::Unknown { .. } => query.bind(None::<String>),
+    }
+}
+
+fn decode<'r, T>(value: PgValueRef<'r>) -> Result<T>
+where
+    T: Decode<'r, Pg> + Type<Pg>,
+{
+    if !value.is_null() {
+        let ty = value.type_info();
+
+        if !ty.is_null() && !T::compatible(&ty) {
+            bail!("Invalid column type encountered")
+        }
+    }
+
+    Ok(T::decode(value).map_err(|_| anyhow!("Invalid column type encountered"))?)
+}
+
+fn map_column_value(col: &SGDBColumn, row: &PgRow) -> Result<SGDBRowValue> {
+    let value = row.try_get_raw(col.ordinal())?;
+
+    if value.is_null() {
+        return Ok(SGDBRowValue::Null);
+    }
+
+    // Postgres integers and floats have a fixed width, so the decoded rust type
+    // depends on the exact type and not only on the `SGDBColumnType`.
+    let type_name = value.type_info().name().to_string();
+
+    Ok(match type_name.as_str() {
+        "BOOL" => SGDBRowValue::Boolean(decode(value)?),
+        "INT2" => SGDBRowValue::Integer(decode::<i16>(value)?.into()),
+        "INT4" => SGDBRowValue::Integer(decode::<i32>(value)?.into()),
+        "INT8" => SGDBRowValue::Integer(decode(value)?),
+        "OID" => SGDBRowValue::UInteger(decode::<u32>(value)?.into()),
+        "FLOAT4" => SGDBRowValue::Double(decode::<f32>(value)?.into()),
+        "FLOAT8" => SGDBRowValue::Double(decode(value)?),
+        "NUMERIC" => SGDBRowValue::Decimal(decode(value)?),
+        "TEXT" | "VARCHAR" | "CHAR" | "BPCHAR" | "NAME" => SGDBRowValue::Text(decode(value)?),
+        "UUID" => SGDBRowValue::Uuid(decode(value)?),
+        "JSON" | "JSONB" => SGDBRowValue::Json(decode(value)?),
+        "DATE" => SGDBRowValue::Date(decode(value)?),
+        "TIME" => SGDBRowValue::Time(decode(value)?),
+        "TIMESTAMP" => SGDBRowValue::DateTime(decode(value)?),
+        "TIMESTAMPTZ" => SGDBRowValue::Timestamp(decode_timestamptz(value)?),
+        "BYTEA" => SGDBRowValue::Binary(decode(value)?),
+        _ => SGDBRowValue::Unknown {
+            error: format!("Unknown column type {}", type_name),
+        },
+    })
+}
+
+/// A `timestamptz`, whose text is in the time zone of the session. sqlx reads it as if the
+/// session was in UTC, it's only right for the binary values of the prepared statements.
+fn decode_timestamptz(value: PgValueRef<'_>) -> Result<DateTime<Utc>> {
+    // The binary values aren't UTF-8 or don't parse
+    let timestamp = <&str as Decode<Pg>>::decode(value.clone())
+        .ok()
+        .and_then(|text| DateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f%#z").ok());
+
+    match timestamp {
+        Some(timestamp) => Ok(timestamp.with_timezone(&Utc)),
+        None => decode(value),
+    }
+}
+
+fn map_type_name(name: &str) -> SGDBColumnType {
+    match name {
+        "BOOL" => SGDBColumnType::Boolean,
+        "INT2" | "INT4" | "INT8" => SGDBColumnType::Integer,
+        "OID" => SGDBColumnType::UInteger,
+        "FLOAT4" | "FLOAT8" => SGDBColumnType::Double,
+        "NUMERIC" => SGDBColumnType::Decimal,
+        "TEXT" | "VARCHAR" | "CHAR" | "BPCHAR" | "NAME" => SGDBColumnType::Text,
+        "UUID" => SGDBColumnType::Uuid,
+        "JSON" | "JSONB" => SGDBColumnType::Json,
+        "DATE" => SGDBColumnType::Date,
+        "TIME" => SGDBColumnType::Time,
+        "TIMESTAMP" => SGDBColumnType::DateTime,
+        "TIMESTAMPTZ" => SGDBColumnType::Timestamp,
+        "BYTEA" => SGDBColumnType::Binary,
+
+        _ => SGDBColumnType::Unknown,
+    }
+}
+
+fn map_column(col: &PgColumn) -> SGDBColumn {
+    SGDBColumn {
+        name: col.name().to_string(),
+        ordinal: col.ordinal(),
+        r#type: map_type_name(col.type_info().name()),
+    }
+}
+
+fn referential_action(action: &str) -> String {
+    match action {
+        "r" => "RESTRICT",
+        "c" => "CASCADE",
+        "n" => "SET NULL",
+        "d" => "SET DEFAULT",
+        _ => "NO ACTION",
+    }
+    .to_string()
+}
+
+/// Decode the `pg_trigger.tgtype` bit field into the trigger timing and events.
+fn trigger_timing_event(tgtype: i32) -> (String, String) {
+    let timing = if tgtype & (1 << 1) != 0 {
+        "BEFORE"
+    } else if tgtype & (1 << 6) != 0 {
+        "INSTEAD OF"
+    } else {
+        "AFTER"
+    };
+
+    let event = [
+        (1 << 2, "INSERT"),
+        (1 << 4, "UPDATE"),
+        (1 << 3, "DELETE"),
+        (1 << 5, "TRUNCATE"),
+    ]
+    .iter()
+    .filter(|(bit, _)| tgtype & bit != 0)
+    .map(|(_, event)| *event)
+    .collect::<Vec<_>>()
+    .join(" OR ");
+
+    (timing.to_string(), event)
+}
+
+fn result_set(rows: Vec<PgRow>) -> SGDBFetchResult {
+    let num_rows = rows.len();
+
+    let mut res = rows
+        .into_iter()
+        .enumerate()
+        .fold(IndexMap::new(), |mut map, (index, row)| {
+            if index == 0 {
+                for col in row.columns() {
+                    map.entry(map_column(col)).or_insert_with(SGDBColumnValues::default);
+                }
+            }
+
+            for (col, values) in map.iter_mut() {
+                values.push(map_column_value(col, &row).unwrap_or_else(|err| {
+                    SGDBRowValue::Unknown {
+                        error: format!("{}", err),
+                    }
+                }));
+            }
+
+            map
+        });
+
+    for values in res.values_mut() {
+        values.shrink_to_fit();
+    }
+
+    SGDBFetchResult {
+        data: res,
+        num_rows,
+    }
+}
+
+async fn fetch_statement(
+    conn: &mut PgConnection,
+    statement: &str,
+    params: Option<Vec<String>>,
+) -> Result<SGDBStatementOutput> {
+    // Without parameters the statement isn't prepared, like the statements of a `psql` script
+    let mut stream = match params {
+        Some(params) => {
+            let mut query = sqlx::query(statement);
+            for param in params {
+                query = query.bind(param);
+            }
+            query.fetch_many(conn)
+        }
+        None => conn.fetch_many(statement),
+    };
+
+    let mut output = SGDBStatementOutput::default();
+    let mut rows = vec![];
+
+    while let Some(next) = stream.try_next().await? {
+        match next {
+            Either::Left(result) => {
+                output.rows_affected += result.rows_affected();
+
+                if !rows.is_empty() {
+                    output.result_sets.push(result_set(std::mem::take(&mut rows)));
+                }
+            }
+            Either::Right(row) => rows.push(row),
+        }
+    }
+
+    if !rows.is_empty() {
+        output.result_sets.push(result_set(rows));
+    }
+
+    Ok(output)
+}
+
+/// Pass the rows of the first result set of `statement` to `sink`, the other ones are skipped.
+async fn stream_statement(
+    conn: &mut PgConnection,
+    statement: &str,
+    sink: &mut dyn SGDBRowSink,
+) -> Result<u64> {
+    let mut stream = conn.fetch_many(statement);
+    let mut columns: Option<Vec<SGDBColumn>> = None;
+    let mut done = false;
+    let mut num_rows = 0;
+
+    // The stream is read to its end, leaving the connection ready for the next statement
+    while let Some(next) = stream.try_next().await? {
+        let row = match next {
+            Either::Left(_) => {
+                done |= columns.is_some();
+                continue;
+            }
+            Either::Right(_) if done => continue,
+            Either::Right(row) => row,
+        };
+
+        let columns = match &mut columns {
+            Some(columns) => columns,
+            None => {
+                let first = row.columns().iter().map(map_column).collect::<Vec<_>>();
+                sink.columns(&first)?;
+                columns.insert(first)
+            }
+        };
+
+        let values = columns
+            .iter()
+            .map(|col| {
+                map_column_value(col, &row).unwrap_or_else(|err| SGDBRowValue::Unknown {
+                    error: format!("{}", err),
+                })
+            })
+            .collect();
+
+        sink.row(values)?;
+        num_rows += 1;
+    }
+
+    sink.finish()?;
+
+    Ok(num_rows)
+}
+
+async fn execute(tx: &mut Transaction<'_, Pg>, statements: Vec<SGDBStatement>) -> Result<u64> {
+    let mut rows_affected = 0;
+
+    for statement in statements.iter() {
+        let mut query = sqlx::query(&statement.query);
+        for param in statement.params.iter().cloned() {
+            query = bind_value(query, param);
+        }
+
+        rows_affected += query.execute(&mut *tx).await?.rows_affected();
+    }
+
+    Ok(rows_affected)
+}
+
+/// The server side id of a connection, to cancel its query.
+async fn connection_id(conn: &mut PgConnection) -> Result<u64> {
+    let id: i32 = sqlx::query_scalar("SELECT pg_backend_pid()").fetch_one(conn).await?;
+
+    Ok(id as u64)
+}
+
+struct PgTransaction {
+    tx: Transaction<'static, Pg>,
+    connection_id: u64,
+    running: RunningQueries,
+}
+
+struct PgSession {
+    conn: PoolConnection<Pg>,
+    connection_id: u64,
+    running: RunningQueries,
+}
+
+#[async_trait]
+impl SGDBSession for PgSession {
+    async fn fetch_statement(
+        &mut self,
+        statement: &str,
+        params: Option<Vec<String>>,
+    ) -> Result<SGDBStatementOutput> {
+        let _running = self.running.track(self.connection_id);
+        fetch_statement(&mut self.conn, statement, params).await
+    }
+
+    async fn stream_statement(
+        &mut self,
+        statement: &str,
+        sink: &mut dyn SGDBRowSink,
+    ) -> Result<u64> {
+        let _running = self.running.track(self.connection_id);
+        stream_statement(&mut self.conn, statement, sink).await
+    }
+}
+
+/// End the savepoint of a statement, rolled back if the statement failed. A stream stopped
+/// early isn't a failure.
+async fn end_savepoint<T>(savepoint: Transaction<'_, Pg>, res: &Result<T>) -> Result<()> {
+    match res {
+        Err(err) if !err.is::<crate::db::StreamStopped>() => savepoint.rollback().await?,
+        _ => savepoint.commit().await?,
+    }
+
+    Ok(())
+}
+
+// An error aborts the whole transaction with Postgres, each statement runs in a savepoint
+#[async_trait]
+impl SGDBSession for PgTransaction {
+    async fn fetch_statement(
+        &mut self,
+        statement: &str,
+        params: Option<Vec<String>>,
+    ) -> Result<SGDBStatementOutput> {
+        let _running = self.running.track(self.connection_id);
+        let mut savepoint = sqlx::Connection::begin(&mut *self.tx).await?;
+        let res = fetch_statement(&mut savepoint, statement, params).await;
+        end_savepoint(savepoint, &res).await?;
+
+        res
+    }
+
+    async fn stream_statement(
+        &mut self,
+        statement: &str,
+        sink: &mut dyn SGDBRowSink,
+    ) -> Result<u64> {
+        let _running = self.running.track(self.connection_id);
+        let mut savepoint = sqlx::Connection::begin(&mut *self.tx).await?;
+        let res = stream_statement(&mut savepoint, statement, sink).await;
+        end_savepoint(savepoint, &res).await?;
+
+        res
+    }
+}
+
+#[async_trait]
+impl SGDBTransaction for PgTransaction {
+
+    async fn execute(&mut self, statements: Vec<SGDBStatement>) -> Result<u64> {
+        let mut savepoint = sqlx::Connection::begin(&mut *self.tx).await?;
+        let rows_affected = execute(&mut savepoint, statements).await?;
+        savepoint.commit().await?;
+
+        Ok(rows_affected)
+    }
+
+    async fn commit(self: Box<Self>) -> Result<()> {
+        Ok(self.tx.commit().await?)
+    }
+
+    async fn rollback(self: Box<Self>) -> Result<()> {
+        Ok(self.tx.rollback().await?)
+    }
+}
+
+#[async_trait]
+impl SGDB for Postgres {
+    async fn session(&self) -> Result<Box<dyn SGDBSession>> {
+        let mut conn = self.pool.acquire().await?;
+        let connection_id = connection_id(&mut conn).await?;
+
+        Ok(Box::new(PgSession {
+            conn,
+            connection_id,
+            running: self.running.clone(),
+        }))
+    }
+
+    async fn list_tables(&self) -> Result<Vec<SGDBTable>> {
+        let tables = sqlx::query(
+            r#"SELECT c.relname AS table_name,
+                CASE c.relkind
+                    WHEN 'v' THEN 'VIEW'
+                    WHEN 'm' THEN 'MATERIALIZED VIEW'
+                    WHEN 'f' THEN 'FOREIGN TABLE'
+                    ELSE 'BASE TABLE'
+                END AS table_type,
+                COALESCE(am.amname, '') AS engine,
+                GREATEST(c.reltuples, 0)::BIGINT AS table_rows
+            FROM pg_catalog.pg_class c
+            JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+            LEFT JOIN pg_catalog.pg_am am ON am.oid = c.relam
+            WHERE n.nspname = $1 AND c.relkind IN ('r', 'p', 'v', 'm', 'f')
+            ORDER BY c.relname"#,
+        )
+        .bind(&self.schema)
+        .map(|row: PgRow| {
+            let table_name: String = row.get("table_name");
+            let full_path = format!("{}.{}", &self.schema, table_name);
+            SGDBTable {
+                table_name,
+                table_type: row.get("table_type"),
+                full_path,
+                schema: self.schema.clone(),
+                engine: row.get("engine"),
+                table_rows: row.get::<i64, _>("table_rows") as u64,
+            }
+        })
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(tables)
+    }
+
+    async fn list_columns(&self, table: &str) -> Result<Vec<SGDBTableColumn>> {
+        let indexes = self.list_indexes(table).await?;
+
+        let columns = sqlx::query(
+            r#"SELECT a.attname::TEXT AS name,
+                UPPER(ty.typname::TEXT) AS type_name,
+                format_type(a.atttypid, a.atttypmod) AS column_type,
+                NOT a.attnotnull AS nullable,
+                pg_get_expr(d.adbin, d.adrelid) AS column_default,
+                a.attidentity::TEXT AS identity,
+                COALESCE(col_description(t.oid, a.attnum), '') AS comment
+            FROM pg_catalog.pg_attribute a
+            JOIN pg_catalog.pg_class t ON t.oid = a.attrelid
+            JOIN pg_catalog.pg_namespace n ON n.oid = t.relnamespace
+            JOIN pg_catalog.pg_type ty ON ty.oid = a.atttypid
+            LEFT JOIN pg_catalog.pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+            WHERE n.nspname = $1 AND t.relname = $2 AND a.attnum > 0 AND NOT a.attisdropped
+            ORDER BY a.attnum"#,
+        )
+        .bind(&self.schema)
+        .bind(table)
+        .try_map(|row: PgRow| {
+            let name: String = row.try_get("name")?;
+            let type_name: String = row.try_get("type_name")?;
+            let default: Option<String> = row.try_get("column_default")?;
+            let identity: String = row.try_get("identity")?;
+
+            let serial = default
+                .as_deref()
+                .map_or(false, |default| default.starts_with("nextval("));
+
+            let extra = match identity.as_str() {
+                "a" => "GENERATED ALWAYS AS IDENTITY",
+                "d" => "GENERATED BY DEFAULT AS IDENTITY",
+                _ if serial => "serial",
+                _ => "",
+            };
+
+            Ok(SGDBTableColumn {
+                key: SGDBColumnKey::from_indexes(&name, &indexes),
+                name,
+                column_type: row.try_get("column_type")?,
+                r#type: map_type_name(&type_name),
+                nullable: row.try_get("nullable")?,
+                default,
+                auto_increment: !extra.is_empty(),
+                extra: extra.to_string(),
+                comment: row.try_get("comment")?,
+            })
+        })
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(columns)
+    }
+
+    async fn list_indexes(&self, table: &str) -> Result<Vec<SGDBTableIndex>> {
+        let indexes = sqlx::query(
+            r#"SELECT i.relname::TEXT AS name,
+                ARRAY(
+                    SELECT COALESCE(a.attname::TEXT, '<expression>')
+                    FROM unnest(ix.indkey) WITH ORDINALITY AS k(attnum, ord)
+                    LEFT JOIN pg_catalog.pg_attribute a ON a.attrelid = ix.indrelid AND a.attnum = k.attnum
+                    ORDER BY k.ord
+                ) AS columns,
+                ix.indisunique AS is_unique,
+                ix.indisprimary AS is_primary,
+                UPPER(am.amname::TEXT) AS index_type
+            FROM pg_catalog.pg_index ix
+            JOIN pg_catalog.pg_class t ON t.oid = ix.indrelid
+            JOIN pg_catalog.pg_class i ON i.oid = ix.indexrelid
+            JOIN pg_catalog.pg_namespace n ON n.oid = t.relnamespace
+            JOIN pg_catalog.pg_am am ON am.oid = i.relam
+            WHERE n.nspname = $1 AND t.relname = $2
+            ORDER BY ix.indisprimary DESC, i.relname"#,
+        )
+        .bind(&self.schema)
+        .bind(table)
+        .try_map(|row: PgRow| {
+            Ok(SGDBTableIndex {
+                name: row.try_get("name")?,
+                columns: row.try_get("columns")?,
+                unique: row.try_get("is_unique")?,
+                primary: row.try_get("is_primary")?,
+                index_type: row.try_get("index_type")?,
+            })
+        })
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(indexes)
+    }
+
+    async fn list_foreign_keys(&self, table: &str) -> Result<Vec<SGDBTableForeignKey>> {
+        let foreign_keys = sqlx::query(
+            r#"SELECT con.conname::TEXT AS name,
+                ARRAY(
+                    SELECT a.attname::TEXT
+                    FROM unnest(con.conkey) WITH ORDINALITY AS k(attnum, ord)
+                    JOIN pg_catalog.pg_attribute a ON a.attrelid = con.conrelid AND a.attnum = k.attnum
+                    ORDER BY k.ord
+                ) AS columns,
+                rn.nspname::TEXT AS referenced_schema,
+                rt.relname::TEXT AS referenced_table,
+                ARRAY(
+                    SELECT a.attname::TEXT
+                    FROM unnest(con.confkey) WITH ORDINALITY AS k(attnum, ord)
+                    JOIN pg_catalog.pg_attribute a ON a.attrelid = con.confrelid AND a.attnum = k.attnum
+                    ORDER BY k.ord
+                ) AS referenced_columns,
+                con.confupdtype::TEXT AS on_update,
+                con.confdeltype::TEXT AS on_delete
+            FROM pg_catalog.pg_constraint con
+            JOIN pg_catalog.pg_class t ON t.oid = con.conrelid
+            JOIN pg_catalog.pg_namespace n ON n.oid = t.relnamespace
+            JOIN pg_catalog.pg_class rt ON rt.oid = con.confrelid
+            JOIN pg_catalog.pg_namespace rn ON rn.oid = rt.relnamespace
+            WHERE con.contype = 'f' AND n.nspname = $1 AND t.relname = $2
+            ORDER BY con.conname"#,
+        )
+        .bind(&self.schema)
+        .bind(table)
+        .try_map(|row: PgRow| {
+            let on_update: String = row.try_get("on_update")?;
+            let on_delete: String = row.try_get("on_delete")?;
+
+            Ok(SGDBTableForeignKey {
+                name: row.try_get("name")?,
+                columns: row.try_get("columns")?,
+                referenced_schema: row.try_get("referenced_schema")?,
+                referenced_table: row.try_get("referenced_table")?,
+                referenced_columns: row.try_get("referenced_columns")?,
+                on_update: referential_action(&on_update),
+                on_delete: referential_action(&on_delete),
+            })
+        })
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(foreign_keys)
+    }
+
+    async fn list_triggers(&self, table: &str) -> Result<Vec<SGDBTableTrigger>> {
+        let triggers = sqlx::query(
+            r#"SELECT tg.tgname::TEXT AS name, tg.tgtype::INT4 AS tgtype, pg_get_triggerdef(tg.oid, true) AS statement
+            FROM pg_catalog.pg_trigger tg
+            JOIN pg_catalog.pg_class t ON t.oid = tg.tgrelid
+            JOIN pg_catalog.pg_namespace n ON n.oid = t.relnamespace
+            WHERE NOT tg.tgisinternal AND n.nspname = $1 AND t.relname = $2
+            ORDER BY tg.tgname"#,
+        )
+        .bind(&self.schema)
+        .bind(table)
+        .try_map(|row: PgRow| {
+            let (timing, event) = trigger_timing_event(row.try_get("tgtype")?);
+
+            Ok(SGDBTableTrigger {
+                name: row.try_get("name")?,
+                timing,
+                event,
+                statement: row.try_get("statement")?,
+            })
+        })
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(triggers)
+    }
+
+    async fn execute(&self, statements: Vec<SGDBStatement>) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let rows_affected = execute(&mut tx, statements).await?;
+        tx.commit().await?;
+
+        Ok(rows_affected)
+    }
+
+    async fn begin(&self) -> Result<Box<dyn SGDBTransaction>> {
+        let mut tx = self.pool.begin().await?;
+        let connection_id = connection_id(&mut tx).await?;
+
+        Ok(Box::new(PgTransaction {
+            tx,
+            connection_id,
+            running: self.running.clone(),
+        }))
+    }
+
+    async fn cancel(&self, seq: Option<u64>) -> Result<()> {
+        // The running connections are busy, the pool gives another one
+        for id in self.running.ids(seq) {
+            sqlx::query("SELECT pg_cancel_backend($1)")
+                .bind(id as i32)
+                .execute(&self.pool)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+
+        Ok(())
+    }
+
+    async fn table_ddl(&self, table: &str) -> Result<String> {
+        let path = format!(
+            "{}.{}",
+            SGDBKind::Postgres.quote_identifier(&self.schema),
+            SGDBKind::Postgres.quote_identifier(table)
+        );
+
+        // Postgres has no `SHOW CREATE TABLE`, the statement is rebuilt from the catalog.
+        let (kind, view_definition): (String, Option<String>) = sqlx::query_as(
+            r#"SELECT c.relkind::TEXT, CASE WHEN c.relkind IN ('v', 'm') THEN pg_get_viewdef(c.oid, true) END
+            FROM pg_catalog.pg_class c WHERE c.oid = $1::regclass"#,
+        )
+        .bind(&path)
+        .fetch_one(&self.pool)
+        .await?;
+
+        if let Some(definition) = view_definition {
+            let create = if kind == "m" {
+                "CREATE MATERIALIZED VIEW"
+            } else {
+                "CREATE VIEW"
+            };
+
+            return Ok(format!("{} {} AS\n{}", create, path, definition));
+        }
+
+        let mut lines = self
+            .list_columns(table)
+            .await?
+            .into_iter()
+            .map(|col| {
+                let mut line = format!(
+                    "    {} {}",
+                    SGDBKind::Postgres.quote_identifier(&col.name),
+                    col.column_type
+                );
+                if let Some(default) = &col.default {
+                    line.push_str(&format!(" DEFAULT {}", default));
+                }
+                if !col.nullable {
+                    line.push_str(" NOT NULL");
+                }
+                if col.extra.starts_with("GENERATED") {
+                    line.push_str(&format!(" {}", col.extra));
+                }
+                line
+            })
+            .collect::<Vec<_>>();
+
+        let constraints: Vec<(String, String)> = sqlx::query_as(
+            r#"SELECT conname::TEXT, pg_get_constraintdef(oid, true)
+            FROM pg_catalog.pg_constraint
+            WHERE conrelid = $1::regclass
+            ORDER BY contype = 'p' DESC, conname"#,
+        )
+        .bind(&path)
+        .fetch_all(&self.pool)
+        .await?;
+
+        for (name, definition) in constraints {
+            lines.push(format!(
+                "    CONSTRAINT {} {}",
+                SGDBKind::Postgres.quote_identifier(&name),
+                definition
+            ));
+        }
+
+        let mut ddl = format!("CREATE TABLE {} (\n{}\n);", path, lines.join(",\n"));
+
+        // Indexes backing a constraint are already part of the constraint definitions.
+        let indexes: Vec<(String,)> = sqlx::query_as(
+            r#"SELECT pg_get_indexdef(ix.indexrelid)
+            FROM pg_catalog.pg_index ix
+            WHERE ix.indrelid = $1::regclass
+                AND NOT EXISTS (SELECT 1 FROM pg_catalog.pg_constraint con WHERE con.conindid = ix.indexrelid)
+            ORDER BY ix.indexrelid"#,
+        )
+        .bind(&path)
+        .fetch_all(&self.pool)
+        .await?;
+
+        for (definition,) in indexes {
+            ddl.push_str(&format!("\n\n{};", definition));
+        }
+
+        Ok(ddl)
+    }
+}
@@ -0,0 +1,702 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use futures_util::TryStreamExt;
+use indexmap::IndexMap;
+use sqlx::{
+    pool::PoolConnection,
+    query::Query,
+    sqlite::{
+        SqliteArguments, SqliteColumn, SqliteConnectOptions, SqliteConnection, SqliteError,
+        SqlitePoolOptions, SqliteRow, SqliteValueRef,
+    },
+    error::DatabaseError,
+    Column, Decode, Either, Executor, Row, Sqlite as Lite, SqlitePool, Transaction, Type,
+    TypeInfo, ValueRef,
+};
+
+use super::{
+    SGDBColumn, SGDBColumnKey, SGDBColumnType, SGDBColumnValues, SGDBError, SGDBFetchResult,
+    SGDBRowSink, SGDBRowValue, SGDBTable, SGDBSession, SGDBStatement, SGDBStatementOutput,
+    SGDBTableColumn, SGDBTableForeignKey, SGDBTableIndex, SGDBTableTrigger, SGDBTransaction, SGDB,
+};
+use crate::sql;
+
+pub struct Sqlite {
+    pool: SqlitePool,
+}
+
+impl Sqlite {
+    /// `path` is a database file (optionally prefixed by `sqlite:`) or `:memory:`.
+    pub async fn connect(path: &str) -> Result<Self> {
+        let path = path.trim();
+        let path = path
+            .strip_prefix("sqlite://")
+            .or_else(|| path.strip_prefix("sqlite:"))
+            .unwrap_or(path);
+
+        let pool = if path == ":memory:" {
+            // The connections share the same in-memory database, which is dropped with the last
+            // one: keep a connection open.
+            SqlitePoolOptions::new()
+                .min_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+                .connect_with(SqliteConnectOptions::from_str("sqlite::memory:")?)
+                .await?
+        } else {
+            SqlitePool::connect_with(SqliteConnectOptions::new().filename(path)).await?
+        };
+
+        Ok(Sqlite { pool })
+    }
+}
+
+/// The details of a SQLite error, its position is found from the `near "..": syntax error`
+/// errors.
+pub(super) fn error_details(err: &dyn DatabaseError, query: &str) -> Option<SGDBError> {
+    let err = err.try_downcast_ref::<SqliteError>()?;
+
+    let position = err
+        .message()
+        .strip_prefix("near \"")
+        .and_then(|near| near.split_once("\": "))
+        .and_then(|(token, _)| query.find(token));
+
+    Some(SGDBError {
+        message: err.message().to_string(),
+        code: err.code().map(|code| code.into_owned()),
+        sqlstate: None,
+        position,
+        connection: false,
+    })
+}
+
+fn bind_value<'q>(
+    query: Query<'q, Lite, SqliteArguments<'q>>,
+    value: SGDBRowValue,
+) -> Query<'q, Lite, SqliteArguments<'q>> {
+    match value {
+        SGDBRowValue::Text(v) => query.bind(v),
+        SGDBRowValue::Boolean(v) => query.bind(v),
+        SGDBRowValue::Integer(v) => query.bind(v),
+        SGDBRowValue::UInteger(v) => query.bind(v as i64),
+        SGDBRowValue::Double(v) => query.bind(v),
+        // SQLite has no decimal type, keep the exact representation
+        SGDBRowValue::Decimal(v) => query.bind(v.to_string()),
+        SGDBRowValue::DateTime(v) => query.bind(v),
+        SGDBRowValue::Timestamp(v) => query.bind(v),
+        SGDBRowValue::Date(v) => query.bind(v),
+        SGDBRowValue::Time(v) => query.bind(v),
+        SGDBRowValue::Binary(v) => query.bind(v),
+        // SQLite stores JSON and UUIDs in text
+        SGDBRowValue::Json(v) => query.bind(v.to_string()),
+        SGDBRowValue::Uuid(v) => query.bind(v.to_hyphenated().to_string()),
+        SGDBRowValue::Null | SGDBRowValue::Unknown { .. } => query.bind(None::<String>),
+    }
+}
+
+fn decode<'r, T>(value: SqliteValueRef<'r>) -> Result<T>
+where
+    T: Decode<'r, Lite> + Type<Lite>,
+{
+    if !value.is_null() {
+        let ty = value.type_info();
+
+        if !ty.is_null() && !T::compatible(&ty) {
+            bail!("Invalid column type encountered")
+        }
+    }
+
+    Ok(T::decode(value).map_err(|_| anyhow!("Invalid column type encountered"))?)
+}
+
+/// Map a SQLite type name, either the declared type affinity of a column or the storage
+/// class of a value.
+fn map_type_name(name: &str) -> SGDBColumnType {
+    match name {
+        "BOOLEAN" => SGDBColumnType::Boolean,
+        "INTEGER" => SGDBColumnType::Integer,
+        "REAL" | "NUMERIC" => SGDBColumnType::Double,
+        "TEXT" => SGDBColumnType::Text,
+        "DATE" => SGDBColumnType::Date,
+        "TIME" => SGDBColumnType::Time,
+        "DATETIME" => SGDBColumnType::DateTime,
+        "BLOB" => SGDBColumnType::Binary,
+        _ => SGDBColumnType::Unknown,
+    }
+}
+
+/// Map a declared column type with the SQLite affinity rules
+/// (<https://www.sqlite.org/datatype3.html#determination_of_column_affinity>),
+/// plus the boolean and date extensions understood by sqlx.
+fn map_declared_type(declared: &str) -> SGDBColumnType {
+    let declared = declared.to_uppercase();
+
+    match declared.as_str() {
+        "BOOLEAN" | "BOOL" => SGDBColumnType::Boolean,
+        "DATE" => SGDBColumnType::Date,
+        "TIME" => SGDBColumnType::Time,
+        "DATETIME" | "TIMESTAMP" => SGDBColumnType::DateTime,
+        _ if declared.contains("INT") => SGDBColumnType::Integer,
+        _ if declared.contains("CHAR") || declared.contains("CLOB") || declared.contains("TEXT") => {
+            SGDBColumnType::Text
+        }
+        _ if declared.contains("BLOB") || declared.is_empty() => SGDBColumnType::Binary,
+        // REAL and NUMERIC affinities
+        _ => SGDBColumnType::Double,
+    }
+}
+
+fn map_column_value(col: &SGDBColumn, row: &SqliteRow) -> Result<SGDBRowValue> {
+    let value = row.try_get_raw(col.ordinal())?;
+
+    if value.is_null() {
+        return Ok(SGDBRowValue::Null);
+    }
+
+    // SQLite is dynamically typed: any value can be stored in any column, so only booleans
+    // and dates are decoded from the column affinity, everything else from its storage class.
+    // It has no time zones either, the dates and times are wall clock ones.
+    Ok(match col.r#type {
+        SGDBColumnType::Boolean => SGDBRowValue::Boolean(decode(value)?),
+        SGDBColumnType::DateTime => SGDBRowValue::DateTime(
+            decode::<NaiveDateTime>(row.try_get_raw(col.ordinal())?)
+                .or_else(|_| decode::<NaiveDate>(value).map(|date| date.and_hms(0, 0, 0)))?,
+        ),
+        SGDBColumnType::Date => match decode::<NaiveDate>(row.try_get_raw(col.ordinal())?) {
+            Ok(date) => SGDBRowValue::Date(date),
+            Err(_) => SGDBRowValue::DateTime(decode(value)?),
+        },
+        SGDBColumnType::Time => match decode::<NaiveTime>(row.try_get_raw(col.ordinal())?) {
+            Ok(time) => SGDBRowValue::Time(time),
+            Err(_) => SGDBRowValue::Text(decode(value)?),
+        },
+        _ => match map_type_name(value.type_info().name()) {
+            SGDBColumnType::Integer => SGDBRowValue::Integer(decode(value)?),
+            SGDBColumnType::Double => SGDBRowValue::Double(decode(value)?),
+            SGDBColumnType::Text => SGDBRowValue::Text(decode(value)?),
+            SGDBColumnType::Binary => SGDBRowValue::Binary(decode(value)?),
+            _ => SGDBRowValue::Unknown {
+                error: format!("Unknown storage class {}", value.type_info().name()),
+            },
+        },
+    })
+}
+
+fn map_column(col: &SqliteColumn, rows: &[SqliteRow]) -> SGDBColumn {
+    let mut r#type = map_type_name(col.type_info().name());
+
+    // Expressions and columns without a recognized declared type have no affinity,
+    // use the storage class of the first value which isn't NULL instead.
+    if col.type_info().is_null() {
+        r#type = rows
+            .iter()
+            .filter_map(|row| row.try_get_raw(col.ordinal()).ok())
+            .find(|value| !value.is_null())
+            .map_or(SGDBColumnType::Unknown, |value| map_type_name(value.type_info().name()));
+    }
+
+    SGDBColumn {
+        name: col.name().to_string(),
+        ordinal: col.ordinal(),
+        r#type,
+    }
+}
+
+fn map_columns(rows: &[SqliteRow]) -> Vec<SGDBColumn> {
+    match rows.first() {
+        Some(first) => first.columns().iter().map(|col| map_column(col, rows)).collect(),
+        None => vec![],
+    }
+}
+
+/// Find the timing and the event of a `CREATE TRIGGER` statement, SQLite only keeps the
+/// statement itself.
+fn trigger_timing_event(sql: &str) -> (String, String) {
+    let header = sql.to_uppercase();
+    let header = header.split(" ON ").next().unwrap_or_default();
+    let words = header.split_whitespace().collect::<Vec<_>>();
+
+    let timing = if words.contains(&"INSTEAD") {
+        "INSTEAD OF"
+    } else if words.contains(&"AFTER") {
+        "AFTER"
+    } else {
+        "BEFORE"
+    };
+
+    let event = ["INSERT", "UPDATE", "DELETE"]
+        .into_iter()
+        .find(|event| words.contains(event))
+        .unwrap_or_default();
+
+    (timing.to_string(), event.to_string())
+}
+
+fn result_set(rows: Vec<SqliteRow>) -> SGDBFetchResult {
+    let num_rows = rows.len();
+
+    let mut res = map_columns(&rows)
+        .into_iter()
+        .map(|col| (col, SGDBColumnValues::default()))
+        .collect::<IndexMap<_, _>>();
+
+    for row in &rows {
+        for (col, values) in res.iter_mut() {
+            values.push(map_column_value(col, row).unwrap_or_else(|err| {
+                SGDBRowValue::Unknown {
+                    error: format!("{}", err),
+                }
+            }));
+        }
+    }
+
+    for values in res.values_mut() {
+        values.shrink_to_fit();
+    }
+
+    SGDBFetchResult {
+        data: res,
+        num_rows,
+    }
+}
+
+async fn fetch_statement(
+    conn: &mut SqliteConnection,
+    statement: &str,
+    params: Option<Vec<String>>,
+) -> Result<SGDBStatementOutput> {
+    let mut stream = match params {
+        Some(params) => {
+            let mut query = sqlx::query(statement);
+            for param in params {
+                query = query.bind(param);
+            }
+            query.fetch_many(conn)
+        }
+        None => conn.fetch_many(statement),
+    };
+
+    let mut output = SGDBStatementOutput::default();
+    let mut rows = vec![];
+
+    // The change counters of the connection are kept until the next change
+    let tokens = sql::tokenize(statement);
+    let first = tokens.iter().find(|token| !token.is_trivia());
+    let insert = first.map_or(false, |token| token.is_keyword("INSERT") || token.is_keyword("REPLACE"));
+    let change = insert || first.map_or(false, |token| token.is_keyword("UPDATE") || token.is_keyword("DELETE"));
+
+    while let Some(next) = stream.try_next().await? {
+        match next {
+            Either::Left(result) => {
+                if change {
+                    output.rows_affected += result.rows_affected();
+                }
+                if insert {
+                    output.last_insert_id = Some(result.last_insert_rowid() as u64);
+                }
+
+                if !rows.is_empty() {
+                    output.result_sets.push(result_set(std::mem::take(&mut rows)));
+                }
+            }
+            Either::Right(row) => rows.push(row),
+        }
+    }
+
+    if !rows.is_empty() {
+        output.result_sets.push(result_set(rows));
+    }
+
+    Ok(output)
+}
+
+/// The rows read at most before the columns of a stream are sent, to find the type of its
+/// expressions, see [`map_column`].
+const TYPE_SAMPLE_ROWS: usize = 100;
+
+fn send_row(sink: &mut dyn SGDBRowSink, columns: &[SGDBColumn], row: &SqliteRow) -> Result<()> {
+    let values = columns
+        .iter()
+        .map(|col| {
+            map_column_value(col, row).unwrap_or_else(|err| SGDBRowValue::Unknown {
+                error: format!("{}", err),
+            })
+        })
+        .collect();
+
+    sink.row(values)
+}
+
+/// Pass the rows of the first result set of `statement` to `sink`, the other ones are skipped.
+async fn stream_statement(
+    conn: &mut SqliteConnection,
+    statement: &str,
+    sink: &mut dyn SGDBRowSink,
+) -> Result<u64> {
+    let mut stream = conn.fetch_many(statement);
+    let mut columns: Option<Vec<SGDBColumn>> = None;
+    // The first rows, until the type of each column is known
+    let mut sample = vec![];
+    let mut done = false;
+    let mut num_rows = 0;
+
+    // The stream is read to its end, leaving the connection ready for the next statement
+    loop {
+        let next = stream.try_next().await?;
+
+        let sampled = match &next {
+            None | Some(Either::Left(_)) => !sample.is_empty(),
+            Some(Either::Right(_)) => {
+                let typed = map_columns(&sample)
+                    .iter()
+                    .all(|col| col.r#type != SGDBColumnType::Unknown);
+                !sample.is_empty() && (typed || sample.len() >= TYPE_SAMPLE_ROWS)
+            }
+        };
+        if sampled {
+            let first = map_columns(&sample);
+            sink.columns(&first)?;
+            for row in sample.drain(..) {
+                send_row(sink, &first, &row)?;
+                num_rows += 1;
+            }
+            columns = Some(first);
+        }
+
+        match next {
+            None => break,
+            Some(Either::Left(_)) => done |= columns.is_some(),
+            Some(Either::Right(_)) if done => {}
+            Some(Either::Right(row)) => match &columns {
+                Some(columns) => {
+                    send_row(sink, columns, &row)?;
+                    num_rows += 1;
+                }
+                None => sample.push(row),
+            },
+        }
+    }
+
+    sink.finish()?;
+
+    Ok(num_rows)
+}
+
+async fn execute(tx: &mut Transaction<'_, Lite>, statements: Vec<SGDBStatement>) -> Result<u64> {
+    let mut rows_affected = 0;
+
+    for statement in statements.iter() {
+        let mut query = sqlx::query(&statement.query);
+        for param in statement.params.iter().cloned() {
+            query = bind_value(query, param);
+        }
+
+        rows_affected += query.execute(&mut *tx).await?.rows_affected();
+    }
+
+    Ok(rows_affected)
+}
+
+struct SqliteTransaction(Transaction<'static, Lite>);
+
+struct SqliteSession(PoolConnection<Lite>);
+
+#[async_trait]
+impl SGDBSession for SqliteSession {
+    async fn fetch_statement(
+        &mut self,
+        statement: &str,
+        params: Option<Vec<String>>,
+    ) -> Result<SGDBStatementOutput> {
+        fetch_statement(&mut self.0, statement, params).await
+    }
+
+    async fn stream_statement(
+        &mut self,
+        statement: &str,
+        sink: &mut dyn SGDBRowSink,
+    ) -> Result<u64> {
+        stream_statement(&mut self.0, statement, sink).await
+    }
+}
+
+#[async_trait]
+impl SGDBSession for SqliteTransaction {
+    async fn fetch_statement(
+        &mut self,
+        statement: &str,
+        params: Option<Vec<String>>,
+    ) -> Result<SGDBStatementOutput> {
+        fetch_statement(&mut self.0, statement, params).await
+    }
+
+    async fn stream_statement(
+        &mut self,
+        statement: &str,
+        sink: &mut dyn SGDBRowSink,
+    ) -> Result<u64> {
+        stream_statement(&mut self.0, statement, sink).await
+    }
+}
+
+#[async_trait]
+impl SGDBTransaction for SqliteTransaction {
+
+    async fn execute(&mut self, statements: Vec<SGDBStatement>) -> Result<u64> {
+        let mut savepoint = sqlx::Connection::begin(&mut *self.0).await?;
+        let rows_affected = execute(&mut savepoint, statements).await?;
+        savepoint.commit().await?;
+
+        Ok(rows_affected)
+    }
+
+    async fn commit(self: Box<Self>) -> Result<()> {
+        Ok(self.0.commit().await?)
+    }
+
+    async fn rollback(self: Box<Self>) -> Result<()> {
+        Ok(self.0.rollback().await?)
+    }
+}
+
+#[async_trait]
+impl SGDB for Sqlite {
+    async fn session(&self) -> Result<Box<dyn SGDBSession>> {
+        Ok(Box::new(SqliteSession(self.pool.acquire().await?)))
+    }
+
+    async fn list_tables(&self) -> Result<Vec<SGDBTable>> {
+        let tables = sqlx::query(
+            "SELECT name, type FROM sqlite_master WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%' ORDER BY name",
+        )
+        .map(|row: SqliteRow| {
+            let table_name: String = row.get("name");
+            let table_type: String = row.get("type");
+            SGDBTable {
+                full_path: format!("main.{}", table_name),
+                table_name,
+                table_type: if table_type == "view" {
+                    "VIEW".to_string()
+                } else {
+                    "BASE TABLE".to_string()
+                },
+                schema: "main".to_string(),
+                engine: String::new(),
+                table_rows: 0,
+            }
+        })
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(tables)
+    }
+
+    async fn list_columns(&self, table: &str) -> Result<Vec<SGDBTableColumn>> {
+        let indexes = self.list_indexes(table).await?;
+
+        let rows = sqlx::query(
+            r#"SELECT name, type, "notnull", dflt_value, pk FROM pragma_table_info(?) ORDER BY cid"#,
+        )
+        .bind(table)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let pk_count = rows
+            .iter()
+            .filter(|row| row.try_get::<i64, _>("pk").unwrap_or(0) > 0)
+            .count();
+
+        let mut columns = Vec::with_capacity(rows.len());
+        for row in rows {
+            let name: String = row.try_get("name")?;
+            let column_type: String = row.try_get("type")?;
+            let not_null: bool = row.try_get("notnull")?;
+            let pk: i64 = row.try_get("pk")?;
+
+            // A single `INTEGER PRIMARY KEY` column is an alias of the rowid.
+            let auto_increment = pk > 0 && pk_count == 1 && column_type.eq_ignore_ascii_case("INTEGER");
+
+            columns.push(SGDBTableColumn {
+                key: if pk > 0 {
+                    SGDBColumnKey::Primary
+                } else {
+                    SGDBColumnKey::from_indexes(&name, &indexes)
+                },
+                name,
+                r#type: map_declared_type(&column_type),
+                column_type,
+                nullable: !not_null,
+                default: row.try_get("dflt_value")?,
+                auto_increment,
+                extra: if auto_increment {
+                    "rowid".to_string()
+                } else {
+                    String::new()
+                },
+                comment: String::new(),
+            });
+        }
+
+        Ok(columns)
+    }
+
+    async fn list_indexes(&self, table: &str) -> Result<Vec<SGDBTableIndex>> {
+        let rows = sqlx::query(r#"SELECT name, "unique", origin FROM pragma_index_list(?) ORDER BY seq"#)
+            .bind(table)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut indexes = Vec::with_capacity(rows.len());
+        for row in rows {
+            let name: String = row.try_get("name")?;
+            let origin: String = row.try_get("origin")?;
+
+            let columns = sqlx::query(r#"SELECT name FROM pragma_index_info(?) ORDER BY seqno"#)
+                .bind(&name)
+                .try_map(|row: SqliteRow| {
+                    Ok(row
+                        .try_get::<Option<String>, _>("name")?
+                        .unwrap_or_else(|| "<expression>".to_string()))
+                })
+                .fetch_all(&self.pool)
+                .await?;
+
+            indexes.push(SGDBTableIndex {
+                name,
+                columns,
+                unique: row.try_get("unique")?,
+                primary: origin == "pk",
+                index_type: "BTREE".to_string(),
+            });
+        }
+
+        // A rowid primary key has no index of its own.
+        if !indexes.iter().any(|index| index.primary) {
+            let columns = sqlx::query(r#"SELECT name FROM pragma_table_info(?) WHERE pk > 0 ORDER BY pk"#)
+                .bind(table)
+                .try_map(|row: SqliteRow| row.try_get::<String, _>("name"))
+                .fetch_all(&self.pool)
+                .await?;
+
+            if !columns.is_empty() {
+                indexes.insert(
+                    0,
+                    SGDBTableIndex {
+                        name: "PRIMARY".to_string(),
+                        columns,
+                        unique: true,
+                        primary: true,
+                        index_type: "ROWID".to_string(),
+                    },
+                );
+            }
+        }
+
+        Ok(indexes)
+    }
+
+    async fn list_foreign_keys(&self, table: &str) -> Result<Vec<SGDBTableForeignKey>> {
+        let rows = sqlx::query(
+            r#"SELECT id, "table", "from", "to", on_update, on_delete FROM pragma_foreign_key_list(?) ORDER BY id, seq"#,
+        )
+        .bind(table)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut foreign_keys: IndexMap<i64, SGDBTableForeignKey> = IndexMap::new();
+        for row in rows {
+            let id: i64 = row.try_get("id")?;
+
+            let foreign_key = match foreign_keys.entry(id) {
+                indexmap::map::Entry::Occupied(entry) => entry.into_mut(),
+                indexmap::map::Entry::Vacant(entry) => entry.insert(SGDBTableForeignKey {
+                    // SQLite does not expose constraint names.
+                    name: format!("fk_{}_{}", table, id),
+                    columns: Vec::new(),
+                    referenced_schema: "main".to_string(),
+                    referenced_table: row.try_get("table")?,
+                    referenced_columns: Vec::new(),
+                    on_update: row.try_get("on_update")?,
+                    on_delete: row.try_get("on_delete")?,
+                }),
+            };
+
+            foreign_key.columns.push(row.try_get("from")?);
+            // `to` is NULL when the parent primary key is referenced implicitly.
+            foreign_key
+                .referenced_columns
+                .push(row.try_get::<Option<String>, _>("to")?.unwrap_or_default());
+        }
+
+        Ok(foreign_keys.into_values().collect())
+    }
+
+    async fn list_triggers(&self, table: &str) -> Result<Vec<SGDBTableTrigger>> {
+        let triggers = sqlx::query(
+            "SELECT name, sql FROM sqlite_master WHERE type = 'trigger' AND tbl_name = ? ORDER BY name",
+        )
+        .bind(table)
+        .try_map(|row: SqliteRow| {
+            let statement: String = row.try_get("sql")?;
+            let (timing, event) = trigger_timing_event(&statement);
+
+            Ok(SGDBTableTrigger {
+                name: row.try_get("name")?,
+                timing,
+                event,
+                statement,
+            })
+        })
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(triggers)
+    }
+
+    async fn execute(&self, statements: Vec<SGDBStatement>) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let rows_affected = execute(&mut tx, statements).await?;
+        tx.commit().await?;
+
+        Ok(rows_affected)
+    }
+
+    async fn begin(&self) -> Result<Box<dyn SGDBTransaction>> {
+        Ok(Box::new(SqliteTransaction(self.pool.begin().await?)))
+    }
+
+    async fn cancel(&self, _seq: Option<u64>) -> Result<()> {
+        bail!("SQLite queries can't be cancelled")
+    }
+
+    async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+
+        Ok(())
+    }
+
+    async fn table_ddl(&self, table: &str) -> Result<String> {
+        let statements = sqlx::query(
+            "SELECT sql FROM sqlite_master WHERE tbl_name = ? AND sql IS NOT NULL ORDER BY CASE type WHEN 'index' THEN 1 WHEN 'trigger' THEN 2 ELSE 0 END, name",
+        )
+        .bind(table)
+        .try_map(|row: SqliteRow| row.try_get::<String, _>("sql"))
+        .fetch_all(&self.pool)
+        .await?;
+
+        if statements.is_empty() {
+            bail!("Table {} not found", table);
+        }
+
+        Ok(statements
+            .into_iter()
+            .map(|statement| format!("{};", statement))
+            .collect::<Vec<_>>()
+            .join("\n\n"))
+    }
+}
@@ -0,0 +1,135 @@
+use crate::{
+    db::sgdb::{SGDBKind, SGDBRowValue},
+    meta::{CellSelection, FetchResult},
+};
+
+use super::json;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CopyFormat {
+    /// Tab separated values, as shown in the table, to paste into a spreadsheet.
+    Tsv,
+    /// An array of objects.
+    Json,
+    /// The distinct values as an SQL list, `(1, 2, 3)`, for an `IN` condition.
+    InList,
+    /// An `INSERT` statement per row.
+    Insert,
+}
+
+impl CopyFormat {
+    pub fn all() -> impl ExactSizeIterator<Item = Self> {
+        [Self::Tsv, Self::Json, Self::InList, Self::Insert].iter().copied()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Tsv => "Copy",
+            Self::Json => "Copy as JSON",
+            Self::InList => "Copy as SQL IN list",
+            Self::Insert => "Copy as INSERT statements",
+        }
+    }
+}
+
+/// The selected cells of `res` in `format`, `None` if nothing is selected. `rows` are the indexes
+/// of the rows shown. The SQL is in the dialect of `kind` and the statements insert into `table`.
+pub fn copy_selection(
+    res: &FetchResult,
+    rows: &[usize],
+    selection: &CellSelection,
+    format: CopyFormat,
+    kind: SGDBKind,
+    table: &str,
+) -> Option<String> {
+    let (positions, columns) = selection.ranges(rows.len(), res.res.len())?;
+    let rows = positions.map(|position| rows[position]);
+    let columns = columns
+        .map(|index| res.res.get_index(index).unwrap())
+        .collect::<Vec<_>>();
+
+    let text = match format {
+        CopyFormat::Tsv => rows
+            .map(|row| {
+                columns
+                    .iter()
+                    .map(|(col, values)| tsv_field(col.display_text(&values.get(row)).unwrap_or_default()))
+                    .collect::<Vec<_>>()
+                    .join("\t")
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        CopyFormat::Json => {
+            let objects = rows
+                .map(|row| {
+                    let fields = columns
+                        .iter()
+                        .map(|(col, values)| {
+                            let name = serde_json::Value::String(col.raw_name.clone());
+                            format!("{}:{}", name, json(&values.get(row)))
+                        })
+                        .collect::<Vec<_>>();
+
+                    format!("{{{}}}", fields.join(","))
+                })
+                .collect::<Vec<_>>();
+
+            format!("[\n{}\n]", objects.join(",\n"))
+        }
+        CopyFormat::InList => {
+            let mut literals: Vec<String> = vec![];
+
+            // NULL never matches an IN condition
+            for row in rows {
+                for (_, values) in &columns {
+                    let value = values.get(row);
+                    if let SGDBRowValue::Null | SGDBRowValue::Unknown { .. } = value {
+                        continue;
+                    }
+
+                    let literal = kind.literal(&value);
+                    if !literals.contains(&literal) {
+                        literals.push(literal);
+                    }
+                }
+            }
+
+            format!("({})", literals.join(", "))
+        }
+        CopyFormat::Insert => {
+            let names = columns
+                .iter()
+                .map(|(col, _)| kind.quote_identifier(&col.raw_name))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            rows.map(|row| {
+                let values = columns
+                    .iter()
+                    .map(|(_, values)| kind.literal(&values.get(row)))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                format!(
+                    "INSERT INTO {} ({}) VALUES ({});",
+                    kind.quote_path(table),
+                    names,
+                    values
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+        }
+    };
+
+    Some(text)
+}
+
+/// Quote a field holding a tab, a line break or a quote, like the spreadsheets do.
+fn tsv_field(field: String) -> String {
+    if field.contains(|c| matches!(c, '\t' | '\n' | '\r' | '"')) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
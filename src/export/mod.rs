@@ -0,0 +1,464 @@
+pub mod copy;
+mod xlsx;
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use chrono_tz::Tz;
+use directories::UserDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::sgdb::{SGDBColumn, SGDBKind, SGDBRowSink, SGDBRowValue},
+    meta::FetchResult,
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExportFormat {
+    Csv,
+    /// An array of objects.
+    Json,
+    /// An object per line.
+    Ndjson,
+    /// `INSERT` statements.
+    Sql,
+    Markdown,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub fn all() -> impl ExactSizeIterator<Item = Self> {
+        [
+            Self::Csv,
+            Self::Json,
+            Self::Ndjson,
+            Self::Sql,
+            Self::Markdown,
+            Self::Xlsx,
+        ]
+        .iter()
+        .copied()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Csv => "CSV",
+            Self::Json => "JSON",
+            Self::Ndjson => "NDJSON",
+            Self::Sql => "SQL INSERT",
+            Self::Markdown => "Markdown",
+            Self::Xlsx => "XLSX",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::Ndjson => "ndjson",
+            Self::Sql => "sql",
+            Self::Markdown => "md",
+            Self::Xlsx => "xlsx",
+        }
+    }
+}
+
+/// Which CSV fields are quoted, `NULL` is always an empty unquoted field.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CsvQuoting {
+    /// The fields containing the delimiter, a quote or a line break, and the empty strings.
+    Necessary,
+    /// Every field but the numbers and booleans.
+    NonNumeric,
+    All,
+}
+
+impl CsvQuoting {
+    pub fn all() -> impl ExactSizeIterator<Item = Self> {
+        [Self::Necessary, Self::NonNumeric, Self::All].iter().copied()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Necessary => "When necessary",
+            Self::NonNumeric => "Non numeric fields",
+            Self::All => "All fields",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    pub csv_delimiter: char,
+    pub csv_quoting: CsvQuoting,
+    pub csv_header: bool,
+    /// The table of the `INSERT` statements.
+    pub sql_table: String,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::Csv,
+            csv_delimiter: ',',
+            csv_quoting: CsvQuoting::Necessary,
+            csv_header: true,
+            sql_table: String::new(),
+        }
+    }
+}
+
+/// A file named after `name` in the download directory of the user, or else the working one.
+pub fn default_path(name: &str, format: ExportFormat) -> String {
+    let dir = UserDirs::new()
+        .and_then(|dirs| dirs.download_dir().map(Path::to_path_buf))
+        .unwrap_or_default();
+
+    dir.join(format!("{}.{}", name, format.extension()))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// The cells of an SQL export written as `NULL` because the dialect has no literal for their
+/// value, e.g. `row 3, column ratio: NaN`. They're added as the rows are written.
+pub type ExportWarnings = Arc<Mutex<Vec<String>>>;
+
+/// Writes the rows of a result set to a file in one of the [`ExportFormat`]s.
+///
+/// The values are written as they are received, it's the sink of a streamed query.
+pub struct Exporter {
+    path: PathBuf,
+    writer: Box<dyn FormatWriter>,
+    warnings: ExportWarnings,
+}
+
+impl std::fmt::Debug for Exporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Exporter").field("path", &self.path).finish()
+    }
+}
+
+impl Exporter {
+    /// Create the file at `path`, the statements of an SQL export are in the dialect of `kind`.
+    /// The timestamps of a spreadsheet are written in `time_zone`, UTC if not set.
+    pub fn create(
+        path: impl AsRef<Path>,
+        kind: SGDBKind,
+        time_zone: Option<Tz>,
+        options: &ExportOptions,
+    ) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let out = BufWriter::new(File::create(&path)?);
+        let warnings = ExportWarnings::default();
+
+        let writer: Box<dyn FormatWriter> = match options.format {
+            ExportFormat::Csv => Box::new(CsvWriter {
+                out,
+                delimiter: options.csv_delimiter,
+                quoting: options.csv_quoting,
+                header: options.csv_header,
+            }),
+            ExportFormat::Json => Box::new(JsonWriter {
+                out,
+                columns: vec![],
+                lines: false,
+                rows: 0,
+            }),
+            ExportFormat::Ndjson => Box::new(JsonWriter {
+                out,
+                columns: vec![],
+                lines: true,
+                rows: 0,
+            }),
+            ExportFormat::Sql => Box::new(SqlWriter {
+                out,
+                kind,
+                table: kind.quote_path(&options.sql_table),
+                names: vec![],
+                columns: String::new(),
+                rows: 0,
+                warnings: warnings.clone(),
+            }),
+            ExportFormat::Markdown => Box::new(MarkdownWriter { out }),
+            ExportFormat::Xlsx => Box::new(xlsx::XlsxWriter::new(out, time_zone.unwrap_or(Tz::UTC))?),
+        };
+
+        Ok(Self { path, writer, warnings })
+    }
+
+    /// The cells written so far without their value.
+    pub fn warnings(&self) -> ExportWarnings {
+        self.warnings.clone()
+    }
+}
+
+impl SGDBRowSink for Exporter {
+    fn columns(&mut self, columns: &[SGDBColumn]) -> Result<()> {
+        let names = columns.iter().map(|col| col.name().to_string()).collect::<Vec<_>>();
+        self.writer.header(&names)
+    }
+
+    fn row(&mut self, row: Vec<SGDBRowValue>) -> Result<()> {
+        self.writer.row(&row)
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.writer.finish()
+    }
+}
+
+/// Write the rows of `res` with `exporter`, returns the number of rows.
+pub fn export_result(res: &FetchResult, exporter: &mut Exporter) -> Result<u64> {
+    let names = res.res.keys().map(|col| col.raw_name.clone()).collect::<Vec<_>>();
+    exporter.writer.header(&names)?;
+
+    for row in 0..res.num_rows {
+        let values = res.res.values().map(|values| values.get(row)).collect::<Vec<_>>();
+        exporter.writer.row(&values)?;
+    }
+
+    exporter.writer.finish()?;
+
+    Ok(res.num_rows as u64)
+}
+
+/// A file format, [`FormatWriter::header`] isn't called for a statement without rows.
+trait FormatWriter: Send {
+    fn header(&mut self, columns: &[String]) -> Result<()>;
+
+    fn row(&mut self, row: &[SGDBRowValue]) -> Result<()>;
+
+    fn finish(&mut self) -> Result<()>;
+}
+
+struct CsvWriter {
+    out: BufWriter<File>,
+    delimiter: char,
+    quoting: CsvQuoting,
+    header: bool,
+}
+
+impl CsvWriter {
+    fn write_record(&mut self, fields: impl Iterator<Item = (Option<String>, bool)>) -> Result<()> {
+        for (index, (field, numeric)) in fields.enumerate() {
+            if index > 0 {
+                write!(self.out, "{}", self.delimiter)?;
+            }
+
+            let field = match field {
+                Some(field) => field,
+                None => continue,
+            };
+
+            let quote = match self.quoting {
+                CsvQuoting::All => true,
+                CsvQuoting::NonNumeric if !numeric => true,
+                _ => {
+                    field.is_empty()
+                        || field.contains(|c| c == self.delimiter || matches!(c, '"' | '\r' | '\n'))
+                }
+            };
+
+            if quote {
+                write!(self.out, "\"{}\"", field.replace('"', "\"\""))?;
+            } else {
+                self.out.write_all(field.as_bytes())?;
+            }
+        }
+
+        self.out.write_all(b"\r\n")?;
+
+        Ok(())
+    }
+}
+
+impl FormatWriter for CsvWriter {
+    fn header(&mut self, columns: &[String]) -> Result<()> {
+        if !self.header {
+            return Ok(());
+        }
+
+        self.write_record(columns.iter().map(|name| (Some(name.clone()), false)))
+    }
+
+    fn row(&mut self, row: &[SGDBRowValue]) -> Result<()> {
+        self.write_record(row.iter().map(|value| {
+            let numeric = matches!(
+                value,
+                SGDBRowValue::Boolean(_)
+                    | SGDBRowValue::Integer(_)
+                    | SGDBRowValue::UInteger(_)
+                    | SGDBRowValue::Double(_)
+                    | SGDBRowValue::Decimal(_)
+            );
+            (value.text(), numeric)
+        }))
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        Ok(self.out.flush()?)
+    }
+}
+
+struct JsonWriter {
+    out: BufWriter<File>,
+    /// The JSON strings of the column names.
+    columns: Vec<String>,
+    /// One object per line without the enclosing array, NDJSON.
+    lines: bool,
+    rows: u64,
+}
+
+/// The JSON of a value, the decimals are strings to keep their precision. The JSON values are
+/// written as they are.
+fn json(value: &SGDBRowValue) -> String {
+    match value {
+        SGDBRowValue::Json(value) => value.to_string(),
+        SGDBRowValue::Boolean(value) => format!("{}", value),
+        SGDBRowValue::Integer(value) => format!("{}", value),
+        SGDBRowValue::UInteger(value) => format!("{}", value),
+        SGDBRowValue::Double(value) if value.is_finite() => format!("{:?}", value),
+        value => match value.text() {
+            Some(text) => serde_json::Value::String(text).to_string(),
+            None => "null".to_string(),
+        },
+    }
+}
+
+impl FormatWriter for JsonWriter {
+    fn header(&mut self, columns: &[String]) -> Result<()> {
+        self.columns = columns
+            .iter()
+            .map(|name| serde_json::Value::String(name.clone()).to_string())
+            .collect();
+
+        Ok(())
+    }
+
+    fn row(&mut self, row: &[SGDBRowValue]) -> Result<()> {
+        if !self.lines {
+            self.out.write_all(if self.rows == 0 { b"[\n" } else { b",\n" })?;
+        }
+        self.rows += 1;
+
+        let fields = self
+            .columns
+            .iter()
+            .zip(row)
+            .map(|(name, value)| format!("{}:{}", name, json(value)))
+            .collect::<Vec<_>>();
+        write!(self.out, "{{{}}}", fields.join(","))?;
+
+        if self.lines {
+            self.out.write_all(b"\n")?;
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if !self.lines {
+            self.out.write_all(if self.rows == 0 { b"[]\n" } else { b"\n]\n" })?;
+        }
+
+        Ok(self.out.flush()?)
+    }
+}
+
+struct SqlWriter {
+    out: BufWriter<File>,
+    kind: SGDBKind,
+    /// The quoted target table.
+    table: String,
+    names: Vec<String>,
+    /// The quoted columns, separated by commas.
+    columns: String,
+    rows: u64,
+    warnings: ExportWarnings,
+}
+
+impl FormatWriter for SqlWriter {
+    fn header(&mut self, columns: &[String]) -> Result<()> {
+        self.names = columns.to_vec();
+        self.columns = columns
+            .iter()
+            .map(|name| self.kind.quote_identifier(name))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        Ok(())
+    }
+
+    fn row(&mut self, row: &[SGDBRowValue]) -> Result<()> {
+        self.rows += 1;
+        for (name, value) in self.names.iter().zip(row) {
+            if !self.kind.has_literal(value) {
+                self.warnings.lock().unwrap().push(format!(
+                    "row {}, column {}: {}",
+                    self.rows,
+                    name,
+                    value.text().unwrap_or_default()
+                ));
+            }
+        }
+
+        let values = row
+            .iter()
+            .map(|value| self.kind.literal(value))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        writeln!(
+            self.out,
+            "INSERT INTO {} ({}) VALUES ({});",
+            self.table, self.columns, values
+        )?;
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        Ok(self.out.flush()?)
+    }
+}
+
+struct MarkdownWriter {
+    out: BufWriter<File>,
+}
+
+impl MarkdownWriter {
+    fn write_cells(&mut self, cells: impl Iterator<Item = String>) -> Result<()> {
+        for cell in cells {
+            let cell = cell
+                .replace('\\', "\\\\")
+                .replace('|', "\\|")
+                .replace("\r\n", "<br>")
+                .replace('\n', "<br>");
+            write!(self.out, "| {} ", cell)?;
+        }
+
+        self.out.write_all(b"|\n")?;
+
+        Ok(())
+    }
+}
+
+impl FormatWriter for MarkdownWriter {
+    fn header(&mut self, columns: &[String]) -> Result<()> {
+        self.write_cells(columns.iter().cloned())?;
+        self.write_cells(columns.iter().map(|_| "---".to_string()))
+    }
+
+    fn row(&mut self, row: &[SGDBRowValue]) -> Result<()> {
+        self.write_cells(row.iter().map(|value| value.text().unwrap_or_default()))
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        Ok(self.out.flush()?)
+    }
+}
@@ -0,0 +1,298 @@
+//! A minimal XLSX writer: a workbook of a single sheet, written as a zip archive whose sheet
+//! is compressed while the rows are received.
+
+use std::io::{Seek, Write};
+
+use anyhow::{bail, Result};
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, Timelike};
+use chrono_tz::Tz;
+use zip::{write::FileOptions, CompressionMethod, DateTime, ZipWriter};
+
+use crate::db::sgdb::SGDBRowValue;
+
+use super::FormatWriter;
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/></Types>"#;
+
+const RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#;
+
+const WORKBOOK: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="Result" sheetId="1" r:id="rId1"/></sheets></workbook>"#;
+
+const WORKBOOK_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/></Relationships>"#;
+
+/// The cell format 1 shows the dates, the header uses the bold format 2.
+const STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><numFmts count="1"><numFmt numFmtId="164" formatCode="yyyy-mm-dd hh:mm:ss"/></numFmts><fonts count="2"><font><sz val="11"/><name val="Calibri"/></font><font><b/><sz val="11"/><name val="Calibri"/></font></fonts><fills count="2"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill></fills><borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders><cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs><cellXfs count="3"><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/><xf numFmtId="164" fontId="0" fillId="0" borderId="0" xfId="0" applyNumberFormat="1"/><xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/></cellXfs></styleSheet>"#;
+
+const SHEET_START: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>"#;
+
+const SHEET_END: &str = "</sheetData></worksheet>";
+
+/// The number of rows of a sheet in the spreadsheets, a larger one can't be opened.
+const MAX_ROWS: u32 = 1_048_576;
+
+pub struct XlsxWriter<W: Write + Seek> {
+    zip: ZipWriter<W>,
+    /// The time zone the timestamps are written in, the spreadsheets have none.
+    time_zone: Tz,
+    /// The number of rows written, with the header.
+    rows: u32,
+}
+
+impl<W: Write + Seek + Send> XlsxWriter<W> {
+    /// Write the parts of the workbook and start its sheet.
+    pub fn new(out: W, time_zone: Tz) -> Result<Self> {
+        let mut zip = ZipWriter::new(out);
+
+        let now = Local::now().naive_local();
+        let modified = DateTime::from_date_and_time(
+            now.year().clamp(1980, 2107) as u16,
+            now.month() as u8,
+            now.day() as u8,
+            now.hour() as u8,
+            now.minute() as u8,
+            now.second().min(59) as u8,
+        )
+        .unwrap_or_default();
+        let options = FileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .last_modified_time(modified);
+
+        for (name, content) in [
+            ("[Content_Types].xml", CONTENT_TYPES),
+            ("_rels/.rels", RELS),
+            ("xl/workbook.xml", WORKBOOK),
+            ("xl/_rels/workbook.xml.rels", WORKBOOK_RELS),
+            ("xl/styles.xml", STYLES),
+        ] {
+            zip.start_file(name, options)?;
+            zip.write_all(content.as_bytes())?;
+        }
+
+        // The sheet may be larger than the 4 GiB of a zip entry without the zip64 extension
+        zip.start_file("xl/worksheets/sheet1.xml", options.large_file(true))?;
+        zip.write_all(SHEET_START.as_bytes())?;
+
+        Ok(Self {
+            zip,
+            time_zone,
+            rows: 0,
+        })
+    }
+}
+
+impl<W: Write + Seek + Send> FormatWriter for XlsxWriter<W> {
+    fn header(&mut self, columns: &[String]) -> Result<()> {
+        self.rows += 1;
+
+        let mut row = format!(r#"<row r="{}">"#, self.rows);
+        for (index, name) in columns.iter().enumerate() {
+            row.push_str(&format!(
+                r#"<c r="{}" t="inlineStr" s="2"><is><t xml:space="preserve">{}</t></is></c>"#,
+                reference(index, self.rows),
+                escape(name)
+            ));
+        }
+        row.push_str("</row>");
+
+        Ok(self.zip.write_all(row.as_bytes())?)
+    }
+
+    fn row(&mut self, values: &[SGDBRowValue]) -> Result<()> {
+        if self.rows == MAX_ROWS {
+            bail!(
+                "A sheet is limited to {} rows, export the rows to CSV instead",
+                MAX_ROWS
+            );
+        }
+        self.rows += 1;
+
+        let mut row = format!(r#"<row r="{}">"#, self.rows);
+        for (index, value) in values.iter().enumerate() {
+            row.push_str(&cell(value, &reference(index, self.rows), self.time_zone));
+        }
+        row.push_str("</row>");
+
+        Ok(self.zip.write_all(row.as_bytes())?)
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.zip.write_all(SHEET_END.as_bytes())?;
+        self.zip.finish()?.flush()?;
+
+        Ok(())
+    }
+}
+
+/// The reference of a cell, like `B3` for the second column of the third row.
+fn reference(column: usize, row: u32) -> String {
+    let mut letters = vec![];
+    let mut column = column + 1;
+
+    while column > 0 {
+        letters.push(b'A' + ((column - 1) % 26) as u8);
+        column = (column - 1) / 26;
+    }
+    letters.reverse();
+
+    format!("{}{}", String::from_utf8(letters).unwrap(), row)
+}
+
+/// The cell of a value, empty for `NULL`. The numbers are limited to the precision of a
+/// double in the spreadsheets, the decimals and the big integers may be rounded.
+fn cell(value: &SGDBRowValue, reference: &str, time_zone: Tz) -> String {
+    match value {
+        SGDBRowValue::Boolean(value) => {
+            format!(r#"<c r="{}" t="b"><v>{}</v></c>"#, reference, *value as u8)
+        }
+        SGDBRowValue::Integer(_) | SGDBRowValue::UInteger(_) | SGDBRowValue::Decimal(_) => {
+            format!(r#"<c r="{}"><v>{}</v></c>"#, reference, value.text().unwrap())
+        }
+        SGDBRowValue::Double(value) if value.is_finite() => {
+            format!(r#"<c r="{}"><v>{:?}</v></c>"#, reference, value)
+        }
+        SGDBRowValue::DateTime(value) => date_cell(*value, reference),
+        // The spreadsheets have no time zones, the timestamps are written as they're shown
+        SGDBRowValue::Timestamp(value) => {
+            date_cell(value.with_timezone(&time_zone).naive_local(), reference)
+        }
+        value => match value.text() {
+            Some(text) => format!(
+                r#"<c r="{}" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+                reference,
+                escape(&text)
+            ),
+            None => String::new(),
+        },
+    }
+}
+
+/// The cell of a date, the dates are days since 1899-12-30 in the spreadsheets.
+fn date_cell(value: NaiveDateTime, reference: &str) -> String {
+    let epoch = NaiveDate::from_ymd(1899, 12, 30).and_hms(0, 0, 0);
+    let duration = value - epoch;
+    let days = duration.num_milliseconds() as f64 / 86_400_000.;
+
+    format!(r#"<c r="{}" s="1"><v>{}</v></c>"#, reference, days)
+}
+
+/// Escape the XML markup of `text`, dropping the control characters XML can't represent.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            '\u{0}'..='\u{1f}' => {}
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use chrono::{TimeZone, Utc};
+    use zip::ZipArchive;
+
+    use super::*;
+
+    /// The entries of the archive written by `writer`, by name. Reading them checks their
+    /// checksums and sizes.
+    fn entries(writer: XlsxWriter<Cursor<Vec<u8>>>) -> Vec<(String, String)> {
+        let mut zip = writer.zip;
+        let out = zip.finish().unwrap();
+        let mut archive = ZipArchive::new(out).unwrap();
+
+        (0..archive.len())
+            .map(|index| {
+                let mut entry = archive.by_index(index).unwrap();
+                let mut content = String::new();
+                entry.read_to_string(&mut content).unwrap();
+                (entry.name().to_string(), content)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn write_a_workbook() {
+        let mut writer = XlsxWriter::new(Cursor::new(vec![]), Tz::UTC).unwrap();
+        writer.header(&["id".to_string(), "name".to_string()]).unwrap();
+        writer
+            .row(&[SGDBRowValue::Integer(1), SGDBRowValue::Text("a < b".to_string())])
+            .unwrap();
+        writer.row(&[SGDBRowValue::Null, SGDBRowValue::Boolean(true)]).unwrap();
+        writer.zip.write_all(SHEET_END.as_bytes()).unwrap();
+
+        let entries = entries(writer);
+        let names = entries.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "[Content_Types].xml",
+                "_rels/.rels",
+                "xl/workbook.xml",
+                "xl/_rels/workbook.xml.rels",
+                "xl/styles.xml",
+                "xl/worksheets/sheet1.xml",
+            ]
+        );
+        assert_eq!(entries[4].1, STYLES);
+        assert_eq!(
+            entries[5].1,
+            format!(
+                "{}{}{}{}",
+                SHEET_START,
+                r#"<row r="1"><c r="A1" t="inlineStr" s="2"><is><t xml:space="preserve">id</t></is></c><c r="B1" t="inlineStr" s="2"><is><t xml:space="preserve">name</t></is></c></row>"#,
+                r#"<row r="2"><c r="A2"><v>1</v></c><c r="B2" t="inlineStr"><is><t xml:space="preserve">a &lt; b</t></is></c></row><row r="3"><c r="B3" t="b"><v>1</v></c></row>"#,
+                SHEET_END
+            )
+        );
+    }
+
+    #[test]
+    fn write_the_timestamps_in_the_time_zone() {
+        let timestamp = SGDBRowValue::Timestamp(Utc.ymd(2022, 7, 1).and_hms(22, 0, 0));
+
+        // 2022-07-02 00:00 in Paris, 44744 days after 1899-12-30
+        assert_eq!(
+            cell(&timestamp, "A1", chrono_tz::Europe::Paris),
+            r#"<c r="A1" s="1"><v>44744</v></c>"#
+        );
+        assert_eq!(
+            cell(&timestamp, "A1", Tz::UTC),
+            r#"<c r="A1" s="1"><v>44743.916666666664</v></c>"#
+        );
+    }
+
+    #[test]
+    fn refuse_the_rows_past_the_limit() {
+        let mut writer = XlsxWriter::new(Cursor::new(vec![]), Tz::UTC).unwrap();
+        writer.rows = MAX_ROWS - 1;
+
+        writer.row(&[SGDBRowValue::Integer(1)]).unwrap();
+        assert!(writer.row(&[SGDBRowValue::Integer(2)]).is_err());
+        assert_eq!(writer.rows, MAX_ROWS);
+    }
+
+    #[test]
+    fn cell_references() {
+        assert_eq!(reference(0, 1), "A1");
+        assert_eq!(reference(25, 2), "Z2");
+        assert_eq!(reference(26, 3), "AA3");
+        assert_eq!(reference(701, 4), "ZZ4");
+        assert_eq!(reference(702, 5), "AAA5");
+    }
+}
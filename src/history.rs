@@ -0,0 +1,130 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{self, SqlifeConfig},
+    db::sgdb::SGDBError,
+    meta::ScriptResult,
+};
+
+/// The number of entries kept per connection, the oldest ones are dropped first.
+const MAX_ENTRIES: usize = 1000;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub query: String,
+    pub executed_at: DateTime<Utc>,
+    pub duration: Duration,
+    /// The number of rows fetched.
+    pub rows: Option<usize>,
+    /// Why the query failed, `None` if it succeeded.
+    pub error: Option<String>,
+}
+
+/// A query waiting for its result to be added to the history.
+pub struct PendingEntry {
+    query: String,
+    executed_at: DateTime<Utc>,
+    started: Instant,
+}
+
+impl PendingEntry {
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            executed_at: Utc::now(),
+            started: Instant::now(),
+        }
+    }
+
+    /// The entry of the query once it returned `res`, the error of a script is the one of the
+    /// statement which failed.
+    pub fn finish_with(self, res: &Result<ScriptResult, SGDBError>) -> HistoryEntry {
+        let (rows, error) = match res {
+            Ok(res) => (
+                Some(res.num_rows()),
+                res.error.as_ref().map(|err| err.message.clone()),
+            ),
+            Err(err) => (None, Some(err.message.clone())),
+        };
+
+        HistoryEntry {
+            query: self.query,
+            executed_at: self.executed_at,
+            duration: self.started.elapsed(),
+            rows,
+            error,
+        }
+    }
+}
+
+/// The queries executed on each connection, by connection name.
+#[derive(Serialize, Deserialize, Default)]
+pub struct QueryHistory {
+    connections: IndexMap<String, Vec<HistoryEntry>>,
+    /// When the first entry not saved yet was added.
+    #[serde(skip)]
+    changed: Option<Instant>,
+}
+
+impl QueryHistory {
+    fn file_path() -> PathBuf {
+        SqlifeConfig::config_file_path().with_file_name("history.json")
+    }
+
+    pub fn load() -> Result<Self> {
+        config::load_json(Self::file_path())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        config::save_json(Self::file_path(), self, false)
+    }
+
+    /// Save the entries added [`config::SAVE_DELAY`] ago or more, or right away if `now`.
+    pub fn save_changes(&mut self, now: bool) {
+        match self.changed {
+            Some(changed) if now || changed.elapsed() >= config::SAVE_DELAY => {}
+            _ => return,
+        }
+
+        self.changed = None;
+        if let Err(err) = self.save() {
+            warn!("Can't save the query history: {}", err);
+        }
+    }
+
+    /// Add an entry to the history of `connection`, it's saved by [`QueryHistory::save_changes`].
+    pub fn push(&mut self, connection: &str, entry: HistoryEntry) {
+        let entries = self.connections.entry(connection.to_string()).or_default();
+
+        entries.push(entry);
+        if entries.len() > MAX_ENTRIES {
+            entries.drain(..entries.len() - MAX_ENTRIES);
+        }
+
+        self.changed.get_or_insert_with(Instant::now);
+    }
+
+    /// The entries of `connection` containing `search`, the most recent first.
+    pub fn search<'a>(
+        &'a self,
+        connection: &str,
+        search: &str,
+    ) -> impl Iterator<Item = &'a HistoryEntry> {
+        let search = search.to_lowercase();
+
+        self.connections
+            .get(connection)
+            .into_iter()
+            .flat_map(|entries| entries.iter().rev())
+            .filter(move |entry| entry.query.to_lowercase().contains(&search))
+    }
+}
@@ -3,8 +3,12 @@
 mod app;
 mod config;
 mod db;
+mod export;
+mod history;
 mod meta;
+mod sql;
 mod ui;
+mod workspace;
 
 use app::Sqlife;
 use config::{ConnectionConfig, SqlifeConfig};
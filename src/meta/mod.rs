@@ -1,9 +1,19 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
 use egui::{Key, Modifiers};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    db::sgdb::{SGDBColumnType, SGDBRowValue},
+    db::sgdb::{
+        SGDBColumnType, SGDBColumnValues, SGDBError, SGDBFetchResult, SGDBKind, SGDBRowValue,
+        SGDBScriptResult, SGDBStatement, SGDBTableColumn,
+    },
     ui::components::icons,
 };
 
@@ -105,7 +115,13 @@ pub enum MetaColumnType {
     Text { color: Option<(u8, u8, u8)> },
     CheckBox,
     Number { variant: MetaColNumber },
-    DateTime { format: String },
+    /// The timestamps are converted to `time_zone`, UTC if not set, the other dates and times
+    /// are shown as they are.
+    DateTime {
+        format: String,
+        #[serde(default)]
+        time_zone: Option<Tz>,
+    },
     Image(ImageType),
     Binary,
     Unknown,
@@ -143,10 +159,20 @@ impl MetaColumn {
             SGDBColumnType::Decimal => MetaColumnType::Number {
                 variant: MetaColNumber::Simple,
             },
-            SGDBColumnType::DateTime => MetaColumnType::DateTime {
+            SGDBColumnType::DateTime | SGDBColumnType::Timestamp => MetaColumnType::DateTime {
                 format: "%d/%m/%Y %H:%M:%S".to_string(),
+                time_zone: None,
+            },
+            SGDBColumnType::Date => MetaColumnType::DateTime {
+                format: "%d/%m/%Y".to_string(),
+                time_zone: None,
+            },
+            SGDBColumnType::Time => MetaColumnType::DateTime {
+                format: "%H:%M:%S".to_string(),
+                time_zone: None,
             },
             SGDBColumnType::Binary => MetaColumnType::Binary,
+            SGDBColumnType::Json | SGDBColumnType::Uuid => MetaColumnType::Text { color: None },
             SGDBColumnType::Unknown => MetaColumnType::Unknown,
         };
 
@@ -160,7 +186,660 @@ impl MetaColumn {
     }
 }
 
+impl MetaColumn {
+    /// The text of a value as shown in the tables, `None` for `NULL`.
+    pub fn display_text(&self, value: &SGDBRowValue) -> Option<String> {
+        let text = match (&self.r#type, value) {
+            (MetaColumnType::DateTime { format, .. }, SGDBRowValue::DateTime(value)) => {
+                value.format(format).to_string()
+            }
+            (MetaColumnType::DateTime { format, time_zone }, SGDBRowValue::Timestamp(value)) => {
+                let time_zone = time_zone.unwrap_or(Tz::UTC);
+                value.with_timezone(&time_zone).format(format).to_string()
+            }
+            // Formatted as a date time, a format may have fields they don't have
+            (MetaColumnType::DateTime { format, .. }, SGDBRowValue::Date(value)) => {
+                value.and_hms(0, 0, 0).format(format).to_string()
+            }
+            (MetaColumnType::DateTime { format, .. }, SGDBRowValue::Time(value)) => {
+                NaiveDate::from_ymd(1970, 1, 1).and_time(*value).format(format).to_string()
+            }
+            (_, SGDBRowValue::Double(value)) => value.to_string(),
+            (_, value) => value.text()?,
+        };
+
+        let number = matches!(
+            value,
+            SGDBRowValue::Integer(_)
+                | SGDBRowValue::UInteger(_)
+                | SGDBRowValue::Double(_)
+                | SGDBRowValue::Decimal(_)
+        );
+
+        match &self.r#type {
+            MetaColumnType::Number {
+                variant: MetaColNumber::Money,
+            } if number => Some(format!("{:2}€", text)),
+            _ => Some(text),
+        }
+    }
+}
+
 pub struct FetchResult {
     pub num_rows: usize,
-    pub res: IndexMap<MetaColumn, Vec<SGDBRowValue>>
+    pub res: IndexMap<MetaColumn, SGDBColumnValues>
+}
+
+/// The values of the columns are moved, not copied.
+impl From<SGDBFetchResult> for FetchResult {
+    fn from(res: SGDBFetchResult) -> Self {
+        let results = res
+            .data
+            .into_iter()
+            .map(|(col, values)| {
+                let meta_col = MetaColumn::default_sgdb_column(col.name(), col.r#type());
+
+                (meta_col, values)
+            })
+            .collect();
+
+        FetchResult {
+            num_rows: res.num_rows,
+            res: results,
+        }
+    }
+}
+
+impl FetchResult {
+    /// Show the timestamps in `time_zone`, in the columns which don't have a time zone yet.
+    pub fn set_time_zone(&mut self, time_zone: Tz) {
+        self.res = std::mem::take(&mut self.res)
+            .into_iter()
+            .map(|(mut col, values)| {
+                if let MetaColumnType::DateTime { time_zone: zone @ None, .. } = &mut col.r#type {
+                    *zone = Some(time_zone);
+                }
+                (col, values)
+            })
+            .collect();
+    }
+
+    /// Add the rows of `other`, a result with the same columns.
+    pub fn append(&mut self, other: FetchResult) {
+        self.num_rows += other.num_rows;
+
+        for ((_, values), (_, rows)) in self.res.iter_mut().zip(other.res) {
+            values.append(rows);
+        }
+    }
+
+    /// The values of the `key` columns of a row.
+    pub fn row_key(&self, row: usize, key: &[String]) -> Vec<(String, SGDBRowValue)> {
+        key.iter()
+            .filter_map(|name| {
+                let (_, values) = self.res.iter().find(|(col, _)| &col.raw_name == name)?;
+                Some((name.clone(), values.get(row)))
+            })
+            .collect()
+    }
+}
+
+/// What a statement of a script returned.
+pub struct StatementStatus {
+    pub query: String,
+    /// The number of rows of its result sets.
+    pub rows: usize,
+    /// The number of result sets it returned.
+    pub result_sets: usize,
+    pub rows_affected: u64,
+    pub last_insert_id: Option<u64>,
+    pub duration: Duration,
+}
+
+/// The results of the statements of a script.
+pub struct ScriptResult {
+    pub statements: Vec<StatementStatus>,
+    /// The result sets of all the statements, in order.
+    pub result_sets: Vec<FetchResult>,
+    /// The error of the statement which stopped the script.
+    pub error: Option<SGDBError>,
+}
+
+impl ScriptResult {
+    /// The result, or its error if no statement could be run.
+    pub fn from_sgdb(res: SGDBScriptResult) -> Result<Self, SGDBError> {
+        let error = match res.error {
+            Some(err) if res.statements.is_empty() => return Err(err),
+            error => error,
+        };
+
+        let mut script = ScriptResult {
+            statements: vec![],
+            result_sets: vec![],
+            error,
+        };
+
+        for statement in res.statements {
+            let output = statement.output;
+
+            script.statements.push(StatementStatus {
+                query: statement.query,
+                rows: output.result_sets.iter().map(|res| res.num_rows).sum(),
+                result_sets: output.result_sets.len(),
+                rows_affected: output.rows_affected,
+                last_insert_id: output.last_insert_id,
+                duration: statement.duration,
+            });
+            script
+                .result_sets
+                .extend(output.result_sets.into_iter().map(FetchResult::from));
+        }
+
+        Ok(script)
+    }
+
+    /// The result of a single `query` whose rows are streamed, from their first batch if they
+    /// have any columns. The next ones are added with [`ScriptResult::append`].
+    pub fn streamed(query: String, first: Option<FetchResult>) -> Self {
+        ScriptResult {
+            statements: vec![StatementStatus {
+                query,
+                rows: first.as_ref().map_or(0, |res| res.num_rows),
+                result_sets: first.iter().count(),
+                rows_affected: 0,
+                last_insert_id: None,
+                duration: Duration::ZERO,
+            }],
+            result_sets: first.into_iter().collect(),
+            error: None,
+        }
+    }
+
+    /// The statement which returned the result set at `index`, and the index of the result set
+    /// among the ones of the statement.
+    pub fn statement_of(&self, index: usize) -> Option<(&StatementStatus, usize)> {
+        let mut first = 0;
+
+        for statement in &self.statements {
+            if index < first + statement.result_sets {
+                return Some((statement, index - first));
+            }
+            first += statement.result_sets;
+        }
+
+        None
+    }
+
+    /// Add the rows of `more` to the result set of a single statement, like the next rows of a
+    /// table.
+    pub fn append(&mut self, more: FetchResult) {
+        if let Some(statement) = self.statements.first_mut() {
+            statement.rows += more.num_rows;
+        }
+
+        if let Some(res) = self.result_sets.first_mut() {
+            res.append(more);
+        }
+    }
+
+    /// The number of rows of all the result sets.
+    pub fn num_rows(&self) -> usize {
+        self.statements.iter().map(|statement| statement.rows).sum()
+    }
+}
+
+/// The cells selected in a [`FetchResult`], from the first one clicked to the last one: a block
+/// of cells, whole rows or whole columns. The rows are positions among the rows shown, see
+/// [`RowView`].
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CellSelection {
+    #[default]
+    None,
+    /// The (row, column) of the first and the last cell.
+    Cells {
+        anchor: (usize, usize),
+        end: (usize, usize),
+    },
+    Rows {
+        anchor: usize,
+        end: usize,
+    },
+    Columns {
+        anchor: usize,
+        end: usize,
+    },
+}
+
+impl CellSelection {
+    /// The selected rows and columns of a result of `num_rows` and `num_columns`.
+    pub fn ranges(
+        &self,
+        num_rows: usize,
+        num_columns: usize,
+    ) -> Option<(RangeInclusive<usize>, RangeInclusive<usize>)> {
+        let ordered = |a: usize, b: usize| a.min(b)..=a.max(b);
+
+        let (rows, columns) = match *self {
+            CellSelection::None => return None,
+            CellSelection::Cells { anchor, end } => {
+                (ordered(anchor.0, end.0), ordered(anchor.1, end.1))
+            }
+            CellSelection::Rows { anchor, end } => {
+                (ordered(anchor, end), 0..=num_columns.checked_sub(1)?)
+            }
+            CellSelection::Columns { anchor, end } => {
+                (0..=num_rows.checked_sub(1)?, ordered(anchor, end))
+            }
+        };
+
+        // The result may have fewer rows than when the selection was made
+        (*rows.end() < num_rows && *columns.end() < num_columns).then(|| (rows, columns))
+    }
+
+    pub fn contains(&self, row: usize, column: usize) -> bool {
+        match *self {
+            CellSelection::None => false,
+            CellSelection::Cells { anchor, end } => {
+                (anchor.0.min(end.0)..=anchor.0.max(end.0)).contains(&row)
+                    && (anchor.1.min(end.1)..=anchor.1.max(end.1)).contains(&column)
+            }
+            CellSelection::Rows { anchor, end } => (anchor.min(end)..=anchor.max(end)).contains(&row),
+            CellSelection::Columns { anchor, end } => {
+                (anchor.min(end)..=anchor.max(end)).contains(&column)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+/// A condition on the values of a column, its operands are parsed as values of the column.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ColumnFilter {
+    Equals(String),
+    /// The text of the value contains the operand, ignoring the case.
+    Contains(String),
+    /// Between the bounds, included, an empty bound is open.
+    Range { min: String, max: String },
+    IsNull,
+    IsNotNull,
+}
+
+impl ColumnFilter {
+    /// A filter of each kind, without operand.
+    pub fn all() -> [Self; 5] {
+        [
+            Self::Equals(String::new()),
+            Self::Contains(String::new()),
+            Self::Range {
+                min: String::new(),
+                max: String::new(),
+            },
+            Self::IsNull,
+            Self::IsNotNull,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Equals(_) => "Equals",
+            Self::Contains(_) => "Contains",
+            Self::Range { .. } => "Between",
+            Self::IsNull => "Is null",
+            Self::IsNotNull => "Is not null",
+        }
+    }
+
+    /// Whether `value` of `col` passes the filter, an operand which can't be parsed matches no
+    /// value.
+    pub fn matches(&self, col: &MetaColumn, value: &SGDBRowValue) -> bool {
+        let null = matches!(value, SGDBRowValue::Null | SGDBRowValue::Unknown { .. });
+        let compare = |operand: &str| {
+            value
+                .parse_like(operand)
+                .map(|operand| value.compare(&operand))
+        };
+
+        match self {
+            Self::IsNull => null,
+            Self::IsNotNull => !null,
+            _ if null => false,
+            Self::Equals(operand) => compare(operand) == Some(Ordering::Equal),
+            Self::Contains(operand) => col
+                .display_text(value)
+                .map_or(false, |text| text.to_lowercase().contains(&operand.to_lowercase())),
+            Self::Range { min, max } => {
+                let above = min.trim().is_empty()
+                    || matches!(compare(min), Some(Ordering::Greater | Ordering::Equal));
+                let below = max.trim().is_empty()
+                    || matches!(compare(max), Some(Ordering::Less | Ordering::Equal));
+                above && below
+            }
+        }
+    }
+}
+
+/// How the rows of a [`FetchResult`] are shown: sorted on a column, filtered by conditions on
+/// the values of the columns and by a text searched in all of them. The columns are indexes.
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct RowView {
+    pub sort: Option<(usize, SortOrder)>,
+    pub filters: BTreeMap<usize, ColumnFilter>,
+    pub search: String,
+}
+
+impl RowView {
+    /// Whether all the rows are shown in their order.
+    pub fn is_empty(&self) -> bool {
+        self.sort.is_none() && self.filters.is_empty() && self.search.trim().is_empty()
+    }
+
+    /// Sort on `column` in ascending order, then in descending order, then not anymore.
+    pub fn toggle_sort(&mut self, column: usize) {
+        self.sort = match self.sort {
+            Some((sorted, SortOrder::Ascending)) if sorted == column => {
+                Some((column, SortOrder::Descending))
+            }
+            Some((sorted, SortOrder::Descending)) if sorted == column => None,
+            _ => Some((column, SortOrder::Ascending)),
+        };
+    }
+
+    /// The indexes of the rows of `res` shown, in order. The columns `res` doesn't have are
+    /// ignored.
+    pub fn rows(&self, res: &FetchResult) -> Vec<usize> {
+        let filters = self
+            .filters
+            .iter()
+            .filter_map(|(col, filter)| Some((res.res.get_index(*col)?, filter)))
+            .collect::<Vec<_>>();
+        let search = self.search.trim().to_lowercase();
+
+        let mut rows = (0..res.num_rows)
+            .filter(|row| {
+                filters
+                    .iter()
+                    .all(|((col, values), filter)| filter.matches(col, &values.get(*row)))
+            })
+            .filter(|row| {
+                search.is_empty()
+                    || res.res.iter().any(|(col, values)| {
+                        col.display_text(&values.get(*row))
+                            .map_or(false, |text| text.to_lowercase().contains(&search))
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        if let Some((col, order)) = self.sort {
+            if let Some((_, values)) = res.res.get_index(col) {
+                rows.sort_by(|a, b| {
+                    let ordering = values.compare(*a, *b);
+                    match order {
+                        SortOrder::Ascending => ordering,
+                        SortOrder::Descending => ordering.reverse(),
+                    }
+                });
+            }
+        }
+
+        rows
+    }
+}
+
+/// The rows shown by a [`RowView`], computed again only when the view changes.
+#[derive(Default)]
+pub struct ShownRows(Option<(RowView, Vec<usize>)>);
+
+impl ShownRows {
+    pub fn get(&mut self, view: &RowView, res: &FetchResult) -> &[usize] {
+        if !self.is_current(view) {
+            self.0 = Some((view.clone(), view.rows(res)));
+        }
+
+        &self.0.as_ref().unwrap().1
+    }
+
+    /// Whether the rows were computed for `view`.
+    pub fn is_current(&self, view: &RowView) -> bool {
+        matches!(&self.0, Some((computed, _)) if computed == view)
+    }
+
+    /// Compute the rows again, once the result changed.
+    pub fn clear(&mut self) {
+        self.0 = None;
+    }
+}
+
+/// Pending changes of the rows of a [`FetchResult`], indexed by row and column index.
+#[derive(Default)]
+pub struct RowEdits {
+    /// The row whose cells are being edited.
+    pub editing: Option<usize>,
+    pub rows: IndexMap<usize, IndexMap<usize, SGDBRowValue>>,
+}
+
+impl RowEdits {
+    pub fn value(&self, row: usize, col: usize) -> Option<&SGDBRowValue> {
+        self.rows.get(&row).and_then(|cells| cells.get(&col))
+    }
+
+    /// Set the pending value of a cell, a value equal to the `original` one is no longer pending.
+    pub fn set(&mut self, row: usize, col: usize, value: SGDBRowValue, original: &SGDBRowValue) {
+        if &value == original {
+            if let Some(cells) = self.rows.get_mut(&row) {
+                cells.remove(&col);
+                if cells.is_empty() {
+                    self.rows.remove(&row);
+                }
+            }
+        } else {
+            self.rows.entry(row).or_default().insert(col, value);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.editing = None;
+        self.rows.clear();
+    }
+
+    /// One `UPDATE` per edited row of `table`, rows are matched on their original `key` values.
+    pub fn statements(
+        &self,
+        kind: SGDBKind,
+        table: &str,
+        columns: &[SGDBTableColumn],
+        res: &FetchResult,
+        key: &[String],
+    ) -> Vec<SGDBStatement> {
+        self.rows
+            .iter()
+            .map(|(row, cells)| {
+                let set = cells
+                    .iter()
+                    .filter_map(|(col, value)| {
+                        let (col, _) = res.res.get_index(*col)?;
+                        Some((col.raw_name.clone(), value.clone()))
+                    })
+                    .collect();
+
+                SGDBStatement::update(kind, table, columns, set, res.row_key(*row, key))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A result of the columns `id`, `name` and `score`.
+    fn result() -> FetchResult {
+        let rows = [
+            (1, Some("Alice"), SGDBRowValue::Double(2.5)),
+            (2, Some("bob"), SGDBRowValue::Null),
+            (3, None, SGDBRowValue::Integer(10)),
+            (4, Some("ALINE"), SGDBRowValue::Double(2.5)),
+            (5, Some("Carl"), SGDBRowValue::Integer(-1)),
+        ];
+
+        let mut columns = [
+            ("id", SGDBColumnType::Integer, SGDBColumnValues::default()),
+            ("name", SGDBColumnType::Text, SGDBColumnValues::default()),
+            ("score", SGDBColumnType::Double, SGDBColumnValues::default()),
+        ];
+        let num_rows = rows.len();
+        for (id, name, score) in rows {
+            columns[0].2.push(SGDBRowValue::Integer(id));
+            columns[1]
+                .2
+                .push(name.map_or(SGDBRowValue::Null, |name| SGDBRowValue::Text(name.to_string())));
+            columns[2].2.push(score);
+        }
+
+        FetchResult {
+            num_rows,
+            res: columns
+                .into_iter()
+                .map(|(name, r#type, values)| (MetaColumn::default_sgdb_column(name, r#type), values))
+                .collect(),
+        }
+    }
+
+    fn filtered(column: usize, filter: ColumnFilter) -> Vec<usize> {
+        let view = RowView {
+            filters: BTreeMap::from([(column, filter)]),
+            ..Default::default()
+        };
+        view.rows(&result())
+    }
+
+    fn sorted(column: usize, order: SortOrder) -> Vec<usize> {
+        let view = RowView {
+            sort: Some((column, order)),
+            ..Default::default()
+        };
+        view.rows(&result())
+    }
+
+    fn searched(search: &str) -> Vec<usize> {
+        let view = RowView {
+            search: search.to_string(),
+            ..Default::default()
+        };
+        view.rows(&result())
+    }
+
+    #[test]
+    fn filter_on_the_values_of_the_column() {
+        assert_eq!(filtered(0, ColumnFilter::Equals(" 2 ".to_string())), [1]);
+        assert_eq!(filtered(2, ColumnFilter::Equals("2.50".to_string())), [0, 3]);
+        assert_eq!(filtered(1, ColumnFilter::Contains("LI".to_string())), [0, 3]);
+        assert_eq!(filtered(1, ColumnFilter::IsNull), [2]);
+        assert_eq!(filtered(2, ColumnFilter::IsNotNull), [0, 2, 3, 4]);
+    }
+
+    #[test]
+    fn filter_on_a_range_with_open_bounds() {
+        let range = |min: &str, max: &str| ColumnFilter::Range {
+            min: min.to_string(),
+            max: max.to_string(),
+        };
+
+        // The bounds are included, in the numbers of any type
+        assert_eq!(filtered(2, range("-1", "2.5")), [0, 3, 4]);
+        assert_eq!(filtered(2, range("2.6", "")), [2]);
+        assert_eq!(filtered(2, range(" ", "0")), [4]);
+        // Without bounds the filter only drops the `NULL`s
+        assert_eq!(filtered(2, range("", "")), [0, 2, 3, 4]);
+        assert_eq!(filtered(2, range("3", "2")), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn filter_matches_nothing_on_an_invalid_operand_or_a_null() {
+        assert!(filtered(0, ColumnFilter::Equals("two".to_string())).is_empty());
+        assert!(filtered(0, ColumnFilter::Equals(String::new())).is_empty());
+        assert_eq!(
+            filtered(
+                0,
+                ColumnFilter::Range {
+                    min: "one".to_string(),
+                    max: String::new()
+                }
+            ),
+            Vec::<usize>::new()
+        );
+        // The `NULL` of the name isn't the text "null"
+        assert!(filtered(1, ColumnFilter::Contains("null".to_string())).is_empty());
+        // An empty text is contained in all the values but the `NULL`s
+        assert_eq!(filtered(1, ColumnFilter::Contains(String::new())), [0, 1, 3, 4]);
+    }
+
+    #[test]
+    fn filters_and_sort_on_missing_columns_are_ignored() {
+        let view = RowView {
+            sort: Some((3, SortOrder::Descending)),
+            filters: BTreeMap::from([(3, ColumnFilter::IsNull)]),
+            search: String::new(),
+        };
+
+        assert_eq!(view.rows(&result()), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn sort_keeps_the_order_of_the_equal_values() {
+        assert_eq!(sorted(2, SortOrder::Ascending), [1, 4, 0, 3, 2]);
+        assert_eq!(sorted(2, SortOrder::Descending), [2, 0, 3, 4, 1]);
+        assert_eq!(sorted(1, SortOrder::Ascending), [2, 3, 0, 4, 1]);
+    }
+
+    #[test]
+    fn toggle_the_sort_order() {
+        let mut view = RowView::default();
+
+        view.toggle_sort(1);
+        assert_eq!(view.sort, Some((1, SortOrder::Ascending)));
+        view.toggle_sort(1);
+        assert_eq!(view.sort, Some((1, SortOrder::Descending)));
+        view.toggle_sort(2);
+        assert_eq!(view.sort, Some((2, SortOrder::Ascending)));
+        view.toggle_sort(2);
+        view.toggle_sort(2);
+        assert_eq!(view.sort, None);
+        assert!(view.is_empty());
+    }
+
+    #[test]
+    fn search_in_all_the_columns() {
+        assert_eq!(searched(" ALI "), [0, 3]);
+        assert_eq!(searched("2.5"), [0, 3]);
+        assert_eq!(searched("1"), [0, 2, 4]);
+        assert!(searched("null").is_empty());
+        assert_eq!(searched("  "), [0, 1, 2, 3, 4]);
+        assert!(RowView {
+            search: "  ".to_string(),
+            ..Default::default()
+        }
+        .is_empty());
+    }
+
+    #[test]
+    fn shown_rows_are_computed_again_when_the_view_changes() {
+        let res = result();
+        let mut shown = ShownRows::default();
+        let mut view = RowView::default();
+
+        assert_eq!(shown.get(&view, &res), [0, 1, 2, 3, 4]);
+        assert!(shown.is_current(&view));
+
+        view.search = "b".to_string();
+        assert!(!shown.is_current(&view));
+        assert_eq!(shown.get(&view, &res), [1]);
+
+        shown.clear();
+        assert!(!shown.is_current(&view));
+    }
 }
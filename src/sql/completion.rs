@@ -0,0 +1,217 @@
+use std::ops::Range;
+
+use crate::db::sgdb::SGDBKind;
+
+use super::{statement_at, tokenize, Token, TokenKind};
+
+const KEYWORDS: &[&str] = &[
+    "ADD", "ALL", "ALTER", "AND", "AS", "ASC", "BEGIN", "BETWEEN", "BY", "CASCADE", "CASE",
+    "CHECK", "COLUMN", "COMMIT", "CONSTRAINT", "CREATE", "CROSS", "DATABASE", "DEFAULT", "DELETE",
+    "DESC", "DISTINCT", "DROP", "ELSE", "END", "EXCEPT", "EXISTS", "EXPLAIN", "FALSE", "FOREIGN",
+    "FROM", "FULL", "GROUP", "HAVING", "IN", "INDEX", "INNER", "INSERT", "INTERSECT", "INTO", "IS",
+    "JOIN", "KEY", "LEFT", "LIKE", "LIMIT", "NOT", "NULL", "OFFSET", "ON", "OR", "ORDER", "OUTER",
+    "PRIMARY", "REFERENCES", "RETURNING", "RIGHT", "ROLLBACK", "SELECT", "SET", "TABLE", "THEN",
+    "TRIGGER", "TRUE", "TRUNCATE", "UNION", "UNIQUE", "UPDATE", "USING", "VALUES", "VIEW", "WHEN",
+    "WHERE", "WITH",
+];
+
+const COMMON_FUNCTIONS: &[&str] = &[
+    "ABS", "AVG", "CAST", "COALESCE", "COUNT", "LENGTH", "LOWER", "MAX", "MIN", "NULLIF", "REPLACE",
+    "ROUND", "SUBSTR", "SUM", "TRIM", "UPPER",
+];
+
+const MYSQL_FUNCTIONS: &[&str] = &[
+    "CONCAT", "CONCAT_WS", "CURDATE", "DATE_ADD", "DATE_FORMAT", "DATE_SUB", "DATEDIFF",
+    "FROM_UNIXTIME", "GROUP_CONCAT", "IF", "IFNULL", "JSON_ARRAY", "JSON_EXTRACT", "JSON_OBJECT",
+    "LAST_INSERT_ID", "LOCATE", "NOW", "STR_TO_DATE", "SUBSTRING_INDEX", "UNIX_TIMESTAMP", "UUID",
+];
+
+const POSTGRES_FUNCTIONS: &[&str] = &[
+    "ARRAY_AGG", "CONCAT", "CURRENT_DATE", "CURRENT_TIMESTAMP", "DATE_PART", "DATE_TRUNC",
+    "EXTRACT", "GENERATE_SERIES", "GEN_RANDOM_UUID", "JSON_AGG", "JSONB_AGG",
+    "JSONB_BUILD_OBJECT", "NOW", "REGEXP_REPLACE", "ROW_NUMBER", "STRING_AGG", "TO_CHAR",
+    "TO_DATE", "TO_TIMESTAMP", "UNNEST",
+];
+
+const SQLITE_FUNCTIONS: &[&str] = &[
+    "DATE", "DATETIME", "GLOB", "GROUP_CONCAT", "HEX", "IFNULL", "IIF", "INSTR", "JSON_EXTRACT",
+    "JSON_OBJECT", "JULIANDAY", "LAST_INSERT_ROWID", "PRINTF", "RANDOM", "STRFTIME", "TIME",
+    "TOTAL", "TYPEOF",
+];
+
+/// The functions suggested for a database.
+pub fn functions(kind: SGDBKind) -> impl Iterator<Item = &'static str> {
+    let specific = match kind {
+        SGDBKind::Mysql => MYSQL_FUNCTIONS,
+        SGDBKind::Postgres => POSTGRES_FUNCTIONS,
+        SGDBKind::Sqlite => SQLITE_FUNCTIONS,
+    };
+
+    COMMON_FUNCTIONS.iter().chain(specific).copied()
+}
+
+pub fn keywords() -> impl Iterator<Item = &'static str> {
+    KEYWORDS.iter().copied()
+}
+
+/// A table referenced in a statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableRef {
+    /// The table name, without its schema.
+    pub name: String,
+    pub alias: Option<String>,
+}
+
+/// What is being typed at the cursor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletionContext {
+    /// The byte range of the partial word before the cursor, replaced by the completion.
+    pub word: Range<usize>,
+    /// The table or alias before the `.` preceding the word.
+    pub qualifier: Option<String>,
+    /// Whether a table name is expected, after `FROM`, `JOIN`, `INTO`..
+    pub expects_table: bool,
+    /// The tables referenced in the statement at the cursor.
+    pub tables: Vec<TableRef>,
+}
+
+impl CompletionContext {
+    /// The table of a qualifier, from an alias or a table name.
+    pub fn qualified_table(&self) -> Option<&str> {
+        let qualifier = self.qualifier.as_deref()?;
+
+        let table = self.tables.iter().find(|table| {
+            table
+                .alias
+                .as_deref()
+                .map_or(false, |alias| alias.eq_ignore_ascii_case(qualifier))
+        });
+
+        Some(table.map_or(qualifier, |table| &table.name))
+    }
+}
+
+/// The completion context at the byte `offset` of `script`, `None` in strings, comments and
+/// anything else than words.
+pub fn context(script: &str, offset: usize) -> Option<CompletionContext> {
+    let tokens = tokenize(script);
+    let before = tokens.iter().take_while(|token| token.start < offset).count();
+
+    let (word, before) = match tokens[..before].split_last() {
+        Some((token, rest)) if token.kind == TokenKind::Word => (token.start..offset, rest),
+        Some((token, _))
+            if token.end() > offset && token.kind != TokenKind::Whitespace
+                || matches!(token.kind, TokenKind::Comment | TokenKind::String) =>
+        {
+            return None
+        }
+        _ => (offset..offset, &tokens[..before]),
+    };
+
+    let mut previous = before.iter().rev().filter(|token| !token.is_trivia());
+    let (qualifier, expects_table) = match previous.next() {
+        Some(token) if token.is_punctuation(".") && token.end() == word.start => {
+            (Some(previous.next()?.identifier()?), false)
+        }
+        Some(token) => (
+            None,
+            ["FROM", "JOIN", "INTO", "UPDATE", "TABLE"]
+                .iter()
+                .any(|keyword| token.is_keyword(keyword)),
+        ),
+        None => (None, false),
+    };
+
+    Some(CompletionContext {
+        word,
+        qualifier,
+        expects_table,
+        tables: statement_tables(script, offset),
+    })
+}
+
+/// The tables referenced by the statement of `script` at `offset`, in its `FROM`, `JOIN`,
+/// `UPDATE` and `INTO` clauses.
+pub fn statement_tables(script: &str, offset: usize) -> Vec<TableRef> {
+    let statement = match statement_at(script, offset) {
+        Some(statement) => statement,
+        None => return Vec::new(),
+    };
+
+    let tokens = tokenize(statement.text)
+        .into_iter()
+        .filter(|token| !token.is_trivia())
+        .collect::<Vec<_>>();
+
+    let mut tables = Vec::new();
+    let mut in_from = false;
+    let mut index = 0;
+
+    while index < tokens.len() {
+        let token = tokens[index];
+        index += 1;
+
+        let starts_table = if ["FROM", "JOIN", "UPDATE", "INTO"]
+            .iter()
+            .any(|keyword| token.is_keyword(keyword))
+        {
+            in_from = token.is_keyword("FROM") || token.is_keyword("JOIN");
+            true
+        } else if in_from && token.is_punctuation(",") {
+            true
+        } else {
+            if CLAUSE_END.iter().any(|keyword| token.is_keyword(keyword)) {
+                in_from = false;
+            }
+            false
+        };
+
+        if starts_table {
+            if let Some((table, next)) = table_ref(&tokens, index) {
+                tables.push(table);
+                index = next;
+            }
+        }
+    }
+
+    tables
+}
+
+/// The keywords ending a `FROM` clause.
+const CLAUSE_END: &[&str] = &[
+    "WHERE", "GROUP", "ORDER", "LIMIT", "HAVING", "UNION", "ON", "USING", "SET", "VALUES",
+    "WINDOW", "OFFSET", "RETURNING", "NATURAL",
+];
+
+/// The table reference starting at `tokens[index]`, a path with an optional alias, and the
+/// index of the token after it.
+fn table_ref(tokens: &[Token], mut index: usize) -> Option<(TableRef, usize)> {
+    let mut name = tokens.get(index)?.identifier()?;
+    index += 1;
+
+    while tokens.get(index).map_or(false, |token| token.is_punctuation(".")) {
+        name = tokens.get(index + 1)?.identifier()?;
+        index += 2;
+    }
+
+    if tokens.get(index).map_or(false, |token| token.is_keyword("AS")) {
+        index += 1;
+    }
+
+    let alias = tokens.get(index).filter(|token| {
+        token.kind == TokenKind::QuotedIdentifier
+            || (token.kind == TokenKind::Word
+                && !CLAUSE_END.iter().chain(KEYWORDS).any(|keyword| token.is_keyword(keyword)))
+    });
+    if alias.is_some() {
+        index += 1;
+    }
+
+    Some((
+        TableRef {
+            name,
+            alias: alias.and_then(|alias| alias.identifier()),
+        },
+        index,
+    ))
+}
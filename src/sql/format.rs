@@ -0,0 +1,552 @@
+use serde::{Deserialize, Serialize};
+
+use crate::db::sgdb::SGDBKind;
+
+use super::{completion, tokenize_dialect, Token, TokenKind};
+
+/// The settings of `format`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct FormatStyle {
+    /// The spaces of an indentation level.
+    pub indent: usize,
+    pub uppercase_keywords: bool,
+    /// Start a new line before the `AND` and `OR` of the conditions.
+    pub break_conditions: bool,
+}
+
+impl Default for FormatStyle {
+    fn default() -> Self {
+        Self {
+            indent: 4,
+            uppercase_keywords: true,
+            break_conditions: true,
+        }
+    }
+}
+
+/// The keywords starting a clause, on their own line with the clause body indented below.
+const CLAUSES: &[&str] = &[
+    "SELECT", "FROM", "WHERE", "GROUP", "ORDER", "HAVING", "LIMIT", "OFFSET", "SET", "VALUES",
+    "RETURNING", "UNION", "INTERSECT", "EXCEPT", "WINDOW",
+];
+
+/// The clauses only starting a statement, they are also parts of other clauses like
+/// `FOR UPDATE` or `WITH TIME ZONE`.
+const STATEMENT_CLAUSES: &[&str] = &["INSERT", "UPDATE", "DELETE", "REPLACE", "WITH"];
+
+/// The keywords completing the keyword of a clause, like `GROUP BY` or `UNION ALL`.
+const CLAUSE_CONTINUATIONS: &[&str] = &["BY", "ALL", "DISTINCT", "INTO", "FROM"];
+
+const JOINS: &[&str] = &["JOIN", "LEFT", "RIGHT", "INNER", "FULL", "CROSS", "NATURAL", "OUTER"];
+
+/// The keywords recognized by the formatter besides the ones of the completion.
+const KEYWORDS: &[&str] = &[
+    "ANY", "ASC", "COLLATE", "CURRENT", "FETCH", "FIRST", "FOLLOWING", "FOR", "IF", "INTERVAL",
+    "LAST", "MATERIALIZED", "NEXT", "NO", "NULLS", "ONLY", "OVER", "PARTITION", "PRECEDING",
+    "RANGE", "RECURSIVE", "RENAME", "ROW", "ROWS", "SCHEMA", "SOME", "TEMPORARY", "TO",
+    "UNBOUNDED", "WINDOW", "WITHOUT",
+];
+
+const MYSQL_KEYWORDS: &[&str] = &[
+    "AUTO_INCREMENT", "CALL", "CHARSET", "DECLARE", "DELIMITER", "DUPLICATE", "ENGINE", "FORCE",
+    "FUNCTION", "IGNORE", "LOCK", "MODIFY", "PROCEDURE", "REGEXP", "RETURNS", "SHOW",
+    "STRAIGHT_JOIN", "UNSIGNED", "USE", "ZEROFILL",
+];
+
+const POSTGRES_KEYWORDS: &[&str] = &[
+    "CONFLICT", "DO", "FUNCTION", "ILIKE", "LANGUAGE", "LATERAL", "NOTHING", "OWNER", "RETURNS",
+    "SIMILAR", "TABLESAMPLE",
+];
+
+const SQLITE_KEYWORDS: &[&str] = &[
+    "ABORT", "AUTOINCREMENT", "CONFLICT", "FAIL", "GLOB", "PRAGMA", "REINDEX", "ROWID", "VACUUM",
+];
+
+fn is_keyword(token: &Token, kind: SGDBKind) -> bool {
+    if token.kind != TokenKind::Word {
+        return false;
+    }
+
+    let dialect = match kind {
+        SGDBKind::Mysql => MYSQL_KEYWORDS,
+        SGDBKind::Postgres => POSTGRES_KEYWORDS,
+        SGDBKind::Sqlite => SQLITE_KEYWORDS,
+    };
+
+    completion::keywords()
+        .chain(KEYWORDS.iter().copied())
+        .chain(dialect.iter().copied())
+        .any(|keyword| token.is_keyword(keyword))
+}
+
+fn is_one_of(token: &Token, keywords: &[&str]) -> bool {
+    keywords.iter().any(|keyword| token.is_keyword(keyword))
+}
+
+fn is_line_comment(token: &Token) -> bool {
+    token.kind == TokenKind::Comment && !token.text.starts_with("/*")
+}
+
+/// Write the `DELIMITER` command of the MySQL client starting at `token` on its own line,
+/// returns the new delimiter.
+fn delimiter_command(sql: &str, token: &Token, kind: SGDBKind, writer: &mut Writer) -> Option<String> {
+    if kind != SGDBKind::Mysql || !token.is_keyword("DELIMITER") || !writer.at_statement_start() {
+        return None;
+    }
+
+    // The command takes the rest of the line
+    let line_end = sql[token.start..].find('\n').map_or(sql.len(), |i| token.start + i);
+    let delimiter = sql[token.end()..line_end].split_whitespace().next()?;
+
+    writer.newline(0);
+    writer.push_raw(sql[token.start..line_end].trim_end());
+    writer.skip_until(line_end);
+    writer.newline(0);
+
+    Some(delimiter.to_string())
+}
+
+/// Write the custom `delimiter` if it starts in `token`, after the part of the token before it,
+/// like the `END` of `END$$`.
+fn custom_delimiter<'a>(
+    sql: &'a str,
+    token: &Token<'a>,
+    delimiter: &str,
+    uppercase_keywords: bool,
+    kind: SGDBKind,
+    writer: &mut Writer<'a>,
+) -> bool {
+    if delimiter == ";" {
+        return false;
+    }
+
+    let found = match token.kind {
+        TokenKind::String | TokenKind::QuotedIdentifier | TokenKind::Comment => {
+            Some(token.start).filter(|at| sql[*at..].starts_with(delimiter))
+        }
+        _ => token
+            .text
+            .char_indices()
+            .map(|(i, _)| token.start + i)
+            .find(|at| sql[*at..].starts_with(delimiter)),
+    };
+    let at = match found {
+        Some(at) => at,
+        None => return false,
+    };
+
+    if at > token.start {
+        let before = Token {
+            text: &sql[token.start..at],
+            ..*token
+        };
+        if uppercase_keywords && is_keyword(&before, kind) {
+            writer.push(&before, &before.text.to_uppercase());
+        } else {
+            writer.push(&before, before.text);
+        }
+    }
+    writer.push_raw(delimiter);
+    writer.skip_until(at + delimiter.len());
+    true
+}
+
+/// Re-indent `sql`, with a line per clause and per item of the lists of a clause. The strings,
+/// quoted identifiers and comments are kept as they are.
+pub fn format(sql: &str, kind: SGDBKind, style: &FormatStyle) -> String {
+    let tokens = tokenize_dialect(sql, kind);
+    let mut writer = Writer::new(sql, style.indent);
+    let mut delimiter = ";".to_string();
+
+    /// A level of parentheses, only the sub queries are indented.
+    #[derive(Clone, Copy)]
+    struct Scope {
+        level: usize,
+        subquery: bool,
+    }
+
+    let mut scopes = vec![Scope {
+        level: 0,
+        subquery: true,
+    }];
+    // The body of a clause starts on the next line
+    let mut body_pending = false;
+    let mut between = false;
+
+    for (index, token) in tokens.iter().enumerate() {
+        if token.kind == TokenKind::Whitespace || token.start < writer.skipped {
+            continue;
+        }
+
+        let scope = *scopes.last().unwrap();
+        let text = if style.uppercase_keywords && is_keyword(token, kind) {
+            token.text.to_uppercase()
+        } else {
+            token.text.to_string()
+        };
+
+        if token.kind == TokenKind::Comment {
+            // Keep the comments on their own line
+            let own_line = index > 0 && tokens[index - 1].text.contains('\n');
+            if own_line && writer.pending_line.is_none() {
+                writer.newline(if body_pending { scope.level + 1 } else { writer.indent });
+            }
+            writer.push(token, &text);
+            if is_line_comment(token) {
+                writer.newline(writer.indent);
+            }
+            continue;
+        }
+
+        if let Some(new) = delimiter_command(sql, token, kind, &mut writer) {
+            delimiter = new;
+            continue;
+        }
+        if custom_delimiter(sql, token, &delimiter, style.uppercase_keywords, kind, &mut writer) {
+            writer.blank_line();
+            scopes.truncate(1);
+            body_pending = false;
+            between = false;
+            continue;
+        }
+
+        let statement_start = writer.at_statement_start()
+            || writer.prev.map_or(false, |prev| prev.is_punctuation(")"));
+
+        if scope.subquery {
+            // Like the `VALUES(col)` function of MySQL
+            let call = tokens
+                .get(index + 1)
+                .map_or(false, |next| next.is_punctuation("("));
+            let clause = !call
+                && (is_one_of(token, CLAUSES)
+                    || (statement_start && is_one_of(token, STATEMENT_CLAUSES)));
+            let continuation = body_pending
+                && is_one_of(token, CLAUSE_CONTINUATIONS)
+                && writer.prev.map_or(false, |prev| prev.kind == TokenKind::Word);
+
+            if continuation {
+                writer.push(token, &text);
+                continue;
+            }
+            if clause {
+                writer.newline(scope.level);
+                writer.push(token, &text);
+                body_pending = true;
+                continue;
+            }
+
+            let after_join = writer.prev.map_or(false, |prev| is_one_of(&prev, JOINS));
+            if is_one_of(token, JOINS) && !after_join {
+                writer.newline(scope.level + 1);
+                writer.push(token, &text);
+                body_pending = false;
+                continue;
+            }
+
+            if token.is_keyword("BETWEEN") {
+                between = true;
+            } else if is_one_of(token, &["AND", "OR"]) && style.break_conditions && !body_pending {
+                if between && token.is_keyword("AND") {
+                    between = false;
+                } else {
+                    writer.newline(scope.level + 1);
+                }
+            }
+
+            if token.is_punctuation(";") {
+                writer.push(token, &text);
+                // The statements of a procedure end with `;` too
+                if delimiter == ";" {
+                    writer.blank_line();
+                } else {
+                    writer.newline(0);
+                }
+                scopes.truncate(1);
+                body_pending = false;
+                between = false;
+                continue;
+            }
+        }
+
+        if body_pending {
+            writer.newline(scope.level + 1);
+            body_pending = false;
+        }
+
+        if token.is_punctuation("(") {
+            let subquery = tokens[index + 1..]
+                .iter()
+                .find(|token| !token.is_trivia())
+                .map_or(false, |next| next.is_keyword("SELECT") || next.is_keyword("WITH"));
+
+            writer.push(token, &text);
+            scopes.push(Scope {
+                level: if subquery { scope.level + 2 } else { scope.level },
+                subquery,
+            });
+            continue;
+        }
+
+        if token.is_punctuation(")") && scopes.len() > 1 {
+            let closed = scopes.pop().unwrap();
+            if closed.subquery {
+                writer.newline(scopes.last().unwrap().level + 1);
+            }
+            writer.push(token, &text);
+            continue;
+        }
+
+        writer.push(token, &text);
+
+        if scope.subquery && token.is_punctuation(",") {
+            writer.newline(scope.level + 1);
+        }
+    }
+
+    writer.out
+}
+
+/// Put `sql` on a single line, only the line comments end a line. The strings, quoted
+/// identifiers and comments are kept as they are.
+pub fn compact(sql: &str, kind: SGDBKind) -> String {
+    let mut writer = Writer::new(sql, 0);
+    let mut delimiter = ";".to_string();
+
+    for token in tokenize_dialect(sql, kind) {
+        if token.kind == TokenKind::Whitespace || token.start < writer.skipped {
+            continue;
+        }
+
+        if let Some(new) = delimiter_command(sql, &token, kind, &mut writer) {
+            delimiter = new;
+            continue;
+        }
+        // The `DELIMITER` command must start a line
+        if custom_delimiter(sql, &token, &delimiter, false, kind, &mut writer) {
+            writer.newline(0);
+            continue;
+        }
+
+        writer.push(&token, token.text);
+        if is_line_comment(&token) {
+            writer.newline(0);
+        }
+    }
+
+    writer.out
+}
+
+/// Writes the tokens with the spaces between them.
+struct Writer<'a> {
+    sql: &'a str,
+    out: String,
+    indent_width: usize,
+    /// The indentation of the current line, in levels.
+    indent: usize,
+    /// The indentation of a new line to start before the next token.
+    pending_line: Option<usize>,
+    pending_blank_line: bool,
+    prev: Option<Token<'a>>,
+    before_prev: Option<Token<'a>>,
+    /// The end of the text written as is.
+    skipped: usize,
+}
+
+impl<'a> Writer<'a> {
+    fn new(sql: &'a str, indent_width: usize) -> Self {
+        Self {
+            sql,
+            out: String::new(),
+            indent_width,
+            indent: 0,
+            pending_line: None,
+            pending_blank_line: false,
+            prev: None,
+            before_prev: None,
+            skipped: 0,
+        }
+    }
+
+    fn newline(&mut self, indent: usize) {
+        self.pending_line = Some(indent);
+    }
+
+    fn blank_line(&mut self) {
+        self.pending_line = Some(0);
+        self.pending_blank_line = true;
+    }
+
+    fn at_statement_start(&self) -> bool {
+        self.prev.map_or(true, |prev| prev.is_punctuation(";"))
+    }
+
+    fn skip_until(&mut self, end: usize) {
+        self.skipped = end;
+        self.prev = None;
+        self.before_prev = None;
+    }
+
+    fn start_token(&mut self) -> bool {
+        match self.pending_line.take() {
+            Some(indent) => {
+                if !self.out.is_empty() {
+                    self.out.truncate(self.out.trim_end_matches(' ').len());
+                    self.out.push('\n');
+                    if std::mem::take(&mut self.pending_blank_line) {
+                        self.out.push('\n');
+                    }
+                }
+                self.out.push_str(&" ".repeat(indent * self.indent_width));
+                self.indent = indent;
+                true
+            }
+            None => self.out.is_empty(),
+        }
+    }
+
+    fn push_raw(&mut self, text: &str) {
+        self.start_token();
+        self.out.push_str(text);
+    }
+
+    fn push(&mut self, token: &Token<'a>, text: &str) {
+        let line_start = self.start_token();
+        if !line_start && self.needs_space(token) {
+            self.out.push(' ');
+        }
+
+        self.out.push_str(text);
+        self.before_prev = self.prev;
+        self.prev = Some(*token);
+    }
+
+    fn needs_space(&self, next: &Token) -> bool {
+        let prev = match self.prev {
+            Some(prev) => prev,
+            None => return false,
+        };
+        let adjacent = prev.end() == next.start;
+        let glued = |token: &Token| {
+            token.kind == TokenKind::Punctuation && !matches!(token.text, "(" | ")" | "," | ";")
+        };
+
+        if [",", ";", ")", "]", "."].iter().any(|p| next.is_punctuation(p))
+            || ["(", "[", "."].iter().any(|p| prev.is_punctuation(p))
+        {
+            return false;
+        }
+        // The casts of Postgres, `a::text`, and the named parameters, `:name`
+        let cast = |colon: &Token| self.sql[colon.end()..].starts_with(':');
+        if next.is_punctuation(":") && (cast(next) || (adjacent && prev.is_punctuation(":"))) {
+            return false;
+        }
+        if prev.is_punctuation(":") && adjacent && matches!(next.kind, TokenKind::Word | TokenKind::Number) {
+            return false;
+        }
+        // Function calls and subscripts
+        if next.is_punctuation("(") || next.is_punctuation("[") {
+            let call = matches!(prev.kind, TokenKind::Word | TokenKind::QuotedIdentifier)
+                && !is_one_of(&prev, &["IN", "VALUES", "AS", "ON", "USING", "EXISTS", "OVER"]);
+            return !(adjacent || call || prev.is_punctuation("]"));
+        }
+        // Operators of several characters, like `>=` or `->>`
+        if adjacent && glued(&prev) && glued(next) {
+            return false;
+        }
+        // The prefixes of strings, like `E'..'` or `_utf8mb4'..'`
+        if adjacent && prev.kind == TokenKind::Word && next.kind == TokenKind::String {
+            return false;
+        }
+        // Signs
+        if adjacent && (prev.is_punctuation("-") || prev.is_punctuation("+")) {
+            let operand_before = self.before_prev.map_or(false, |token| {
+                matches!(
+                    token.kind,
+                    TokenKind::Number | TokenKind::String | TokenKind::QuotedIdentifier
+                ) || token.is_punctuation(")")
+                    || token.is_punctuation("]")
+                    || (token.kind == TokenKind::Word
+                        && !completion::keywords().any(|keyword| token.is_keyword(keyword)))
+            });
+            if !operand_before {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUERY: &str = "select a, 'x  ;  y' as \"Odd  Name\" -- keep  this; comment\n\
+        from t /* block\n  comment */ where a = 'it''s' and b=$$ a  b $$ or c::text = E'\\n'";
+
+    #[test]
+    fn format_keeps_the_strings_and_comments() {
+        assert_eq!(
+            format(QUERY, SGDBKind::Postgres, &FormatStyle::default()),
+            "SELECT\n\
+            \x20   a,\n\
+            \x20   'x  ;  y' AS \"Odd  Name\" -- keep  this; comment\n\
+            FROM\n\
+            \x20   t /* block\n  comment */\n\
+            WHERE\n\
+            \x20   a = 'it''s'\n\
+            \x20   AND b = $$ a  b $$\n\
+            \x20   OR c::text = E'\\n'"
+        );
+
+        let style = FormatStyle {
+            indent: 2,
+            uppercase_keywords: false,
+            break_conditions: false,
+        };
+        assert_eq!(
+            format("select `a  b`, 'c\\' d' # note\nfrom t", SGDBKind::Mysql, &style),
+            "select\n  `a  b`,\n  'c\\' d' # note\nfrom\n  t"
+        );
+    }
+
+    #[test]
+    fn compact_keeps_the_strings_and_comments() {
+        let compacted = "SELECT a, 'x  ;  y' AS \"Odd  Name\" -- keep  this; comment\n\
+            FROM t /* block\n  comment */ WHERE a = 'it''s' AND b = $$ a  b $$ OR c::text = E'\\n'";
+
+        assert_eq!(
+            compact(
+                &format(QUERY, SGDBKind::Postgres, &FormatStyle::default()),
+                SGDBKind::Postgres
+            ),
+            compacted
+        );
+        assert_eq!(compact(compacted, SGDBKind::Postgres), compacted);
+    }
+
+    #[test]
+    fn format_and_compact_keep_the_delimiter_commands() {
+        let script = "DELIMITER $$\n\
+            CREATE PROCEDURE p() BEGIN select 1; select 'a$$b'; END$$\n\
+            DELIMITER ;\n\
+            call p()";
+
+        assert_eq!(
+            format(script, SGDBKind::Mysql, &FormatStyle::default()),
+            "DELIMITER $$\n\
+            CREATE PROCEDURE p() BEGIN\n\
+            SELECT\n\
+            \x20   1;\n\
+            SELECT\n\
+            \x20   'a$$b';\n\
+            END$$\n\
+            \n\
+            DELIMITER ;\n\
+            CALL p()"
+        );
+        assert_eq!(compact(script, SGDBKind::Mysql), script);
+    }
+}
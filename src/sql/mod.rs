@@ -0,0 +1,621 @@
+pub mod completion;
+pub mod format;
+
+use crate::db::sgdb::SGDBKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Whitespace,
+    Comment,
+    /// A string literal, `'..'` or the `$tag$..$tag$` strings of Postgres.
+    String,
+    /// A quoted identifier, `"..."` or `` `..` ``.
+    QuotedIdentifier,
+    /// A keyword or an identifier.
+    Word,
+    Number,
+    Punctuation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    /// Byte offset of the token in the tokenized text.
+    pub start: usize,
+}
+
+impl<'a> Token<'a> {
+    pub fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.kind, TokenKind::Whitespace | TokenKind::Comment)
+    }
+
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        self.kind == TokenKind::Word && self.text.eq_ignore_ascii_case(keyword)
+    }
+
+    pub fn is_punctuation(&self, punctuation: &str) -> bool {
+        self.kind == TokenKind::Punctuation && self.text == punctuation
+    }
+
+    /// The identifier without its quotes.
+    pub fn identifier(&self) -> Option<String> {
+        match self.kind {
+            TokenKind::Word => Some(self.text.to_string()),
+            TokenKind::QuotedIdentifier => {
+                let quote = &self.text[..1];
+                let inner = self
+                    .text
+                    .strip_prefix(quote)
+                    .map(|text| text.strip_suffix(quote).unwrap_or(text))
+                    .unwrap_or(self.text);
+                Some(inner.replace(&quote.repeat(2), quote))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The lexical differences between the SQL dialects.
+#[derive(Debug, Clone, Copy)]
+struct Syntax {
+    /// Whether a backslash escapes the quote in a `'..'` string, always the case in the `E'..'`
+    /// strings of Postgres.
+    backslash_escapes: bool,
+    /// The `# ..` comments of MySQL.
+    hash_comments: bool,
+    /// The `$tag$..$tag$` strings of Postgres.
+    dollar_quotes: bool,
+}
+
+impl Syntax {
+    /// Any of the syntaxes, when the dialect is unknown.
+    const ANY: Syntax = Syntax {
+        backslash_escapes: true,
+        hash_comments: true,
+        dollar_quotes: true,
+    };
+
+    fn of(kind: SGDBKind) -> Self {
+        match kind {
+            SGDBKind::Mysql => Syntax {
+                backslash_escapes: true,
+                hash_comments: true,
+                dollar_quotes: false,
+            },
+            SGDBKind::Postgres => Syntax {
+                backslash_escapes: false,
+                hash_comments: false,
+                dollar_quotes: true,
+            },
+            SGDBKind::Sqlite => Syntax {
+                backslash_escapes: false,
+                hash_comments: false,
+                dollar_quotes: false,
+            },
+        }
+    }
+}
+
+/// Split `sql` into tokens, the concatenation of the tokens is always `sql`.
+///
+/// Unterminated strings and comments run until the end of the text.
+pub fn tokenize(sql: &str) -> Vec<Token<'_>> {
+    tokenize_with(sql, Syntax::ANY)
+}
+
+/// Like `tokenize`, with the syntax of the strings and comments of a database.
+pub fn tokenize_dialect(sql: &str, kind: SGDBKind) -> Vec<Token<'_>> {
+    tokenize_with(sql, Syntax::of(kind))
+}
+
+fn tokenize_with(sql: &str, syntax: Syntax) -> Vec<Token<'_>> {
+    let bytes = sql.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    // End of a quoted token starting at `start`, a doubled or escaped quote is not an end.
+    let quoted_end = |start: usize, quote: u8, backslash: bool| {
+        let mut i = start + 1;
+        while i < bytes.len() {
+            if backslash && bytes[i] == b'\\' {
+                i += 2;
+            } else if bytes[i] == quote {
+                if bytes.get(i + 1) == Some(&quote) {
+                    i += 2;
+                } else {
+                    return i + 1;
+                }
+            } else {
+                i += 1;
+            }
+        }
+        bytes.len()
+    };
+
+    while pos < bytes.len() {
+        let c = bytes[pos];
+        let next = bytes.get(pos + 1).copied();
+
+        let (kind, end) = if c.is_ascii_whitespace() {
+            let len = bytes[pos..]
+                .iter()
+                .take_while(|c| c.is_ascii_whitespace())
+                .count();
+            (TokenKind::Whitespace, pos + len)
+        } else if (c == b'-' && next == Some(b'-')) || (c == b'#' && syntax.hash_comments) {
+            let end = sql[pos..].find('\n').map_or(sql.len(), |i| pos + i);
+            (TokenKind::Comment, end)
+        } else if c == b'/' && next == Some(b'*') {
+            let end = sql[pos + 2..].find("*/").map_or(sql.len(), |i| pos + 2 + i + 2);
+            (TokenKind::Comment, end)
+        } else if c == b'\'' {
+            let escape_string = tokens.last().map_or(false, |token: &Token| {
+                token.end() == pos && token.text.eq_ignore_ascii_case("E")
+            });
+            (TokenKind::String, quoted_end(pos, c, syntax.backslash_escapes || escape_string))
+        } else if let Some(tag) = dollar_tag(&sql[pos..]).filter(|_| syntax.dollar_quotes) {
+            let body = pos + tag.len();
+            let end = sql[body..].find(tag).map_or(sql.len(), |i| body + i + tag.len());
+            (TokenKind::String, end)
+        } else if c == b'"' || c == b'`' {
+            (TokenKind::QuotedIdentifier, quoted_end(pos, c, false))
+        } else if c.is_ascii_digit() || (c == b'.' && next.map_or(false, |n| n.is_ascii_digit()))
+        {
+            let mut end = pos + 1;
+            while end < bytes.len() {
+                let c = bytes[end];
+                if c.is_ascii_alphanumeric() || c == b'.' || c == b'_' {
+                    end += 1;
+                } else if (c == b'+' || c == b'-') && matches!(bytes[end - 1], b'e' | b'E') {
+                    end += 1;
+                } else {
+                    break;
+                }
+            }
+            (TokenKind::Number, end)
+        } else if c.is_ascii_alphabetic()
+            || c == b'_'
+            || c == b'$'
+            || c == b'@'
+            || (c >= 0x80 && sql[pos..].chars().next().map_or(false, char::is_alphanumeric))
+        {
+            let len = sql[pos..]
+                .char_indices()
+                .find(|(_, c)| !(c.is_alphanumeric() || *c == '_' || *c == '$' || *c == '@'))
+                .map_or(sql.len() - pos, |(i, _)| i);
+            (TokenKind::Word, pos + len)
+        } else {
+            let len = sql[pos..].chars().next().map_or(1, char::len_utf8);
+            (TokenKind::Punctuation, pos + len)
+        };
+
+        tokens.push(Token {
+            kind,
+            text: &sql[pos..end],
+            start: pos,
+        });
+        pos = end;
+    }
+
+    tokens
+}
+
+/// The opening tag of a dollar quoted string starting `sql`, like `$$` or `$body$`. The
+/// parameters like `$1` are not tags.
+fn dollar_tag(sql: &str) -> Option<&str> {
+    let tag = sql.strip_prefix('$')?;
+    let len = tag.find('$')?;
+
+    let name = &tag[..len];
+    if name.starts_with(|c: char| c.is_ascii_digit())
+        || !name.chars().all(|c| c.is_alphanumeric() || c == '_')
+    {
+        return None;
+    }
+
+    Some(&sql[..len + 2])
+}
+
+/// A statement of a script, without its delimiter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Statement<'a> {
+    pub text: &'a str,
+    /// Byte offset of the statement in the script.
+    pub start: usize,
+}
+
+impl<'a> Statement<'a> {
+    /// The whole `script` as a single statement, whatever delimiters it contains.
+    pub fn whole(script: &'a str) -> Self {
+        Statement {
+            text: script.trim(),
+            start: script.len() - script.trim_start().len(),
+        }
+    }
+}
+
+/// Split a script into its statements, on `;` or on the delimiter set by the `DELIMITER`
+/// command of the MySQL client. The delimiters in strings, quoted identifiers and comments don't
+/// count, the comments between the statements and the empty statements are dropped.
+pub fn split_statements(script: &str) -> Vec<Statement<'_>> {
+    let mut statements = Vec::new();
+    let mut delimiter = ";".to_string();
+
+    // The tokens of `script[base..]`, tokenized again when a delimiter ends inside a token
+    let mut base = 0;
+    let mut tokens = tokenize(script);
+    let mut index = 0;
+
+    let mut start = None;
+
+    while index < tokens.len() {
+        let token = tokens[index];
+        let token_start = base + token.start;
+        index += 1;
+
+        if token.is_trivia() {
+            continue;
+        }
+
+        if start.is_none() && token.is_keyword("DELIMITER") {
+            // The client command takes the rest of the line
+            let line_end = script[token_start..]
+                .find('\n')
+                .map_or(script.len(), |i| token_start + i);
+
+            if let Some(new) = script[base + token.end()..line_end].split_whitespace().next() {
+                delimiter = new.to_string();
+            }
+
+            base = line_end;
+            tokens = tokenize(&script[base..]);
+            index = 0;
+            continue;
+        }
+
+        // A custom delimiter may be part of a token, like `END$$`, or span several, like `//`
+        let found = match token.kind {
+            TokenKind::String | TokenKind::QuotedIdentifier => {
+                Some(token_start).filter(|at| script[*at..].starts_with(&delimiter))
+            }
+            _ => token
+                .text
+                .char_indices()
+                .map(|(i, _)| token_start + i)
+                .find(|at| script[*at..].starts_with(&delimiter)),
+        };
+
+        let at = match found {
+            Some(at) => at,
+            None => {
+                start.get_or_insert(token_start);
+                continue;
+            }
+        };
+
+        if let Some(start) = start.take().or(Some(token_start).filter(|_| at > token_start)) {
+            statements.push(Statement {
+                text: script[start..at].trim_end(),
+                start,
+            });
+        }
+
+        let end = at + delimiter.len();
+        index = tokens[index - 1..]
+            .iter()
+            .position(|token| base + token.start >= end)
+            .map_or(tokens.len(), |i| index - 1 + i);
+
+        if tokens.get(index).map_or(end < script.len(), |token| base + token.start > end) {
+            base = end;
+            tokens = tokenize(&script[base..]);
+            index = 0;
+        }
+    }
+
+    if let Some(start) = start {
+        statements.push(Statement {
+            text: script[start..].trim_end(),
+            start,
+        });
+    }
+
+    statements
+}
+
+/// The statement of `script` around the byte `offset`, or the one before it when the offset
+/// is between two statements.
+pub fn statement_at(script: &str, offset: usize) -> Option<Statement<'_>> {
+    let statements = split_statements(script);
+
+    statements
+        .iter()
+        .rev()
+        .find(|statement| statement.start <= offset)
+        .or_else(|| statements.first())
+        .copied()
+}
+
+/// The table read by a simple `SELECT <columns> FROM <table> ..` query, the only kind of query
+/// whose rows can be mapped back to table rows, or why the rows can't be. The columns are `*` or
+/// plain column names: an alias or an expression would be matched by name to another column.
+/// Joins, unions, groupings, sub queries in the `FROM` clause and anything else than a select
+/// are refused.
+pub fn source_table(query: &str) -> Result<String, &'static str> {
+    const NOT_SIMPLE: &str = "Only the rows of a simple SELECT on a single table can be edited";
+    const NOT_PLAIN: &str =
+        "Only the rows of a SELECT of plain columns, without aliases nor expressions, can be edited";
+
+    let tokens = tokenize(query)
+        .into_iter()
+        .filter(|token| !token.is_trivia())
+        .collect::<Vec<_>>();
+
+    if !tokens.first().map_or(false, |token| token.is_keyword("SELECT")) {
+        return Err(NOT_SIMPLE);
+    }
+
+    let mut depth = 0;
+    let mut from = None;
+
+    for (index, token) in tokens.iter().enumerate() {
+        if token.is_punctuation("(") {
+            depth += 1;
+        } else if token.is_punctuation(")") {
+            depth -= 1;
+        } else if depth == 0 {
+            if ["UNION", "INTERSECT", "EXCEPT", "GROUP", "JOIN", "DISTINCT"]
+                .iter()
+                .any(|keyword| token.is_keyword(keyword))
+            {
+                return Err(NOT_SIMPLE);
+            } else if from.is_none() && token.is_keyword("FROM") {
+                from = Some(index);
+            }
+        }
+    }
+    let from = from.ok_or(NOT_SIMPLE)?;
+
+    if !tokens[1..from]
+        .split(|token| token.is_punctuation(","))
+        .all(is_plain_column)
+    {
+        return Err(NOT_PLAIN);
+    }
+
+    let mut rest = tokens[from + 1..].iter();
+    let mut path = vec![rest.next().and_then(Token::identifier).ok_or(NOT_SIMPLE)?];
+
+    let mut next = rest.next();
+    while next.map_or(false, |token| token.is_punctuation(".")) {
+        path.push(rest.next().and_then(Token::identifier).ok_or(NOT_SIMPLE)?);
+        next = rest.next();
+    }
+
+    // Skip the alias
+    if next.map_or(false, |token| token.is_keyword("AS")) {
+        rest.next().ok_or(NOT_SIMPLE)?;
+        next = rest.next();
+    } else if next.map_or(false, |token| {
+        token.kind == TokenKind::QuotedIdentifier
+            || (token.kind == TokenKind::Word
+                && !["WHERE", "ORDER", "LIMIT", "OFFSET", "FOR", "HAVING", "WINDOW"]
+                    .iter()
+                    .any(|keyword| token.is_keyword(keyword)))
+    }) {
+        next = rest.next();
+    }
+
+    if next.map_or(false, |token| token.is_punctuation(",")) {
+        return Err(NOT_SIMPLE);
+    }
+
+    Ok(path.join("."))
+}
+
+/// Whether an item of a select list is `*` or a column name, both possibly qualified.
+fn is_plain_column(item: &[Token]) -> bool {
+    let (last, qualifiers) = match item.split_last() {
+        Some(split) => split,
+        None => return false,
+    };
+
+    let named = last.is_punctuation("*") || last.identifier().is_some();
+    // `a.b.c`: names separated by dots
+    let qualified = qualifiers.len() % 2 == 0
+        && qualifiers.chunks(2).all(|chunk| {
+            chunk[0].identifier().is_some() && chunk[1].is_punctuation(".")
+        });
+
+    named && qualified
+}
+
+/// Whether `query` only returns rows, like a `SELECT`, rather than changing any. Its rows can be
+/// streamed, it has no affected rows to report.
+pub fn returns_rows(query: &str) -> bool {
+    let mut words = tokenize(query)
+        .into_iter()
+        .filter(|token| token.kind == TokenKind::Word);
+
+    let first = match words.next() {
+        Some(first) => first,
+        None => return false,
+    };
+
+    // A common table expression may wrap a change
+    if first.is_keyword("WITH") {
+        return !words.any(|token| {
+            ["INSERT", "UPDATE", "DELETE", "MERGE"]
+                .iter()
+                .any(|keyword| token.is_keyword(keyword))
+        });
+    }
+
+    ["SELECT", "VALUES", "TABLE", "SHOW", "EXPLAIN", "DESCRIBE", "DESC", "PRAGMA"]
+        .iter()
+        .any(|keyword| first.is_keyword(keyword))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The texts of the statements of `script`.
+    fn texts(script: &str) -> Vec<&str> {
+        split_statements(script)
+            .into_iter()
+            .map(|statement| statement.text)
+            .collect()
+    }
+
+    #[test]
+    fn split_on_semicolons() {
+        let script = "SELECT 1;\n  SELECT 2 ;;\nSELECT 3";
+        let statements = split_statements(script);
+
+        assert_eq!(
+            statements,
+            [
+                Statement { text: "SELECT 1", start: 0 },
+                Statement { text: "SELECT 2", start: 12 },
+                Statement { text: "SELECT 3", start: 24 },
+            ]
+        );
+        assert!(split_statements("  ;\n; ").is_empty());
+    }
+
+    #[test]
+    fn split_ignores_the_semicolons_of_strings_and_comments() {
+        assert_eq!(
+            texts("SELECT 'a;b', \"c;d\", `e;f`; SELECT 'it''s;'"),
+            ["SELECT 'a;b', \"c;d\", `e;f`", "SELECT 'it''s;'"]
+        );
+        assert_eq!(
+            texts("SELECT 'a\\';b'; SELECT E'\\';'"),
+            ["SELECT 'a\\';b'", "SELECT E'\\';'"]
+        );
+        assert_eq!(
+            texts("SELECT 1 -- one; two\n; /* three; */ SELECT 2 # four;\n"),
+            ["SELECT 1 -- one; two", "SELECT 2 # four;"]
+        );
+    }
+
+    #[test]
+    fn split_ignores_the_semicolons_of_dollar_quoted_strings() {
+        let function = "CREATE FUNCTION f() RETURNS int AS $body$ BEGIN RETURN 1; END; $body$ LANGUAGE plpgsql";
+        assert_eq!(
+            texts(&format!("{};\nSELECT f()", function)),
+            [function, "SELECT f()"]
+        );
+        assert_eq!(texts("SELECT $$a;b$$; SELECT $1"), ["SELECT $$a;b$$", "SELECT $1"]);
+    }
+
+    #[test]
+    fn split_on_the_delimiter_of_the_client() {
+        let script = "DELIMITER $$\n\
+            CREATE PROCEDURE p() BEGIN SELECT 1; SELECT 2; END$$\n\
+            DELIMITER ;\n\
+            CALL p();\n\
+            DELIMITER //\n\
+            SELECT 'a//b'//\n\
+            SELECT 3 //";
+
+        assert_eq!(
+            texts(script),
+            [
+                "CREATE PROCEDURE p() BEGIN SELECT 1; SELECT 2; END",
+                "CALL p()",
+                "SELECT 'a//b'",
+                "SELECT 3",
+            ]
+        );
+    }
+
+    #[test]
+    fn statement_at_the_edges_of_the_statements() {
+        let script = "SELECT 1;\n\nSELECT 2;  -- two\nSELECT 3";
+        let text_at = |offset| statement_at(script, offset).map(|statement| statement.text);
+
+        // The first character, the semicolon and the end of the first statement.
+        assert_eq!(text_at(0), Some("SELECT 1"));
+        assert_eq!(text_at(8), Some("SELECT 1"));
+        assert_eq!(text_at(10), Some("SELECT 1"));
+        // The first character and the end of the second one, then the comment after it.
+        assert_eq!(text_at(11), Some("SELECT 2"));
+        assert_eq!(text_at(19), Some("SELECT 2"));
+        assert_eq!(text_at(28), Some("SELECT 2"));
+        // The last statement, up to the end of the script and past it.
+        assert_eq!(text_at(29), Some("SELECT 3"));
+        assert_eq!(text_at(script.len()), Some("SELECT 3"));
+        assert_eq!(text_at(script.len() + 1), Some("SELECT 3"));
+    }
+
+    #[test]
+    fn statement_at_before_the_first_statement() {
+        assert_eq!(
+            statement_at("\n  -- one\nSELECT 1;", 0),
+            Some(Statement { text: "SELECT 1", start: 10 })
+        );
+        assert_eq!(statement_at("", 0), None);
+        assert_eq!(statement_at(" -- nothing\n", 3), None);
+    }
+
+    #[test]
+    fn source_table_of_plain_columns() {
+        let queries = [
+            ("SELECT * FROM users", "users"),
+            ("select id, name from users where id > 1", "users"),
+            ("SELECT users.id, \"name\" FROM public.users ORDER BY id", "public.users"),
+            ("SELECT u.* FROM users u LIMIT 10", "users"),
+            ("SELECT `id` FROM `users` AS u", "users"),
+            ("SELECT id /* the key */ FROM users -- all of them", "users"),
+        ];
+
+        for (query, table) in queries {
+            assert_eq!(source_table(query), Ok(table.to_string()), "{}", query);
+        }
+    }
+
+    #[test]
+    fn source_table_refuses_aliases_and_expressions() {
+        let queries = [
+            "SELECT name AS id, id AS name FROM users",
+            "SELECT id, UPPER(name) AS name FROM users",
+            "SELECT id, UPPER(name) FROM users",
+            "SELECT id, name nickname FROM users",
+            "SELECT id, 1 FROM users",
+            "SELECT id, 'a' FROM users",
+            "SELECT id, price * 2 FROM items",
+            "SELECT ALL id FROM users",
+            "SELECT FROM users",
+        ];
+
+        for query in queries {
+            assert!(source_table(query).is_err(), "{}", query);
+        }
+    }
+
+    #[test]
+    fn source_table_refuses_the_other_queries() {
+        let queries = [
+            "",
+            "UPDATE users SET name = 'a'",
+            "SELECT 1",
+            "SELECT DISTINCT name FROM users",
+            "SELECT id FROM users JOIN roles ON roles.user_id = users.id",
+            "SELECT id FROM users, roles",
+            "SELECT id FROM users UNION SELECT id FROM admins",
+            "SELECT id FROM users GROUP BY id",
+            "SELECT id FROM (SELECT id FROM users) AS u",
+        ];
+
+        for query in queries {
+            assert!(source_table(query).is_err(), "{}", query);
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::path::Path;
+
+use eframe::egui::{self, Ui};
+
+use crate::export::{CsvQuoting, ExportFormat, ExportOptions};
+
+/// The CSV delimiters offered, with their label.
+const DELIMITERS: &[(char, &str)] = &[(',', ","), (';', ";"), ('\t', "Tab"), ('|', "|")];
+
+pub enum ExportAction {
+    /// Export the rows of the result shown.
+    Result,
+    /// Run the statement of the result again, its rows are streamed to the file.
+    Rerun,
+}
+
+/// The content of the export menu. `rerun` is why the statement can't be run again, if it can't.
+pub fn export_menu(
+    ui: &mut Ui,
+    options: &mut ExportOptions,
+    path: &mut String,
+    rows: usize,
+    rerun: Result<(), &str>,
+) -> Option<ExportAction> {
+    let mut action = None;
+
+    ui.set_min_width(320.);
+
+    egui::Grid::new("export_options")
+        .num_columns(2)
+        .spacing([20.0, 4.0])
+        .show(ui, |ui| {
+            // A combo box would close the menu, its popup is outside of it
+            ui.label("Format");
+            ui.horizontal_wrapped(|ui| {
+                for format in ExportFormat::all() {
+                    let selected = ui.selectable_value(&mut options.format, format, format.name());
+
+                    // Keep the extension of the file in line with its format
+                    if selected.clicked() && !path.is_empty() {
+                        *path = Path::new(path.as_str())
+                            .with_extension(format.extension())
+                            .to_string_lossy()
+                            .into_owned();
+                    }
+                }
+            });
+            ui.end_row();
+
+            match options.format {
+                ExportFormat::Csv => {
+                    ui.label("Delimiter");
+                    ui.horizontal(|ui| {
+                        for (delimiter, label) in DELIMITERS {
+                            ui.selectable_value(&mut options.csv_delimiter, *delimiter, *label);
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label("Quote");
+                    ui.vertical(|ui| {
+                        for quoting in CsvQuoting::all() {
+                            ui.radio_value(&mut options.csv_quoting, quoting, quoting.name());
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label("");
+                    ui.checkbox(&mut options.csv_header, "Header with the column names");
+                    ui.end_row();
+                }
+                ExportFormat::Sql => {
+                    ui.label("Target table");
+                    ui.text_edit_singleline(&mut options.sql_table);
+                    ui.end_row();
+                }
+                ExportFormat::Json
+                | ExportFormat::Ndjson
+                | ExportFormat::Markdown
+                | ExportFormat::Xlsx => {}
+            }
+
+            ui.label("File");
+            ui.add(egui::TextEdit::singleline(path).desired_width(240.));
+            ui.end_row();
+        });
+
+    ui.separator();
+
+    let ready = !path.trim().is_empty()
+        && (options.format != ExportFormat::Sql || !options.sql_table.trim().is_empty());
+
+    if ui
+        .add_enabled(ready, egui::Button::new(format!("Export the result ({} rows)", rows)))
+        .clicked()
+    {
+        action = Some(ExportAction::Result);
+    }
+
+    let mut button = ui
+        .add_enabled(ready && rerun.is_ok(), egui::Button::new("Run again and export all the rows"))
+        .on_hover_text("The rows are written to the file as they are fetched, without being kept");
+    if let Err(err) = rerun {
+        button = button.on_disabled_hover_text(err);
+    }
+    if button.clicked() {
+        action = Some(ExportAction::Rerun);
+    }
+
+    action
+}
@@ -0,0 +1,85 @@
+use chrono::Local;
+use eframe::egui::{self, RichText, ScrollArea, Ui};
+use eframe::epaint::Color32;
+
+use crate::history::QueryHistory;
+use crate::ui::components::icons;
+
+/// The number of entries shown in the menu, the search finds the older ones.
+const SHOWN_ENTRIES: usize = 100;
+
+pub enum HistoryAction {
+    /// Replace the query of the editor.
+    Load(String),
+    Run(String),
+}
+
+/// The content of the history menu of `connection`, filtered by `search`.
+pub fn history_menu(
+    ui: &mut Ui,
+    history: &QueryHistory,
+    connection: &str,
+    search: &mut String,
+) -> Option<HistoryAction> {
+    let mut action = None;
+
+    ui.set_min_width(420.);
+
+    ui.add(egui::TextEdit::singleline(search).hint_text("Search.."));
+    ui.separator();
+
+    ScrollArea::vertical().max_height(400.).show(ui, |ui| {
+        let mut entries = history.search(connection, search).take(SHOWN_ENTRIES).peekable();
+
+        if entries.peek().is_none() {
+            ui.label(RichText::new("No queries").weak());
+        }
+
+        for entry in entries {
+            ui.horizontal(|ui| {
+                match &entry.error {
+                    Some(err) => {
+                        ui.colored_label(Color32::RED, "✖").on_hover_text(err);
+                    }
+                    None => {
+                        ui.colored_label(Color32::GREEN, "✔");
+                    }
+                }
+
+                let executed_at = entry.executed_at.with_timezone(&Local);
+                ui.label(RichText::new(executed_at.format("%Y-%m-%d %H:%M:%S").to_string()).weak());
+                ui.label(format!("{} ms", entry.duration.as_millis()));
+
+                if let Some(rows) = entry.rows {
+                    ui.label(format!("{} rows", rows));
+                }
+
+                ui.with_layout(egui::Layout::right_to_left(), |ui| {
+                    if ui.small_button(icons::ICON_RUN).on_hover_text("Run").clicked() {
+                        action = Some(HistoryAction::Run(entry.query.clone()));
+                    }
+                    if ui
+                        .small_button(icons::ICON_EDIT)
+                        .on_hover_text("Load into the editor")
+                        .clicked()
+                    {
+                        action = Some(HistoryAction::Load(entry.query.clone()));
+                    }
+                });
+            });
+
+            let first_line = entry.query.lines().next().unwrap_or_default();
+            let preview = match first_line.char_indices().nth(80) {
+                Some((end, _)) => format!("{}..", &first_line[..end]),
+                None if first_line.len() < entry.query.trim_end().len() => format!("{}..", first_line),
+                None => first_line.to_string(),
+            };
+
+            ui.label(RichText::new(preview).monospace())
+                .on_hover_text(&entry.query);
+            ui.separator();
+        }
+    });
+
+    action
+}
@@ -6,3 +6,15 @@ pub const ICON_TRASH: &'static str = "";
 pub const ICON_HISTORY: &'static str = "";
 pub const ICON_ARROW_DOWN: &'static str = "";
 pub const ICON_CLOSE: &'static str = "";
+pub const ICON_STOP: &'static str = "";
+pub const ICON_PIN: &'static str = "";
+pub const ICON_PLUS: &'static str = "";
+pub const ICON_DOWNLOAD: &'static str = "";
+pub const ICON_SORT: &'static str = "";
+pub const ICON_SORT_ASC: &'static str = "";
+pub const ICON_SORT_DESC: &'static str = "";
+pub const ICON_FILTER: &'static str = "";
+pub const ICON_FIRST: &'static str = "";
+pub const ICON_PREVIOUS: &'static str = "";
+pub const ICON_NEXT: &'static str = "";
+pub const ICON_LAST: &'static str = "";
@@ -2,19 +2,20 @@ use egui::{Align, Color32, Frame, Layout, ScrollArea, Stroke};
 
 use crate::{meta::FetchResult, ui::components::meta_table::MetaTableCell};
 
-pub fn meta_grid(ui: &mut egui::Ui, res: &FetchResult, selected_index: &mut usize) {
+/// Show the `rows` of `res` in order, `selected_index` is a position among them.
+pub fn meta_grid(ui: &mut egui::Ui, res: &FetchResult, rows: &[usize], selected_index: &mut usize) {
     let size = ui.available_width();
     const cols: usize = 3;
 
-    {
+    if !rows.is_empty() {
         if ui.input().key_pressed(egui::Key::J) {
             *selected_index += 1;
-            if *selected_index >= res.num_rows {
+            if *selected_index >= rows.len() {
                 *selected_index = 0;
             }
         } else if ui.input().key_pressed(egui::Key::K) {
             if *selected_index == 0 {
-                *selected_index = res.num_rows - 1;
+                *selected_index = rows.len() - 1;
             } else {
                 *selected_index -= 1;
             }
@@ -24,13 +25,13 @@ pub fn meta_grid(ui: &mut egui::Ui, res: &FetchResult, selected_index: &mut usiz
 
         if scroll > 0. {
             if *selected_index == 0 {
-                *selected_index = res.num_rows - 1;
+                *selected_index = rows.len() - 1;
             } else {
                 *selected_index -= 1;
             }
         } else if scroll < 0. {
             *selected_index += 1;
-            if *selected_index >= res.num_rows {
+            if *selected_index >= rows.len() {
                 *selected_index = 0;
             }
         }
@@ -38,11 +39,11 @@ pub fn meta_grid(ui: &mut egui::Ui, res: &FetchResult, selected_index: &mut usiz
 
     ScrollArea::both().enable_scrolling(false).show(ui, |ui| {
         ui.with_layout(Layout::top_down(egui::Align::Min), |ui| {
-            for row_index in 0..res.num_rows {
+            for (position, row_index) in rows.iter().copied().enumerate() {
                 Frame::group(ui.style())
                     .stroke(Stroke::new(
                         2.,
-                        if *selected_index == row_index {
+                        if *selected_index == position {
                             Color32::BLUE
                         } else {
                             Color32::BLACK
@@ -53,7 +54,7 @@ pub fn meta_grid(ui: &mut egui::Ui, res: &FetchResult, selected_index: &mut usiz
                             for (index, (col, values)) in res.res.iter().enumerate() {
                                 ui.label(&col.name);
 
-                                col.table_cell(ui, &values[row_index]);
+                                col.table_cell(ui, &values.get(row_index));
 
                                 if index + 1 < res.res.len() {
                                     ui.separator();
@@ -66,7 +67,7 @@ pub fn meta_grid(ui: &mut egui::Ui, res: &FetchResult, selected_index: &mut usiz
                         });
                     });
 
-                if *selected_index == row_index {
+                if *selected_index == position {
                     ui.scroll_to_cursor(Some(Align::Center));
                 }
             }
@@ -1,55 +1,432 @@
-use egui::{RichText, Ui};
+use std::{collections::BTreeSet, mem, str::FromStr};
 
-use crate::meta::{MetaColNumber, MetaColumnType, FetchResult};
+use chrono::{NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use egui::{DragValue, Id, Label, Response, RichText, SelectableLabel, Sense, TextEdit, Ui};
+
+use crate::export::copy::CopyFormat;
+use crate::meta::{
+    CellSelection, ColumnFilter, FetchResult, MetaColumnType, RowEdits, RowView, SortOrder,
+};
 use crate::ui::components::icons;
-use crate::{db::sgdb::SGDBRowValue, meta::MetaColumn};
+use crate::{db::sgdb::{SGDBColumnType, SGDBColumnValues, SGDBRowValue}, meta::MetaColumn};
 
 use eframe::{egui::Layout, emath::Align, epaint::Color32};
 
-pub fn meta_table(ui: &mut egui::Ui, res: &FetchResult) {
+/// The rows shown before the end of a result which make it visible.
+const END_ROWS: usize = 20;
+
+pub struct MetaTableOutput {
+    /// The format of the copy of the selected cells asked for, with Ctrl+C or the context menu.
+    pub copy: Option<CopyFormat>,
+    /// Whether the end of the rows is visible, the next ones can be loaded.
+    pub end_visible: bool,
+}
+
+/// Show the `rows` of `res` in order, they are sorted and filtered after `view` which the headers
+/// change. The rows can be edited when `edits` is given. The `selection` holds the rows checked
+/// for an action, `cells` the ones selected to be copied.
+pub fn meta_table(
+    ui: &mut egui::Ui,
+    res: &FetchResult,
+    rows: &[usize],
+    view: &mut RowView,
+    selection: &mut BTreeSet<usize>,
+    cells: &mut CellSelection,
+    mut edits: Option<&mut RowEdits>,
+) -> MetaTableOutput {
     use egui_extras::{Size, TableBuilder};
 
-    let text_height = egui::TextStyle::Body.resolve(ui.style()).size;
+    let mut copy = None;
+    let mut end_visible = rows.len() < END_ROWS;
+
+    // The text fields copy their own selection
+    let copied = ui
+        .input()
+        .events
+        .iter()
+        .any(|event| matches!(event, egui::Event::Copy));
+    if copied && ui.memory().focus().is_none() && *cells != CellSelection::None {
+        copy = Some(CopyFormat::Tsv);
+    }
+
+    let drag_id = Id::new("meta_table_drag");
+    let mut dragging = ui.data().get_temp::<bool>(drag_id).unwrap_or_default()
+        && ui.input().pointer.primary_down();
+    let shift = ui.input().modifiers.shift;
+    let selected_fill = ui.visuals().selection.bg_fill.linear_multiply(0.4);
 
     TableBuilder::new(ui)
         .striped(true)
         .cell_layout(egui::Layout::left_to_right().with_cross_align(egui::Align::Center))
-        .column(Size::initial(20.))
+        .column(Size::initial(80.))
         .columns(Size::remainder().at_least(100.), res.res.len())
         .resizable(true)
         .header(20.0, |mut header| {
             header.col(|ui| {
-                ui.with_layout(Layout::top_down(Align::Center), |ui| {
-                    let rich = RichText::new("Actions").underline();
-                    ui.label(rich);
-                });
+                let mut all = !rows.is_empty() && rows.iter().all(|row| selection.contains(row));
+                if ui
+                    .checkbox(&mut all, "")
+                    .on_hover_text("Select all the rows shown")
+                    .changed()
+                {
+                    for row in rows {
+                        if all {
+                            selection.insert(*row);
+                        } else {
+                            selection.remove(row);
+                        }
+                    }
+                }
+
+                let rich = RichText::new("Actions").underline();
+                ui.label(rich);
             });
-            for col in res.res.keys() {
+            for (col_index, col) in res.res.keys().enumerate() {
                 header.col(|ui| {
-                    ui.with_layout(Layout::top_down(Align::Center), |ui| {
-                        let rich = RichText::new(&col.name).underline();
-                        ui.label(rich);
-                    });
+                    let rich = RichText::new(&col.name).underline();
+                    let label = ui
+                        .add(Label::new(rich).sense(Sense::click()))
+                        .on_hover_text("Click to select the column, shift click to select several");
+
+                    let pressed = label.clicked()
+                        || label.secondary_clicked() && !selects_column(cells, col_index);
+                    if pressed {
+                        *cells = match *cells {
+                            CellSelection::Columns { anchor, .. } if shift => {
+                                CellSelection::Columns { anchor, end: col_index }
+                            }
+                            _ => CellSelection::Columns { anchor: col_index, end: col_index },
+                        };
+                    }
+                    copy_menu(label, &mut copy);
+
+                    sort_button(ui, view, col_index);
+                    filter_menu(ui, view, col_index);
                 });
             }
         })
         .body(|mut body| {
-            body.rows(25.0, res.num_rows, |row_index, mut table_row| {
+            body.rows(25.0, rows.len(), |position, mut table_row| {
+                let row_index = rows[position];
+                // Only the visible rows are shown
+                end_visible |= position + END_ROWS >= rows.len();
+                let editing = edits
+                    .as_ref()
+                    .map_or(false, |edits| edits.editing == Some(row_index));
+
                 table_row.col(|ui| {
-                    ui.button(icons::ICON_EDIT);
+                    // The number of the row in the result, whatever its position
+                    let number = RichText::new(format!("{}", row_index + 1)).weak();
+                    let number = ui
+                        .add(Label::new(number).sense(Sense::click()))
+                        .on_hover_text("Click to select the row, shift click to select several");
+
+                    let pressed = number.clicked()
+                        || number.secondary_clicked() && !selects_row(cells, position);
+                    if pressed {
+                        *cells = match *cells {
+                            CellSelection::Rows { anchor, .. } if shift => {
+                                CellSelection::Rows { anchor, end: position }
+                            }
+                            _ => CellSelection::Rows { anchor: position, end: position },
+                        };
+                    }
+                    copy_menu(number, &mut copy);
+
+                    let mut selected = selection.contains(&row_index);
+                    if ui.checkbox(&mut selected, "").changed() {
+                        if selected {
+                            selection.insert(row_index);
+                        } else {
+                            selection.remove(&row_index);
+                        }
+                    }
+
+                    let pending = edits
+                        .as_ref()
+                        .map_or(false, |edits| edits.rows.contains_key(&row_index));
+
+                    let icon = if pending {
+                        RichText::new(icons::ICON_EDIT).color(Color32::GOLD)
+                    } else {
+                        RichText::new(icons::ICON_EDIT)
+                    };
+
+                    let btn = ui
+                        .add_enabled(edits.is_some(), SelectableLabel::new(editing, icon))
+                        .on_hover_text("Edit the row")
+                        .on_disabled_hover_text("The rows of this result can't be edited");
+
+                    if btn.clicked() {
+                        if let Some(edits) = edits.as_mut() {
+                            edits.editing = if editing { None } else { Some(row_index) };
+                        }
+                    }
                 });
 
-                for (col, values) in res.res.iter() {
+                for (col_index, (col, values)) in res.res.iter().enumerate() {
+                    let original = values.get(row_index);
+
                     table_row.col(|ui| {
-                        col.table_cell(ui, &values[row_index]);
+                        if !editing && cells.contains(position, col_index) {
+                            ui.painter().rect_filled(ui.max_rect(), 0., selected_fill);
+                        }
+
+                        match edits.as_mut() {
+                            Some(edits) if editing => {
+                                let mut value = edits
+                                    .value(row_index, col_index)
+                                    .unwrap_or(&original)
+                                    .clone();
+
+                                let id = Id::new("meta_table_edit").with(row_index).with(col_index);
+                                col.table_cell_edit(ui, id, &mut value, || default_value(values));
+
+                                if Some(&value) != edits.value(row_index, col_index) {
+                                    edits.set(row_index, col_index, value, &original);
+                                }
+                            }
+                            Some(edits) => {
+                                let value = edits.value(row_index, col_index).unwrap_or(&original);
+                                col.table_cell(ui, value);
+                            }
+                            None => {
+                                col.table_cell(ui, &original);
+                            }
+                        }
+
+                        // The fields of the edited row take the clicks
+                        if !editing {
+                            let id = Id::new("meta_table_cell").with(row_index).with(col_index);
+                            let response = ui.interact(ui.max_rect(), id, Sense::click_and_drag());
+                            let hovered = ui.rect_contains_pointer(response.rect);
+
+                            let cell = (position, col_index);
+                            select_cell(&response, hovered, cells, cell, shift, &mut dragging);
+                            copy_menu(response, &mut copy);
+                        }
                     });
                 }
             });
         });
+
+    ui.data().insert_temp(drag_id, dragging);
+
+    MetaTableOutput { copy, end_visible }
+}
+
+/// Select the cell at `cell` when it's clicked, or extend the selection to it with shift or
+/// while the pointer is dragged over the cells.
+fn select_cell(
+    response: &Response,
+    hovered: bool,
+    cells: &mut CellSelection,
+    cell: (usize, usize),
+    shift: bool,
+    dragging: &mut bool,
+) {
+    let pressed = response.clicked()
+        || response.drag_started()
+        // The context menu copies the cell, unless it's part of the selection
+        || response.secondary_clicked() && !cells.contains(cell.0, cell.1);
+
+    if pressed {
+        *cells = match *cells {
+            CellSelection::Cells { anchor, .. } if shift => CellSelection::Cells { anchor, end: cell },
+            _ => CellSelection::Cells { anchor: cell, end: cell },
+        };
+        *dragging = response.drag_started();
+    } else if *dragging && hovered {
+        if let CellSelection::Cells { anchor, .. } = *cells {
+            *cells = CellSelection::Cells { anchor, end: cell };
+        }
+    }
+}
+
+fn selects_row(cells: &CellSelection, row: usize) -> bool {
+    matches!(cells, CellSelection::Rows { .. }) && cells.contains(row, 0)
+}
+
+fn selects_column(cells: &CellSelection, column: usize) -> bool {
+    matches!(cells, CellSelection::Columns { .. }) && cells.contains(0, column)
+}
+
+/// The button sorting the rows on `column`.
+fn sort_button(ui: &mut Ui, view: &mut RowView, column: usize) {
+    let (icon, hover) = match view.sort {
+        Some((sorted, SortOrder::Ascending)) if sorted == column => (
+            icons::ICON_SORT_ASC,
+            "Sorted in ascending order, click to sort in descending order",
+        ),
+        Some((sorted, SortOrder::Descending)) if sorted == column => (
+            icons::ICON_SORT_DESC,
+            "Sorted in descending order, click to keep the order of the result",
+        ),
+        _ => (icons::ICON_SORT, "Click to sort the rows on the column"),
+    };
+
+    let sorted = matches!(view.sort, Some((sorted, _)) if sorted == column);
+    if ui
+        .add(SelectableLabel::new(sorted, icon))
+        .on_hover_text(hover)
+        .clicked()
+    {
+        view.toggle_sort(column);
+    }
+}
+
+/// The menu of the filter of the rows on `column`.
+fn filter_menu(ui: &mut Ui, view: &mut RowView, column: usize) {
+    let mut filter = view.filters.get(&column).cloned();
+
+    let icon = if filter.is_some() {
+        RichText::new(icons::ICON_FILTER).color(Color32::GOLD)
+    } else {
+        RichText::new(icons::ICON_FILTER)
+    };
+
+    ui.menu_button(icon, |ui| {
+        ui.horizontal(|ui| {
+            for kind in ColumnFilter::all() {
+                let selected = filter
+                    .as_ref()
+                    .map_or(false, |filter| mem::discriminant(filter) == mem::discriminant(&kind));
+
+                if ui.selectable_label(selected, kind.name()).clicked() && !selected {
+                    filter = Some(kind);
+                }
+            }
+        });
+
+        let typed = match filter.as_mut() {
+            Some(ColumnFilter::Equals(operand)) => {
+                ui.text_edit_singleline(operand);
+                true
+            }
+            Some(ColumnFilter::Contains(operand)) => {
+                ui.text_edit_singleline(operand);
+                false
+            }
+            Some(ColumnFilter::Range { min, max }) => {
+                ui.horizontal(|ui| {
+                    ui.add(TextEdit::singleline(min).hint_text("Min").desired_width(120.));
+                    ui.label("and");
+                    ui.add(TextEdit::singleline(max).hint_text("Max").desired_width(120.));
+                });
+                true
+            }
+            Some(ColumnFilter::IsNull | ColumnFilter::IsNotNull) | None => false,
+        };
+
+        if typed {
+            ui.label(
+                RichText::new("Values as 42, 2022-07-31 12:00:00, true or 0x2a for binaries").weak(),
+            );
+        }
+
+        ui.separator();
+
+        if ui
+            .add_enabled(filter.is_some(), egui::Button::new("Clear the filter"))
+            .clicked()
+        {
+            filter = None;
+            ui.close_menu();
+        }
+    })
+    .response
+    .on_hover_text("Filter the rows on the column");
+
+    match filter {
+        Some(filter) => view.filters.insert(column, filter),
+        None => view.filters.remove(&column),
+    };
+}
+
+/// The context menu copying the selected cells.
+fn copy_menu(response: Response, copy: &mut Option<CopyFormat>) {
+    response.context_menu(|ui| {
+        for format in CopyFormat::all() {
+            if ui.button(format.name()).clicked() {
+                *copy = Some(format);
+                ui.close_menu();
+            }
+        }
+    });
+}
+
+/// The value given to a `NULL` cell of a column when it's edited, typed after the other values
+/// of the column.
+fn default_value(values: &SGDBColumnValues) -> SGDBRowValue {
+    let sample = values
+        .iter()
+        .find(|value| !matches!(value, SGDBRowValue::Null | SGDBRowValue::Unknown { .. }));
+
+    match &sample {
+        Some(SGDBRowValue::Boolean(_)) => SGDBRowValue::Boolean(false),
+        Some(SGDBRowValue::Integer(_)) => SGDBRowValue::Integer(0),
+        Some(SGDBRowValue::UInteger(_)) => SGDBRowValue::UInteger(0),
+        Some(SGDBRowValue::Double(_)) => SGDBRowValue::Double(0.),
+        Some(SGDBRowValue::Decimal(_)) => SGDBRowValue::Decimal(0.into()),
+        Some(SGDBRowValue::DateTime(_)) => SGDBColumnType::DateTime.default_value(),
+        Some(SGDBRowValue::Timestamp(_)) => SGDBColumnType::Timestamp.default_value(),
+        Some(SGDBRowValue::Date(_)) => SGDBColumnType::Date.default_value(),
+        Some(SGDBRowValue::Time(_)) => SGDBColumnType::Time.default_value(),
+        Some(SGDBRowValue::Binary(_)) => SGDBRowValue::Binary(vec![]),
+        Some(SGDBRowValue::Json(_)) => SGDBColumnType::Json.default_value(),
+        Some(SGDBRowValue::Uuid(_)) => SGDBColumnType::Uuid.default_value(),
+        _ => SGDBRowValue::Text(String::new()),
+    }
+}
+
+/// A text field for a value which can't be edited directly, the text is kept while the field
+/// has the focus so it can be invalid while typing.
+fn parsed_text_edit<T: ToString + FromStr>(ui: &mut Ui, id: Id, value: &mut T) -> Response {
+    let mut text = if ui.memory().has_focus(id) {
+        ui.data()
+            .get_temp::<String>(id)
+            .unwrap_or_else(|| value.to_string())
+    } else {
+        value.to_string()
+    };
+
+    let response = ui.add(TextEdit::singleline(&mut text).id(id));
+
+    if let Ok(parsed) = text.parse() {
+        *value = parsed;
+    }
+    ui.data().insert_temp(id, text);
+
+    response
+}
+
+/// A date picker and a time field.
+fn datetime_edit(ui: &mut Ui, id: Id, value: &mut NaiveDateTime) -> Response {
+    let mut date = value.date();
+    let mut time = value.time();
+    let id_source = format!("{:?}", id);
+
+    let response = ui
+        .horizontal(|ui| {
+            ui.add(egui_extras::DatePickerButton::new(&mut date).id_source(&id_source));
+            parsed_text_edit::<NaiveTime>(ui, id, &mut time)
+        })
+        .inner;
+
+    *value = date.and_time(time);
+    response
 }
 
 pub trait MetaTableCell {
     fn table_cell(&self, ui: &mut Ui, field: &SGDBRowValue);
+    fn table_cell_edit(
+        &self,
+        ui: &mut Ui,
+        id: Id,
+        field: &mut SGDBRowValue,
+        default: impl FnOnce() -> SGDBRowValue,
+    );
 }
 
 impl MetaTableCell for MetaColumn {
@@ -70,10 +447,16 @@ impl MetaTableCell for MetaColumn {
             match &self.r#type {
                 MetaColumnType::Text { color } => {
                     // ui.with_layout(Layout::left_to_right(), |ui| {
-                        if let SGDBRowValue::Text(text) = field {
-                            ui.label(text);
-                        } else {
-                            invalid_type(ui);
+                        match field {
+                            SGDBRowValue::Text(text) => {
+                                ui.label(text);
+                            }
+                            SGDBRowValue::Json(_) | SGDBRowValue::Uuid(_) => {
+                                ui.label(self.display_text(field).unwrap_or_default());
+                            }
+                            _ => {
+                                invalid_type(ui);
+                            }
                         }
                     // });
                 }
@@ -86,46 +469,113 @@ impl MetaTableCell for MetaColumn {
                         }
                     // });
                 }
-                MetaColumnType::Number { variant } => {
-                    // ui.with_layout(Layout::top_down(Align::Center), |ui| {
-                        if let SGDBRowValue::Integer(v) = field {
-                            match variant {
-                                MetaColNumber::Simple => ui.label(v.to_string()),
-                                MetaColNumber::Money => ui.label(format!("{:2}€", v.to_string())),
-                            };
-                        } else if let SGDBRowValue::UInteger(v) = field {
-                            match variant {
-                                MetaColNumber::Simple => ui.label(v.to_string()),
-                                MetaColNumber::Money => ui.label(format!("{:2}€", v.to_string())),
-                            };
-                        } else if let SGDBRowValue::Decimal(v) = field {
-                            match variant {
-                                MetaColNumber::Simple => ui.label(v.to_string()),
-                                MetaColNumber::Money => ui.label(format!("{:2}€", v.to_string())),
-                            };
-                        } else if let SGDBRowValue::Double(v) = field {
-                            match variant {
-                                MetaColNumber::Simple => ui.label(v.to_string()),
-                                MetaColNumber::Money => ui.label(format!("{:2}€", v.to_string())),
-                            };
-                        } else {
-                            invalid_type(ui);
-                        }
-                    // });
-                }
-                MetaColumnType::DateTime { format } => {
-                    if let SGDBRowValue::DateTime(v) = field {
-                        ui.label(v.format(format).to_string());
+                MetaColumnType::Number { .. } => match field {
+                    SGDBRowValue::Integer(_)
+                    | SGDBRowValue::UInteger(_)
+                    | SGDBRowValue::Decimal(_)
+                    | SGDBRowValue::Double(_) => {
+                        ui.label(self.display_text(field).unwrap_or_default());
+                    }
+                    _ => {
+                        invalid_type(ui);
+                    }
+                },
+                MetaColumnType::DateTime { .. } => match field {
+                    SGDBRowValue::DateTime(_)
+                    | SGDBRowValue::Timestamp(_)
+                    | SGDBRowValue::Date(_)
+                    | SGDBRowValue::Time(_) => {
+                        ui.label(self.display_text(field).unwrap_or_default());
+                    }
+                    _ => {
+                        invalid_type(ui);
+                    }
+                },
+                MetaColumnType::Image(image_type) => todo!(),
+                MetaColumnType::Binary => {
+                    if let SGDBRowValue::Binary(v) = field {
+                        ui.label(format!("<{} bytes>", v.len()));
                     } else {
                         invalid_type(ui);
                     }
                 }
-                MetaColumnType::Image(image_type) => todo!(),
-                MetaColumnType::Binary => todo!(),
                 MetaColumnType::Unknown => {
                     ui.colored_label(Color32::RED, "Unknown type");
                 }
             }
         }
     }
+
+    fn table_cell_edit(
+        &self,
+        ui: &mut Ui,
+        id: Id,
+        field: &mut SGDBRowValue,
+        default: impl FnOnce() -> SGDBRowValue,
+    ) {
+        if let SGDBRowValue::Null = field {
+            if ui
+                .button(RichText::new("null").color(Color32::LIGHT_BLUE))
+                .on_hover_text("Click to set a value")
+                .clicked()
+            {
+                *field = default();
+            }
+            return;
+        }
+
+        let response = match (&self.r#type, &mut *field) {
+            (MetaColumnType::Text { .. }, SGDBRowValue::Text(text)) => {
+                ui.add(TextEdit::singleline(text).id(id))
+            }
+            (MetaColumnType::Text { .. }, SGDBRowValue::Json(v)) => parsed_text_edit(ui, id, v),
+            (MetaColumnType::Text { .. }, SGDBRowValue::Uuid(v)) => parsed_text_edit(ui, id, v),
+            (MetaColumnType::CheckBox, SGDBRowValue::Boolean(v)) => ui.checkbox(v, ""),
+            (MetaColumnType::Number { .. }, SGDBRowValue::Integer(v)) => {
+                ui.add(DragValue::new(v))
+            }
+            (MetaColumnType::Number { .. }, SGDBRowValue::UInteger(v)) => {
+                ui.add(DragValue::new(v))
+            }
+            (MetaColumnType::Number { .. }, SGDBRowValue::Double(v)) => {
+                ui.add(DragValue::new(v).speed(0.1))
+            }
+            (MetaColumnType::Number { .. }, SGDBRowValue::Decimal(v)) => {
+                parsed_text_edit(ui, id, v)
+            }
+            (MetaColumnType::DateTime { .. }, SGDBRowValue::DateTime(v)) => datetime_edit(ui, id, v),
+            // Edited in the time zone it's shown in
+            (MetaColumnType::DateTime { time_zone, .. }, SGDBRowValue::Timestamp(v)) => {
+                let time_zone = time_zone.unwrap_or(Tz::UTC);
+                let shown = v.with_timezone(&time_zone).naive_local();
+                let mut local = shown;
+                let response = datetime_edit(ui, id, &mut local);
+
+                // A time repeated when the clocks go back is only changed when edited
+                if local != shown {
+                    if let Some(timestamp) = time_zone.from_local_datetime(&local).earliest() {
+                        *v = timestamp.with_timezone(&Utc);
+                    }
+                }
+                response
+            }
+            (MetaColumnType::DateTime { .. }, SGDBRowValue::Date(v)) => {
+                let id_source = format!("{:?}", id);
+                ui.add(egui_extras::DatePickerButton::new(v).id_source(&id_source))
+            }
+            (MetaColumnType::DateTime { .. }, SGDBRowValue::Time(v)) => {
+                parsed_text_edit(ui, id, v)
+            }
+            _ => {
+                ui.label("This type can't be edited")
+            }
+        };
+
+        response.context_menu(|ui| {
+            if ui.button("Set NULL").clicked() {
+                *field = SGDBRowValue::Null;
+                ui.close_menu();
+            }
+        });
+    }
 }
@@ -2,3 +2,9 @@ pub mod icons;
 pub mod sql_editor;
 pub mod meta_table;
 pub mod meta_grid;
+pub mod row_form;
+pub mod query_error;
+pub mod history_menu;
+pub mod script_result;
+pub mod export_menu;
+pub mod pager;
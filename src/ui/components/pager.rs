@@ -0,0 +1,83 @@
+use eframe::egui::{self, Ui};
+
+use crate::ui::components::icons;
+
+/// The page sizes offered.
+const PAGE_SIZES: &[usize] = &[100, 500, 1000, 5000];
+
+pub enum PageAction {
+    First,
+    Previous,
+    Next,
+    Last,
+    PageSize(usize),
+}
+
+/// The controls of the pages of a table of `total` rows, `None` until they're counted. The
+/// `rows` loaded start at `offset`, `end` tells whether they're the last ones of the table.
+pub fn pager(
+    ui: &mut Ui,
+    offset: u64,
+    rows: usize,
+    page_size: usize,
+    total: Option<u64>,
+    end: bool,
+) -> Option<PageAction> {
+    let mut action = None;
+
+    if ui
+        .add_enabled(offset > 0, egui::Button::new(icons::ICON_FIRST))
+        .on_hover_text("First page")
+        .clicked()
+    {
+        action = Some(PageAction::First);
+    }
+    if ui
+        .add_enabled(offset > 0, egui::Button::new(icons::ICON_PREVIOUS))
+        .on_hover_text("Previous page")
+        .clicked()
+    {
+        action = Some(PageAction::Previous);
+    }
+
+    let last = offset + rows as u64;
+    let range = if rows == 0 {
+        "No rows".to_string()
+    } else {
+        format!("Rows {} - {}", offset + 1, last)
+    };
+    match total {
+        Some(total) => ui.label(format!("{} of {}", range, total)),
+        None => ui.label(range).on_hover_text("Counting the rows of the table.."),
+    };
+
+    if ui
+        .add_enabled(!end, egui::Button::new(icons::ICON_NEXT))
+        .on_hover_text("Next page")
+        .clicked()
+    {
+        action = Some(PageAction::Next);
+    }
+    if ui
+        .add_enabled(!end && total.is_some(), egui::Button::new(icons::ICON_LAST))
+        .on_hover_text("Last page")
+        .clicked()
+    {
+        action = Some(PageAction::Last);
+    }
+
+    egui::ComboBox::from_id_source("page_size")
+        .selected_text(format!("{} rows per page", page_size))
+        .show_ui(ui, |ui| {
+            for size in PAGE_SIZES {
+                if ui
+                    .selectable_label(*size == page_size, format!("{}", size))
+                    .clicked()
+                {
+                    action = Some(PageAction::PageSize(*size));
+                }
+            }
+        });
+
+    action
+}
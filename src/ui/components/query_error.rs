@@ -0,0 +1,36 @@
+use eframe::egui::{RichText, Ui};
+use eframe::epaint::Color32;
+
+use crate::db::sgdb::SGDBError;
+
+/// The line and the column, from 1, of the byte `offset` in `text`.
+pub fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = text.get(..offset).unwrap_or(text);
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+
+    (line, column)
+}
+
+/// Show an error returned for `query`, with its codes and the line it points to.
+pub fn query_error(ui: &mut Ui, err: &SGDBError, query: &str) {
+    let mut title = String::from("Query failed");
+    if let Some(code) = &err.code {
+        title += &format!(", error {}", code);
+    }
+    if let Some(sqlstate) = &err.sqlstate {
+        title += &format!(" (SQLSTATE {})", sqlstate);
+    }
+
+    ui.colored_label(Color32::RED, RichText::new(title).strong());
+    ui.label(&err.message);
+
+    if let Some(position) = err.position {
+        let (line, column) = line_column(query, position);
+        let text = query.lines().nth(line - 1).unwrap_or_default();
+
+        ui.add_space(4.);
+        ui.label(RichText::new(format!("At line {}, column {}:", line, column)).weak());
+        ui.label(RichText::new(format!("{}\n{}^", text, " ".repeat(column - 1))).monospace());
+    }
+}
@@ -0,0 +1,116 @@
+use egui::{Grid, Id, RichText, Ui};
+use indexmap::IndexMap;
+
+use crate::db::sgdb::{SGDBRowValue, SGDBTableColumn};
+use crate::meta::MetaColumn;
+use crate::ui::components::meta_table::MetaTableCell;
+
+struct RowFormField {
+    column: SGDBTableColumn,
+    /// Leave the column out of the statement so the database sets its default value.
+    default: bool,
+    null: bool,
+    value: SGDBRowValue,
+}
+
+/// A form to fill the values of a new row, built from the columns of its table.
+pub struct RowForm {
+    fields: IndexMap<String, RowFormField>,
+}
+
+impl RowForm {
+    pub fn new(columns: &[SGDBTableColumn]) -> Self {
+        let fields = columns
+            .iter()
+            .map(|column| {
+                let field = RowFormField {
+                    column: column.clone(),
+                    default: column.auto_increment || column.default.is_some(),
+                    null: column.nullable && column.default.is_none(),
+                    value: column.r#type.default_value(),
+                };
+
+                (column.name.clone(), field)
+            })
+            .collect();
+
+        Self { fields }
+    }
+
+    pub fn show(&mut self, ui: &mut Ui) {
+        Grid::new("row_form")
+            .num_columns(5)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(RichText::new("Column").underline());
+                ui.label(RichText::new("Type").underline());
+                ui.label(RichText::new("Default").underline());
+                ui.label(RichText::new("NULL").underline());
+                ui.label(RichText::new("Value").underline());
+                ui.end_row();
+
+                for (name, field) in self.fields.iter_mut() {
+                    let column = &field.column;
+
+                    ui.label(name);
+
+                    if column.auto_increment {
+                        ui.label(format!("{} (auto increment)", column.column_type));
+                    } else {
+                        ui.label(&column.column_type);
+                    }
+
+                    let has_default = column.auto_increment || column.default.is_some();
+                    ui.add_enabled(has_default, egui::Checkbox::new(&mut field.default, ""))
+                        .on_hover_text(column.default.as_deref().unwrap_or("auto increment"))
+                        .on_disabled_hover_text("The column has no default value");
+
+                    ui.add_enabled(
+                        column.nullable && !field.default,
+                        egui::Checkbox::new(&mut field.null, ""),
+                    )
+                    .on_disabled_hover_text("The column is not nullable");
+
+                    if field.default {
+                        ui.label(RichText::new("default").weak());
+                    } else if field.null {
+                        ui.label(RichText::new("null").color(egui::Color32::LIGHT_BLUE));
+                    } else {
+                        let meta_column = MetaColumn::default_sgdb_column(name, column.r#type);
+                        let r#type = column.r#type;
+
+                        meta_column.table_cell_edit(
+                            ui,
+                            Id::new("row_form").with(name),
+                            &mut field.value,
+                            || r#type.default_value(),
+                        );
+
+                        // The NULL checkbox is the way to set NULL here
+                        if let SGDBRowValue::Null = field.value {
+                            field.value = r#type.default_value();
+                            field.null = column.nullable;
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+
+    /// The values of the columns which aren't left to their default value.
+    pub fn values(&self) -> Vec<(String, SGDBRowValue)> {
+        self.fields
+            .iter()
+            .filter(|(_, field)| !field.default)
+            .map(|(name, field)| {
+                let value = if field.null {
+                    SGDBRowValue::Null
+                } else {
+                    field.value.clone()
+                };
+
+                (name.clone(), value)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,66 @@
+use eframe::egui::{self, RichText, Ui};
+
+use crate::meta::ScriptResult;
+
+/// Pick the result set of `res` to show, `None` for the status of its statements. Returns
+/// whether the shown result changed.
+///
+/// Nothing is shown for a single statement returning at most one result set.
+pub fn result_sets_bar(ui: &mut Ui, res: &ScriptResult, shown: &mut Option<usize>) -> bool {
+    if res.statements.len() <= 1 && res.result_sets.len() <= 1 && res.error.is_none() {
+        return false;
+    }
+
+    let mut changed = false;
+
+    ui.horizontal_wrapped(|ui| {
+        let label = format!("Statements ({})", res.statements.len());
+        if ui.selectable_label(shown.is_none(), label).clicked() {
+            changed |= shown.take().is_some();
+        }
+
+        for (index, result_set) in res.result_sets.iter().enumerate() {
+            let label = format!("Result {} ({} rows)", index + 1, result_set.num_rows);
+            if ui.selectable_label(*shown == Some(index), label).clicked() {
+                changed |= shown.replace(index) != Some(index);
+            }
+        }
+    });
+
+    ui.separator();
+
+    changed
+}
+
+/// The status of each statement run by the script.
+pub fn statements_ui(ui: &mut Ui, res: &ScriptResult) {
+    egui::Grid::new("script_statements")
+        .striped(true)
+        .spacing([20.0, 4.0])
+        .show(ui, |ui| {
+            for header in ["#", "Statement", "Rows", "Affected rows", "Last insert id", "Duration"] {
+                ui.label(RichText::new(header).underline());
+            }
+            ui.end_row();
+
+            for (index, statement) in res.statements.iter().enumerate() {
+                let first_line = statement.query.lines().next().unwrap_or_default();
+
+                ui.label(format!("{}", index + 1));
+                ui.label(RichText::new(first_line).monospace())
+                    .on_hover_text(&statement.query);
+                ui.label(format!("{}", statement.rows));
+                ui.label(format!("{}", statement.rows_affected));
+                match statement.last_insert_id {
+                    Some(id) => ui.label(format!("{}", id)),
+                    None => ui.label(""),
+                };
+                ui.label(format!("{} ms", statement.duration.as_millis()));
+                ui.end_row();
+            }
+        });
+
+    if res.statements.is_empty() && res.error.is_none() {
+        ui.label(RichText::new("The script has no statements").weak());
+    }
+}
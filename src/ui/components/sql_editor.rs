@@ -1,5 +1,13 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
 use eframe::egui;
-use eframe::egui::text::LayoutJob;
+use eframe::egui::text::{CCursor, CCursorRange, LayoutJob, LayoutSection, TextFormat};
+use eframe::egui::widgets::text_edit::{TextEditOutput, TextEditState};
+use eframe::egui::{Key, Modifiers};
+
+use crate::db::sgdb::SGDBKind;
+use crate::sql::{self, completion::{self, CompletionContext}};
 
 pub fn code_view_ui_read_only(ui: &mut egui::Ui, mut code: &str) {
     let language = "sql";
@@ -23,23 +31,302 @@ pub fn code_view_ui_read_only(ui: &mut egui::Ui, mut code: &str) {
 
 /// View some code with syntax highlighting and selection.
 pub fn code_view_ui(ui: &mut egui::Ui, code: &mut String) {
+    code_view_ui_with_error(ui, code, None);
+}
+
+/// Like `code_view_ui`, the token at the byte offset `error` is marked as the location of an
+/// error.
+pub fn code_view_ui_with_error(ui: &mut egui::Ui, code: &mut String, error: Option<usize>) {
+    code_editor(ui, code, None, error, None);
+}
+
+/// A part of the code to run, by its byte range.
+pub enum RunRequest {
+    /// The statement around the cursor, on Ctrl+Enter.
+    Statement(Range<usize>),
+    /// The selected text, on Ctrl+Shift+Enter.
+    Selection(Range<usize>),
+}
+
+/// The schema suggested by the completion of `query_editor_ui`.
+pub struct CompletionSchema<'a> {
+    pub kind: SGDBKind,
+    pub tables: Vec<&'a str>,
+    /// The column names of a table, `None` until they are fetched.
+    pub columns: &'a mut dyn FnMut(&str) -> Option<Vec<String>>,
+}
+
+/// The editor of the queries to run, like `code_view_ui_with_error` with the `executed` byte
+/// range highlighted, and the completion of the words typed from `schema`.
+pub fn query_editor_ui(
+    ui: &mut egui::Ui,
+    code: &mut String,
+    error: Option<usize>,
+    executed: Option<Range<usize>>,
+    mut schema: CompletionSchema,
+) -> Option<RunRequest> {
+    let id = ui.make_persistent_id("query_editor");
+    let focused = ui.memory().has_focus(id);
+    let mut popup = ui
+        .data()
+        .get_temp::<CompletionPopup>(id)
+        .filter(|_| focused)
+        .unwrap_or_default();
+
+    // Consumed before the editor sees them, it would insert a new line
+    let (run_statement, run_selection) = if focused {
+        let mut input = ui.input_mut();
+        let run_selection = input.consume_key(Modifiers::COMMAND | Modifiers::SHIFT, Key::Enter);
+        let run_statement = input.consume_key(Modifiers::COMMAND, Key::Enter);
+        (run_statement, run_selection)
+    } else {
+        (false, false)
+    };
+
+    let mut accept = false;
+    if popup.word_start.is_some() {
+        let mut input = ui.input_mut();
+        if input.consume_key(Modifiers::NONE, Key::ArrowDown) {
+            popup.selected += 1;
+        }
+        if input.consume_key(Modifiers::NONE, Key::ArrowUp) {
+            popup.selected = popup.selected.saturating_sub(1);
+        }
+        if input.consume_key(Modifiers::NONE, Key::Escape) {
+            popup.word_start = None;
+        }
+        accept = input.consume_key(Modifiers::NONE, Key::Enter)
+            | input.consume_key(Modifiers::NONE, Key::Tab);
+    }
+
+    let output = code_editor(ui, code, Some(id), error, executed);
+
+    let cursor_range = output.cursor_range?;
+    let [start, end] = cursor_range.as_ccursor_range().sorted();
+    let start = byte_offset(code, start.index);
+    let end = byte_offset(code, end.index);
+
+    let context = Some(start)
+        .filter(|_| focused && start == end)
+        .and_then(|offset| completion::context(code, offset))
+        .filter(|context| {
+            let typed = !context.word.is_empty() || context.qualifier.is_some();
+            popup.word_start == Some(context.word.start) || (output.response.changed() && typed)
+        });
+
+    let candidates = context
+        .as_ref()
+        .map(|context| candidates(context, &code[context.word.clone()], &mut schema))
+        .unwrap_or_default();
+
+    match context.filter(|_| !candidates.is_empty()) {
+        Some(context) => {
+            popup.word_start = Some(context.word.start);
+            popup.selected = popup.selected.min(candidates.len() - 1);
+
+            let cursor = output
+                .galley
+                .pos_from_cursor(&cursor_range.primary)
+                .translate(output.text_draw_pos.to_vec2());
+            let clicked = completion_popup_ui(ui, id, cursor.left_bottom(), &candidates, popup.selected);
+
+            if let Some(selected) = clicked.or(Some(popup.selected).filter(|_| accept)) {
+                let candidate = &candidates[selected];
+                code.replace_range(context.word.clone(), candidate);
+
+                let end = code[..context.word.start + candidate.len()].chars().count();
+                if let Some(mut state) = TextEditState::load(ui.ctx(), id) {
+                    state.set_ccursor_range(Some(CCursorRange::one(CCursor::new(end))));
+                    state.store(ui.ctx(), id);
+                }
+
+                ui.memory().request_focus(id);
+                ui.ctx().request_repaint();
+                popup = CompletionPopup::default();
+            }
+        }
+        None => popup = CompletionPopup::default(),
+    }
+
+    ui.data().insert_temp(id, popup);
+
+    if run_selection && start < end {
+        Some(RunRequest::Selection(start..end))
+    } else if run_statement {
+        let statement = sql::statement_at(code, start)?;
+        Some(RunRequest::Statement(
+            statement.start..statement.start + statement.text.len(),
+        ))
+    } else {
+        None
+    }
+}
+
+fn code_editor(
+    ui: &mut egui::Ui,
+    code: &mut String,
+    id: Option<egui::Id>,
+    error: Option<usize>,
+    executed: Option<Range<usize>>,
+) -> TextEditOutput {
     let language = "sql";
     let theme = CodeTheme::from_memory(ui.ctx());
+    let executed_color = ui.visuals().selection.bg_fill.linear_multiply(0.5);
 
     let mut layouter = |ui: &egui::Ui, string: &str, _wrap_width: f32| {
-        let layout_job = highlight(ui.ctx(), &theme, string, language);
+        let mut layout_job = highlight(ui.ctx(), &theme, string, language);
         // layout_job.wrap.max_width = wrap_width; // no wrapping
+        if let Some(range) = executed.clone().filter(|range| range.end <= string.len()) {
+            mark_range(&mut layout_job, range, |format| format.background = executed_color);
+        }
+        if let Some(range) = error.and_then(|offset| error_range(string, offset)) {
+            mark_range(&mut layout_job, range, |format| {
+                format.background = egui::Color32::from_rgba_unmultiplied(255, 0, 0, 60);
+                format.underline = egui::Stroke::new(1.5, egui::Color32::RED);
+            });
+        }
         ui.fonts().layout_job(layout_job)
     };
 
-    ui.add(
-        egui::TextEdit::multiline(code)
-            .font(egui::TextStyle::Monospace) // for cursor height
-            .code_editor()
-            .desired_rows(1)
-            .lock_focus(true)
-            .layouter(&mut layouter),
-    );
+    let mut editor = egui::TextEdit::multiline(code)
+        .font(egui::TextStyle::Monospace) // for cursor height
+        .code_editor()
+        .desired_rows(1)
+        .lock_focus(true)
+        .layouter(&mut layouter);
+    if let Some(id) = id {
+        editor = editor.id(id);
+    }
+
+    editor.show(ui)
+}
+
+/// The state of the completion popup of `query_editor_ui`.
+#[derive(Clone, Default)]
+struct CompletionPopup {
+    /// The start of the word completed, the popup is shown while it is typed.
+    word_start: Option<usize>,
+    selected: usize,
+}
+
+/// The most candidates shown by the completion popup.
+const MAX_CANDIDATES: usize = 12;
+
+/// The completions of `word`: the columns of the qualified table, or the tables where a table
+/// is expected, or else the columns of the tables of the statement, the tables, the functions
+/// and the keywords.
+fn candidates(context: &CompletionContext, word: &str, schema: &mut CompletionSchema) -> Vec<String> {
+    let mut all = Vec::new();
+
+    if let Some(table) = context.qualified_table() {
+        all.extend((schema.columns)(table).unwrap_or_default());
+    } else if context.expects_table {
+        all.extend(schema.tables.iter().map(|table| table.to_string()));
+    } else {
+        for table in &context.tables {
+            all.extend((schema.columns)(&table.name).unwrap_or_default());
+        }
+        all.extend(schema.tables.iter().map(|table| table.to_string()));
+
+        // Keep the case of the word typed
+        let lowercase = word.chars().any(char::is_lowercase);
+        let words = completion::functions(schema.kind).chain(completion::keywords());
+        all.extend(words.map(|word| if lowercase { word.to_lowercase() } else { word.to_string() }));
+    }
+
+    let word = word.to_lowercase();
+    let mut seen = HashSet::new();
+    all.into_iter()
+        .filter(|candidate| {
+            let lowercase = candidate.to_lowercase();
+            lowercase.starts_with(&word) && lowercase != word && seen.insert(lowercase)
+        })
+        .take(MAX_CANDIDATES)
+        .collect()
+}
+
+/// Show the `candidates` below the cursor at `pos`, returns the one clicked.
+fn completion_popup_ui(
+    ui: &egui::Ui,
+    id: egui::Id,
+    pos: egui::Pos2,
+    candidates: &[String],
+    selected: usize,
+) -> Option<usize> {
+    let mut clicked = None;
+
+    egui::Area::new(id.with("completion"))
+        .order(egui::Order::Foreground)
+        .fixed_pos(pos)
+        .show(ui.ctx(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                for (index, candidate) in candidates.iter().enumerate() {
+                    let text = egui::RichText::new(candidate).monospace();
+                    if ui.selectable_label(index == selected, text).clicked() {
+                        clicked = Some(index);
+                    }
+                }
+            });
+        });
+
+    clicked
+}
+
+/// The byte offset of the character at `index` in `code`.
+fn byte_offset(code: &str, index: usize) -> usize {
+    code.char_indices().nth(index).map_or(code.len(), |(offset, _)| offset)
+}
+
+/// The token at the byte `offset`, or the last one if the error is at the end of the code.
+fn error_range(code: &str, offset: usize) -> Option<Range<usize>> {
+    let tokens = sql::tokenize(code);
+    let tokens = tokens.iter().filter(|token| !token.is_trivia());
+
+    let token = tokens
+        .clone()
+        .find(|token| token.end() > offset)
+        .or_else(|| tokens.last())?;
+
+    Some(token.start..token.end())
+}
+
+/// Change the format of the `range` of `job`.
+fn mark_range(job: &mut LayoutJob, range: Range<usize>, mark: impl Fn(&mut TextFormat)) {
+    let mut sections = Vec::with_capacity(job.sections.len() + 2);
+
+    for section in job.sections.drain(..) {
+        let bytes = section.byte_range.clone();
+        let start = range.start.clamp(bytes.start, bytes.end);
+        let end = range.end.clamp(bytes.start, bytes.end);
+
+        let parts = [
+            (bytes.start..start, false),
+            (start..end, true),
+            (end..bytes.end, false),
+        ];
+
+        for (part, is_marked) in parts {
+            if part.is_empty() {
+                continue;
+            }
+
+            let mut section = LayoutSection {
+                leading_space: if part.start == bytes.start {
+                    section.leading_space
+                } else {
+                    0.0
+                },
+                byte_range: part,
+                format: section.format.clone(),
+            };
+            if is_marked {
+                mark(&mut section.format);
+            }
+            sections.push(section);
+        }
+    }
+
+    job.sections = sections;
 }
 
 /// Memoized Code highlighting
@@ -1,22 +1,46 @@
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use eframe::{
-    egui::{self, Frame, Layout, ScrollArea, Ui},
-    emath::Align,
+    egui::{self, Frame, Key, Layout, Modifiers, RichText, ScrollArea, Ui},
+    emath::{Align, Vec2},
     epaint::Color32,
 };
-use flume::{Sender, Receiver};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     app::AppData,
-    meta::{MetaColumn, MetaQuery, FetchResult}, ui::components::{icons, sql_editor, meta_table}, config::{SqlifeConfig, ConnectionConfig},
+    history::{PendingEntry, QueryHistory},
+    meta::{CellSelection, MetaQuery, FetchResult, RowEdits, RowView, ScriptResult, ShownRows}, ui::components::{icons, sql_editor::{self, CompletionSchema, RunRequest}, meta_table, query_error, script_result, history_menu::{self, HistoryAction}, row_form::RowForm, export_menu::{self, ExportAction}, pager::{self, PageAction}}, config::{SqlifeConfig, ConnectionConfig},
+    export, sql,
 };
 use crate::db::{
-    sgdb::SGDBTable,
+    sgdb::{
+        SGDBColumnKey, SGDBError, SGDBKind, SGDBScriptResult, SGDBStatement, SGDBTable,
+        SGDBTableColumn,
+    },
         Message, MessageResponse,
     };
 
-use super::{MessageID, QueryState, View};
+use super::{
+    table_structure_view, CurrentView, MessageID, QueryState, RequestID, Requester, View, ViewKind,
+};
+
+/// How long the executed part of the query stays highlighted in the editor.
+const EXECUTED_HIGHLIGHT: Duration = Duration::from_millis(800);
+
+/// The number of rows of the pages of a table, until another size is picked.
+const PAGE_SIZE: usize = 500;
+
+/// The row limits offered for the queries.
+const ROW_LIMITS: &[usize] = &[100, 1000, 10_000, 100_000];
+
+/// The number of cells exported without their value listed under the export warning.
+const EXPORT_WARNINGS_SHOWN: usize = 20;
 
 #[derive(PartialEq, Eq, Serialize, Deserialize)]
 enum BottomTab {
@@ -25,19 +49,295 @@ enum BottomTab {
 }
 
 
+/// A query of the editor with its own result.
+#[derive(Serialize, Deserialize)]
+pub struct QueryTab {
+    name: String,
+    /// The pinned tabs are kept first and can't be closed.
+    pinned: bool,
+    query: String,
+    /// The scroll offset of the result.
+    scroll_offset: Vec2,
+
+    #[serde(skip)]
+    fetch_result: QueryState<ScriptResult, SGDBError>,
+    /// The result set of `fetch_result` shown, `None` for the status of its statements.
+    #[serde(skip)]
+    result_set: Option<usize>,
+    /// The query of `fetch_result`.
+    #[serde(skip)]
+    executed_query: String,
+    /// The byte range of `executed_query` in `query`, unless it was edited since.
+    #[serde(skip)]
+    executed_range: Range<usize>,
+    /// When `executed_range` was run, to highlight it for a moment.
+    #[serde(skip)]
+    executed_at: Option<Instant>,
+    /// The history entry of `executed_query`, added once `fetch_result` is received.
+    #[serde(skip)]
+    history_entry: Option<PendingEntry>,
+    /// Whether `fetch_result` is the result of a query run by the user rather than of a page or
+    /// a refresh, its statements are counted in the open transaction once they succeed.
+    #[serde(skip)]
+    user_query: bool,
+    /// The table the rows of `fetch_result` come from, if they can be edited.
+    #[serde(skip)]
+    edit_table: Option<String>,
+    /// The columns of `edit_table`, or why the rows can't be edited.
+    #[serde(skip)]
+    edit_columns: QueryState<Vec<SGDBTableColumn>>,
+    #[serde(skip)]
+    edits: RowEdits,
+    #[serde(skip)]
+    selection: BTreeSet<usize>,
+    /// The selected rows wait for the user to accept deleting them.
+    #[serde(skip)]
+    confirm_delete: bool,
+    /// The cells of the result selected to be copied.
+    #[serde(skip)]
+    cells: CellSelection,
+    /// How the rows of the result are sorted and filtered.
+    #[serde(skip)]
+    view: RowView,
+    #[serde(skip)]
+    shown: ShownRows,
+    #[serde(skip)]
+    insert_form: Option<RowForm>,
+    /// The result of the last update, insert or delete.
+    #[serde(skip)]
+    execute_result: QueryState<u64>,
+    /// The number of statements of `execute_result`, counted in the open transaction once they
+    /// succeed.
+    #[serde(skip)]
+    execute_statements: usize,
+    #[serde(skip)]
+    cancel_result: QueryState<()>,
+    /// The number of rows of the last export.
+    #[serde(skip)]
+    export_result: QueryState<u64>,
+    #[serde(skip)]
+    export_warnings: export::ExportWarnings,
+    /// The table browsed page by page, when it was picked in the list of the tables.
+    #[serde(skip)]
+    pager: Option<TablePager>,
+    /// The rows of `fetch_result` when they're streamed, kept once received to tell why they
+    /// ended.
+    #[serde(skip)]
+    stream: Option<RowStream>,
+}
+
+impl QueryTab {
+    fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            pinned: false,
+            query: String::new(),
+            scroll_offset: Vec2::ZERO,
+            fetch_result: QueryState::Ready,
+            result_set: None,
+            executed_query: String::new(),
+            executed_range: 0..0,
+            executed_at: None,
+            history_entry: None,
+            user_query: false,
+            edit_table: None,
+            edit_columns: QueryState::Ready,
+            edits: RowEdits::default(),
+            selection: BTreeSet::new(),
+            confirm_delete: false,
+            cells: CellSelection::None,
+            view: RowView::default(),
+            shown: ShownRows::default(),
+            insert_form: None,
+            execute_result: QueryState::Ready,
+            execute_statements: 0,
+            cancel_result: QueryState::Ready,
+            export_result: QueryState::Ready,
+            export_warnings: export::ExportWarnings::default(),
+            pager: None,
+            stream: None,
+        }
+    }
+
+    /// The result set shown.
+    fn result_set(&self) -> Option<&FetchResult> {
+        match &self.fetch_result {
+            QueryState::Success(res) => res.result_sets.get(self.result_set?),
+            _ => None,
+        }
+    }
+
+    /// The columns of the edited table, none until they're received.
+    fn table_columns(&self) -> &[SGDBTableColumn] {
+        match &self.edit_columns {
+            QueryState::Success(columns) => columns,
+            _ => &[],
+        }
+    }
+
+    /// The primary key columns if the rows of `res` can be edited, or why they can't.
+    fn editable_key(&self, res: &FetchResult) -> Result<Vec<String>, String> {
+        let columns = match &self.edit_columns {
+            QueryState::Success(columns) => columns,
+            QueryState::Error(err) => return Err(err.clone()),
+            QueryState::Waiting(_) | QueryState::Ready => {
+                return Err("Fetching the primary key of the table..".into())
+            }
+        };
+
+        // A name which isn't a column of the table, e.g. a function without parentheses, can't be
+        // written back
+        if let Some(col) = res
+            .res
+            .keys()
+            .find(|col| !columns.iter().any(|column| column.name == col.raw_name))
+        {
+            return Err(format!(
+                "{} is not a column of the table {}",
+                col.raw_name,
+                self.edit_table.as_deref().unwrap_or_default()
+            ));
+        }
+
+        let key = primary_key(columns);
+
+        if key.is_empty() {
+            return Err(format!(
+                "The table {} has no primary key",
+                self.edit_table.as_deref().unwrap_or_default()
+            ));
+        }
+
+        match key
+            .iter()
+            .find(|name| !res.res.keys().any(|col| &col.raw_name == *name))
+        {
+            Some(name) => Err(format!(
+                "The primary key column {} is not part of the result",
+                name
+            )),
+            None => Ok(key),
+        }
+    }
+}
+
+/// A table whose rows are fetched a page at a time. The rows are ordered by its primary key so
+/// that the pages don't overlap.
+struct TablePager {
+    table: String,
+    /// The primary key, `None` until the columns of the table are known.
+    key: Option<Vec<String>>,
+    page_size: usize,
+    /// The offset of the first row of the page.
+    offset: u64,
+    /// The number of rows asked for by the last page request.
+    limit: usize,
+    /// Whether the last rows of the table are loaded.
+    end: bool,
+    /// The number of rows of the table.
+    total: QueryState<u64>,
+    /// The rows following the loaded ones, they're added to the result once received.
+    more: QueryState<()>,
+}
+
+impl TablePager {
+    fn new(table: String) -> Self {
+        Self {
+            table,
+            key: None,
+            page_size: PAGE_SIZE,
+            offset: 0,
+            limit: PAGE_SIZE,
+            end: false,
+            total: QueryState::Ready,
+            more: QueryState::Ready,
+        }
+    }
+
+    /// The query of the `(limit, offset)` page of the table, of all its rows if `None`.
+    fn query(&self, kind: SGDBKind, page: Option<(usize, u64)>) -> String {
+        kind.select_rows(&self.table, self.key.as_deref().unwrap_or_default(), page)
+    }
+}
+
+/// The rows of the result of a query shown as they arrive, in batches.
+struct RowStream {
+    seq: u64,
+    /// The byte offset of the statement streamed in the executed query.
+    start: usize,
+    limit: Option<usize>,
+    /// Set to end the stream early, once the rows received so far are enough.
+    stop: Arc<AtomicBool>,
+    started: Instant,
+    /// Whether the last rows were received.
+    done: bool,
+}
+
+impl Drop for RowStream {
+    // The stream of a query run again or of a closed tab isn't needed anymore
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+fn primary_key(columns: &[SGDBTableColumn]) -> Vec<String> {
+    columns
+        .iter()
+        .filter(|col| col.key == SGDBColumnKey::Primary)
+        .map(|col| col.name.clone())
+        .collect()
+}
+
+/// The number of rows returned by a `SELECT COUNT(*)`.
+fn row_count(res: SGDBScriptResult) -> Result<u64, String> {
+    let res = ScriptResult::from_sgdb(res).map_err(|err| err.message)?;
+    if let Some(err) = res.error {
+        return Err(err.message);
+    }
+
+    res.result_sets
+        .first()
+        .and_then(|res| res.res.values().next()?.iter().next()?.text())
+        .and_then(|count| count.parse().ok())
+        .ok_or_else(|| "The rows can't be counted".to_string())
+}
+
+enum TabAction {
+    New,
+    Select(usize),
+    Close(usize),
+    Rename(usize),
+    EndRename,
+    TogglePin(usize),
+    Move(usize, usize),
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ViewData {
     show_left_panel: bool,
     show_bottom_panel: bool,
     bottom_tab: BottomTab,
 
-    query_history: Vec<String>,
-    query: String,
+    tabs: Vec<QueryTab>,
+    current_tab: usize,
 
     #[serde(skip)]
-    fetch_result: QueryState<FetchResult>,
+    renaming_tab: Option<usize>,
+    #[serde(skip)]
+    history_search: String,
     #[serde(skip)]
     tables: QueryState<Vec<SGDBTable>>,
+    /// The columns of the tables completed in the editor, fetched on demand.
+    #[serde(skip)]
+    columns: HashMap<String, QueryState<Vec<SGDBTableColumn>>>,
+    #[serde(skip)]
+    transaction_error: Option<String>,
+    /// The file of the last export.
+    #[serde(default)]
+    export_path: String,
+    /// The number of rows fetched by a query at most, `None` for all of them.
+    #[serde(default)]
+    row_limit: Option<usize>,
 }
 
 impl Default for ViewData {
@@ -46,21 +346,130 @@ impl Default for ViewData {
             show_left_panel: true,
             show_bottom_panel: true,
             bottom_tab: BottomTab::Query,
-            query_history: vec![],
-            query: String::new(),
+            tabs: vec![QueryTab::new("Query 1")],
+            current_tab: 0,
+            renaming_tab: None,
+            history_search: String::new(),
             tables: QueryState::Ready,
-            fetch_result: QueryState::Ready,
+            columns: HashMap::new(),
+            transaction_error: None,
+            export_path: String::new(),
+            row_limit: None,
+        }
+    }
+}
+
+impl ViewData {
+    /// Stop waiting for the responses to the requests sent, once the connection is closed.
+    pub fn abandon_requests(&mut self) {
+        self.tables.abandon();
+        self.columns.values_mut().for_each(QueryState::abandon);
+
+        for tab in &mut self.tabs {
+            tab.fetch_result.abandon();
+            tab.history_entry = None;
+            tab.edit_columns.abandon();
+            tab.execute_result.abandon();
+            tab.cancel_result.abandon();
+            tab.export_result.abandon();
+            // Its drop stops the rows still sent
+            tab.stream = None;
+
+            if let Some(pager) = tab.pager.as_mut() {
+                pager.total.abandon();
+                pager.more.abandon();
+            }
         }
     }
+
+    fn tab(&self) -> &QueryTab {
+        &self.tabs[self.current_tab]
+    }
+
+    fn tab_mut(&mut self) -> &mut QueryTab {
+        &mut self.tabs[self.current_tab]
+    }
+
+    /// The index of the tab whose pager waits for the request `seq` in `state`.
+    fn waiting_pager<T, E>(&self, seq: u64, state: impl Fn(&TablePager) -> &QueryState<T, E>) -> Option<usize> {
+        self.tabs
+            .iter()
+            .position(|tab| tab.pager.as_ref().map_or(false, |pager| state(pager).is_waiting_for(seq)))
+    }
+
+    /// The index of the tab receiving the rows of the request `seq`.
+    fn streaming_tab(&self, seq: u64) -> Option<usize> {
+        self.tabs.iter().position(|tab| {
+            tab.stream
+                .as_ref()
+                .map_or(false, |stream| stream.seq == seq && !stream.done)
+        })
+    }
+
+    /// The index of the tab waiting for the request `seq` in `state`.
+    fn waiting_tab<T, E>(&self, seq: u64, state: impl Fn(&QueryTab) -> &QueryState<T, E>) -> Option<usize> {
+        self.tabs.iter().position(|tab| state(tab).is_waiting_for(seq))
+    }
+
+    fn new_tab(&mut self) {
+        let number = (1..)
+            .find(|n| !self.tabs.iter().any(|tab| tab.name == format!("Query {}", n)))
+            .unwrap();
+
+        self.tabs.push(QueryTab::new(format!("Query {}", number)));
+        self.current_tab = self.tabs.len() - 1;
+    }
+
+    fn close_tab(&mut self, index: usize) {
+        self.tabs.remove(index);
+
+        if self.tabs.is_empty() {
+            self.new_tab();
+        } else if self.current_tab > index || self.current_tab == self.tabs.len() {
+            self.current_tab -= 1;
+        }
+    }
+
+    /// Move the tab at `from` to `to`, the pinned tabs stay before the others.
+    fn move_tab(&mut self, from: usize, to: usize) {
+        let pinned = self.tabs.iter().filter(|tab| tab.pinned).count();
+        let to = if self.tabs[from].pinned {
+            to.min(pinned.saturating_sub(1))
+        } else {
+            to.clamp(pinned, self.tabs.len() - 1)
+        };
+
+        let tab = self.tabs.remove(from);
+        self.tabs.insert(to, tab);
+
+        if self.current_tab == from {
+            self.current_tab = to;
+        } else if from < self.current_tab && self.current_tab <= to {
+            self.current_tab -= 1;
+        } else if to <= self.current_tab && self.current_tab < from {
+            self.current_tab += 1;
+        }
+    }
+
+    fn toggle_pin(&mut self, index: usize) {
+        // The tab joins the end of the pinned ones, or the start of the others
+        let pinned = self.tabs.iter().filter(|tab| tab.pinned).count();
+        let to = if self.tabs[index].pinned { pinned - 1 } else { pinned };
+
+        self.tabs[index].pinned = !self.tabs[index].pinned;
+        self.move_tab(index, to);
+    }
 }
 
 pub struct DBView<'a> {
-    pub tx: &'a Sender<Message<MessageID>>,
-    pub rx: &'a Receiver<MessageResponse<MessageID>>,
+    pub tx: Requester<'a>,
 
     pub current_connection: Option<usize>,
+    pub next_view: &'a mut Option<CurrentView>,
+    pub transaction: &'a mut Option<usize>,
 
     pub config: &'a mut SqlifeConfig,
+    pub history: &'a mut QueryHistory,
 
     pub data: &'a mut ViewData,
 }
@@ -73,9 +482,198 @@ impl<'a> DBView<'a> {
             data,
 
             current_connection: app.current_connection,
+            next_view: &mut app.next_view,
+            transaction: &mut app.transaction,
+            history: &mut app.history,
+
+            tx: Requester::new(app.tx_sgdb.as_ref().unwrap(), &app.request_seq, ViewKind::DBView),
+        }
+    }
+
+    /// Count the statements of the user which succeeded in the open transaction, the ones the
+    /// view runs by itself only read.
+    fn count_statements(&mut self, count: usize) {
+        if let Some(statements) = self.transaction.as_mut() {
+            *statements += count;
+        }
+    }
+
+    /// Run the whole query of the editor in the tab at `index`.
+    fn run_query(&mut self, index: usize) {
+        let range = 0..self.data.tabs[index].query.len();
+        self.run_range(index, range, false);
+    }
+
+    /// Run the `range` of the editor in the tab at `index`, as a single statement or split into
+    /// its statements.
+    fn run_range(&mut self, index: usize, range: Range<usize>, single: bool) {
+        let tab = &mut self.data.tabs[index];
+        let query = tab.query[range.clone()].to_string();
+        tab.executed_range = range;
+        tab.executed_at = Some(Instant::now());
+        tab.pager = None;
+
+        self.fetch(index, query, single);
+        self.data.tabs[index].user_query = true;
+    }
+
+    /// Run the last query of the tab at `index` again, once its rows may have changed.
+    fn rerun(&mut self, index: usize) {
+        // The rows loaded after the page of a table are fetched again too
+        let tab = &self.data.tabs[index];
+        if let Some(pager) = &tab.pager {
+            let limit = tab.result_set().map_or(0, |res| res.num_rows).max(pager.page_size);
+            let offset = pager.offset;
+
+            self.count_rows(index);
+            self.fetch_page(index, offset, limit);
+            return;
+        }
+
+        let query = self.data.tabs[index].executed_query.clone();
+        self.fetch(index, query, true);
+    }
+
+    /// Browse the rows of `table` in the tab at `index`, a page at a time.
+    fn open_table(&mut self, index: usize, table: String) {
+        let kind = self.config.connections[self.current_connection.unwrap()].kind;
+        let tab = &mut self.data.tabs[index];
+
+        tab.query = kind.select_rows(&table, &[], None);
+        tab.executed_at = None;
+        tab.view = RowView::default();
+        tab.pager = Some(TablePager::new(table.clone()));
+
+        // The first page is fetched once the primary key is known, loading until then
+        tab.edit_table = Some(table.clone());
+        tab.edit_columns.query(&self.tx, MessageID::EditColumns, Message::FetchColumns(table));
+        if let QueryState::Waiting(seq) = tab.edit_columns {
+            tab.fetch_result = QueryState::Waiting(seq);
+        }
+
+        self.count_rows(index);
+    }
+
+    /// Fetch the `limit` rows from `offset` of the table browsed in the tab at `index`.
+    fn fetch_page(&mut self, index: usize, offset: u64, limit: usize) {
+        let kind = self.config.connections[self.current_connection.unwrap()].kind;
+        let pager = match self.data.tabs[index].pager.as_mut() {
+            Some(pager) => pager,
+            None => return,
+        };
+
+        pager.offset = offset;
+        pager.limit = limit;
+        pager.more = QueryState::Ready;
+        let query = pager.query(kind, Some((limit, offset)));
+
+        self.fetch(index, query, true);
+    }
+
+    /// Count the rows of the table browsed in the tab at `index`.
+    fn count_rows(&mut self, index: usize) {
+        let kind = self.config.connections[self.current_connection.unwrap()].kind;
+
+        if let Some(pager) = self.data.tabs[index].pager.as_mut() {
+            let query = kind.count_rows(&pager.table);
+            pager.total.query(&self.tx, MessageID::RowCount, Message::FetchStatement(query, None));
+        }
+    }
 
-            rx: app.rx_sgdb.as_ref().unwrap(),
-            tx: app.tx_sgdb.as_ref().unwrap(),
+    fn fetch(&mut self, index: usize, query: String, single: bool) {
+        let statements = if single {
+            vec![sql::Statement::whole(&query)]
+        } else {
+            sql::split_statements(&query)
+        };
+
+        // The rows of a query are shown as they arrive, the pages of a table are fetched whole
+        let streamed = match statements.as_slice() {
+            [statement] if self.data.tabs[index].pager.is_none() => {
+                Some(*statement).filter(|statement| sql::returns_rows(statement.text))
+            }
+            _ => None,
+        };
+        let limit = self.data.row_limit;
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let message = if let Some(statement) = streamed {
+            Message::FetchStream {
+                statement: statement.text.to_string(),
+                limit,
+                stop: stop.clone(),
+            }
+        } else if single {
+            Message::FetchStatement(query.clone(), None)
+        } else {
+            Message::FetchAll(query.clone())
+        };
+
+        let tab = &mut self.data.tabs[index];
+        tab.cancel_result = QueryState::Ready;
+        tab.user_query = false;
+        tab.fetch_result.query(&self.tx, MessageID::FetchAllResult, message);
+        tab.stream = match (&tab.fetch_result, streamed) {
+            (QueryState::Waiting(seq), Some(statement)) => Some(RowStream {
+                seq: *seq,
+                start: statement.start,
+                limit,
+                stop,
+                started: Instant::now(),
+                done: false,
+            }),
+            _ => None,
+        };
+
+        tab.edits.clear();
+        tab.selection.clear();
+        tab.confirm_delete = false;
+        tab.cells = CellSelection::None;
+        // The sort and the filters are kept when the same query or another page of the table is
+        // run, the search is always kept
+        if query != tab.executed_query && tab.pager.is_none() {
+            tab.view = RowView {
+                search: std::mem::take(&mut tab.view.search),
+                ..RowView::default()
+            };
+        }
+        let table = match statements.as_slice() {
+            [statement] => sql::source_table(statement.text),
+            _ => Err("Only the rows of a single SELECT can be edited"),
+        };
+        // The columns of a browsed table are fetched once
+        let known = tab.pager.is_some()
+            && table.as_ref().ok() == tab.edit_table.as_ref()
+            && !matches!(tab.edit_columns, QueryState::Ready | QueryState::Error(_));
+        tab.edit_table = table.as_ref().ok().cloned();
+        tab.history_entry = Some(PendingEntry::new(query.clone()));
+        tab.executed_query = query;
+
+        // The columns are looked up in the schema of the connection
+        let schema = &self.config.connections[self.current_connection.unwrap()].schema;
+        let table_name = tab.edit_table.as_deref().and_then(|table| match table.rsplit_once('.') {
+            Some((qualifier, name)) if qualifier == schema => Some(name),
+            Some(_) => None,
+            None => Some(table),
+        });
+
+        match (&table, table_name) {
+            (Ok(_), _) if known => {}
+            (Ok(_), Some(table_name)) => {
+                let table_name = table_name.to_string();
+                tab.edit_columns.query(
+                    &self.tx,
+                    MessageID::EditColumns,
+                    Message::FetchColumns(table_name),
+                );
+            }
+            (Ok(_), None) => {
+                tab.edit_columns = QueryState::Error(format!(
+                    "Only the rows of the tables of the schema {} can be edited",
+                    schema
+                ));
+            }
+            (Err(reason), _) => tab.edit_columns = QueryState::Error(reason.to_string()),
         }
     }
 
@@ -90,7 +688,8 @@ impl<'a> DBView<'a> {
                     ui.with_layout(Layout::right_to_left(), |ui| {
                         if ui.button(icons::ICON_REFRESH).clicked() {
                             self.data.tables.query(
-                                self.tx,
+                                &self.tx,
+                                MessageID::Tables,
                                 Message::FetchTables,
                             );
                         }
@@ -98,37 +697,44 @@ impl<'a> DBView<'a> {
                 });
 
                 ui.separator();
+
+                let mut clicked_table = None;
+
                 ScrollArea::both().show(ui, |ui| match &self.data.tables {
                     QueryState::Success(res) => {
                         ui.vertical(|ui| {
                             for table in res.iter() {
                                 let id = ui.make_persistent_id(&table.full_path);
                                 ui.with_layout(Layout::top_down_justified(Align::Min), |ui| {
-                                    if ui
+                                    let btn = ui
                                         .button(&format!(
                                             "{} {}",
                                             icons::ICON_TABLE,
                                             table.table_name
                                         ))
-                                        .clicked()
-                                    {
-                                        self.data.query =
-                                            format!("SELECT * FROM `{}`", table.table_name);
-
-                                        self.data.fetch_result.query(
-                                            self.tx,
-                                            Message::FetchAll(
-                                                MessageID::FetchAllResult,
-                                                self.data.query.clone(),
-                                                None
-                                            ),
-                                        );
+                                        .on_hover_text("Click to browse the rows, right click for more actions");
+
+                                    if btn.clicked() {
+                                        clicked_table = Some(table.table_name.clone());
                                     }
+
+                                    btn.context_menu(|ui| {
+                                        if ui.button("Browse rows").clicked() {
+                                            clicked_table = Some(table.table_name.clone());
+                                            ui.close_menu();
+                                        }
+                                        if ui.button("View structure").clicked() {
+                                            *self.next_view = Some(CurrentView::TableStructureView(
+                                                table_structure_view::ViewData::new(&table.table_name),
+                                            ));
+                                            ui.close_menu();
+                                        }
+                                    });
                                 });
                             }
                         });
                     }
-                    QueryState::Waiting => {
+                    QueryState::Waiting(_) => {
                         ui.label("Fetching tables..");
                     }
                     QueryState::Ready => {
@@ -138,7 +744,141 @@ impl<'a> DBView<'a> {
                         ui.colored_label(Color32::RED, "An error occurred while fetching tables");
                     }
                 });
+
+                if let Some(table) = clicked_table {
+                    self.open_table(self.data.current_tab, table);
+                }
+            });
+    }
+
+    fn show_tabs_panel(&mut self, ui: &mut Ui) {
+        egui::TopBottomPanel::top("tabs_panel").show_inside(ui, |ui| {
+            let mut action = None;
+
+            ScrollArea::horizontal().show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    let last = self.data.tabs.len() - 1;
+
+                    for (index, tab) in self.data.tabs.iter_mut().enumerate() {
+                        if self.data.renaming_tab == Some(index) {
+                            let name = ui.add(
+                                egui::TextEdit::singleline(&mut tab.name).desired_width(120.),
+                            );
+
+                            if name.lost_focus() {
+                                action = Some(TabAction::EndRename);
+                            } else {
+                                name.request_focus();
+                            }
+                        } else {
+                            let mut label = tab.name.clone();
+                            if tab.pinned {
+                                label = format!("{} {}", icons::ICON_PIN, label);
+                            }
+                            if let QueryState::Waiting(_) = tab.fetch_result {
+                                label.push_str(" ..");
+                            }
+
+                            let btn = ui
+                                .selectable_label(index == self.data.current_tab, label)
+                                .on_hover_text("Double click to rename, right click for more actions");
+
+                            if btn.clicked() {
+                                action = Some(TabAction::Select(index));
+                            }
+                            if btn.double_clicked() {
+                                action = Some(TabAction::Rename(index));
+                            }
+
+                            btn.context_menu(|ui| {
+                                if ui.button("Rename").clicked() {
+                                    action = Some(TabAction::Rename(index));
+                                    ui.close_menu();
+                                }
+                                if ui.button(if tab.pinned { "Unpin" } else { "Pin" }).clicked() {
+                                    action = Some(TabAction::TogglePin(index));
+                                    ui.close_menu();
+                                }
+                                if ui.add_enabled(index > 0, egui::Button::new("Move left")).clicked() {
+                                    action = Some(TabAction::Move(index, index - 1));
+                                    ui.close_menu();
+                                }
+                                if ui.add_enabled(index < last, egui::Button::new("Move right")).clicked() {
+                                    action = Some(TabAction::Move(index, index + 1));
+                                    ui.close_menu();
+                                }
+                                ui.separator();
+                                if ui.add_enabled(!tab.pinned, egui::Button::new("Close")).clicked() {
+                                    action = Some(TabAction::Close(index));
+                                    ui.close_menu();
+                                }
+                            });
+                        }
+
+                        if !tab.pinned && ui.small_button(icons::ICON_CLOSE).clicked() {
+                            action = Some(TabAction::Close(index));
+                        }
+
+                        ui.separator();
+                    }
+
+                    if ui.button(icons::ICON_PLUS).on_hover_text("New tab").clicked() {
+                        action = Some(TabAction::New);
+                    }
+                });
             });
+
+            match action {
+                Some(TabAction::New) => self.data.new_tab(),
+                Some(TabAction::Select(index)) => self.data.current_tab = index,
+                Some(TabAction::Close(index)) => {
+                    self.data.renaming_tab = None;
+                    self.data.close_tab(index);
+                }
+                Some(TabAction::Rename(index)) => self.data.renaming_tab = Some(index),
+                Some(TabAction::EndRename) => self.data.renaming_tab = None,
+                Some(TabAction::TogglePin(index)) => {
+                    self.data.renaming_tab = None;
+                    self.data.toggle_pin(index);
+                }
+                Some(TabAction::Move(from, to)) => {
+                    self.data.renaming_tab = None;
+                    self.data.move_tab(from, to);
+                }
+                None => {}
+            }
+        });
+    }
+
+    fn show_transaction_bar(&mut self, ui: &mut Ui) {
+        match *self.transaction {
+            None => {
+                if ui
+                    .button("Begin transaction")
+                    .on_hover_text("Run the next statements in a transaction until it's committed or rolled back")
+                    .clicked()
+                {
+                    self.tx.send(MessageID::Transaction, Message::BeginTransaction);
+                }
+            }
+            Some(statements) => {
+                ui.colored_label(
+                    Color32::GOLD,
+                    format!("Transaction open / {} statements pending", statements),
+                );
+
+                if ui.button("Commit").clicked() {
+                    self.tx.send(MessageID::Transaction, Message::CommitTransaction);
+                }
+                if ui.button("Rollback").clicked() {
+                    self.tx.send(MessageID::Transaction, Message::RollbackTransaction);
+                }
+            }
+        }
+
+        if let Some(err) = &self.data.transaction_error {
+            ui.colored_label(Color32::RED, err);
+        }
     }
 
     fn show_bottom_panel(&mut self, ui: &mut Ui) {
@@ -165,36 +905,76 @@ impl<'a> DBView<'a> {
 
                         ui.separator();
 
+                        self.show_transaction_bar(ui);
+
                         ui.with_layout(Layout::right_to_left(), |ui| {
                             if ui.button(icons::ICON_RUN).clicked() {
-                                self.data.fetch_result.query(
-                                    self.tx,
-                                    Message::FetchAll(
-                                        MessageID::FetchAllResult,
-                                        self.data.query.clone(),
-                                        None
-                                    ),
-                                );
+                                self.run_query(self.data.current_tab);
                             }
 
+                            let limit = &mut self.data.row_limit;
+                            let text = match limit {
+                                Some(limit) => format!("{} rows", limit),
+                                None => "All the rows".to_string(),
+                            };
+                            egui::ComboBox::from_id_source("row_limit")
+                                .selected_text(text)
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(limit, None, "All the rows");
+                                    for rows in ROW_LIMITS {
+                                        ui.selectable_value(limit, Some(*rows), format!("{} rows", rows));
+                                    }
+                                })
+                                .response
+                                .on_hover_text("The number of rows fetched by a query at most, the pages of a table aren't limited");
+
                             if ui.button(icons::ICON_TRASH).clicked() {
-                                self.data.fetch_result = QueryState::Ready;
-                                self.data.query.clear();
+                                let tab = self.data.tab_mut();
+                                tab.fetch_result = QueryState::Ready;
+                                tab.edits.clear();
+                                tab.selection.clear();
+                                tab.cells = CellSelection::None;
+                                tab.pager = None;
+                                tab.stream = None;
+                                tab.query.clear();
                             }
 
-                            ui.menu_button(icons::ICON_HISTORY, |ui| {});
+                            let mut history_action = None;
+                            let connection = &self.config.connections[self.current_connection.unwrap()];
+                            ui.menu_button(icons::ICON_HISTORY, |ui| {
+                                history_action = history_menu::history_menu(
+                                    ui,
+                                    self.history,
+                                    &connection.name,
+                                    &mut self.data.history_search,
+                                );
+
+                                if history_action.is_some() {
+                                    ui.close_menu();
+                                }
+                            });
+
+                            match history_action {
+                                Some(HistoryAction::Load(query)) => self.data.tab_mut().query = query,
+                                Some(HistoryAction::Run(query)) => {
+                                    self.data.tab_mut().query = query;
+                                    self.run_query(self.data.current_tab);
+                                }
+                                None => {}
+                            }
 
                             ui.separator();
 
-                            if let QueryState::Success(res) = &self.data.fetch_result {
+                            let tab = self.data.tab();
+                            if let Some(res) = tab.result_set() {
                                 if ui.button(icons::ICON_ARROW_DOWN).clicked() {
                                     let con = &mut self.config.connections[self.current_connection.unwrap()];
                                     con.meta_queries.insert(
                                         "test".into(),
                                         MetaQuery::from_normal_query(
                                             "Test",
-                                            self.data.query.clone(),
-                                            &res,
+                                            tab.query.clone(),
+                                            res,
                                         ),
                                     );
                                 }
@@ -209,8 +989,86 @@ impl<'a> DBView<'a> {
                             ui.with_layout(
                                 Layout::top_down(Align::Min).with_cross_justify(true),
                                 |ui| {
-                                    sql_editor::code_view_ui(ui, &mut self.data.query);
+                                    let tab = &mut self.data.tabs[self.data.current_tab];
+
+                                    // The positions are stale once the query is edited
+                                    let executed = tab.query.get(tab.executed_range.clone())
+                                        == Some(tab.executed_query.as_str());
+                                    let error = match &tab.fetch_result {
+                                        QueryState::Error(err) => err.position,
+                                        QueryState::Success(res) => {
+                                            res.error.as_ref().and_then(|err| err.position)
+                                        }
+                                        _ => None,
+                                    }
+                                    .filter(|_| executed)
+                                    .map(|position| tab.executed_range.start + position);
+
+                                    let highlight = tab
+                                        .executed_at
+                                        .filter(|at| executed && at.elapsed() < EXECUTED_HIGHLIGHT)
+                                        .map(|_| tab.executed_range.clone());
+                                    if highlight.is_some() {
+                                        ui.ctx().request_repaint();
+                                    }
+
+                                    let tables = match &self.data.tables {
+                                        QueryState::Success(tables) => tables
+                                            .iter()
+                                            .map(|table| table.table_name.as_str())
+                                            .collect(),
+                                        _ => vec![],
+                                    };
+                                    let columns = &mut self.data.columns;
+                                    let tx = &self.tx;
+
+                                    let mut fetch_columns = |table: &str| {
+                                        // The table may be typed in another case
+                                        let table = *tables
+                                            .iter()
+                                            .find(|name| name.eq_ignore_ascii_case(table))?;
+
+                                        let state = columns.entry(table.to_string()).or_default();
+                                        if let QueryState::Ready = state {
+                                            state.query(
+                                                tx,
+                                                MessageID::CompletionColumns,
+                                                Message::FetchColumns(table.to_string()),
+                                            );
+                                        }
+
+                                        match state {
+                                            QueryState::Success(columns) => Some(
+                                                columns.iter().map(|column| column.name.clone()).collect(),
+                                            ),
+                                            _ => None,
+                                        }
+                                    };
+
+                                    let schema = CompletionSchema {
+                                        kind: self.config.connections[self.current_connection.unwrap()].kind,
+                                        tables: tables.clone(),
+                                        columns: &mut fetch_columns,
+                                    };
+                                    let run = sql_editor::query_editor_ui(
+                                        ui,
+                                        &mut tab.query,
+                                        error,
+                                        highlight,
+                                        schema,
+                                    );
                                     ui.add_space(2.);
+
+                                    // The selection may hold several statements
+                                    match run {
+                                        Some(RunRequest::Statement(range)) => {
+                                            self.run_range(self.data.current_tab, range, true)
+                                        }
+                                        Some(RunRequest::Selection(range)) => {
+                                            self.run_range(self.data.current_tab, range, false)
+                                        }
+                                        None => {}
+                                    }
                                 },
                             );
                         }
@@ -231,86 +1089,551 @@ impl<'a> DBView<'a> {
             });
     }
 
-    fn show_central_panel(&mut self, ui: &mut Ui) {
-        egui::CentralPanel::default()
-            .frame(Frame::group(ui.style()))
-            .show_inside(ui, |ui| {
-                egui::ScrollArea::both().show(ui, |ui| {
-                    match &self.data.fetch_result {
-                        QueryState::Success(meta) => {
-                            meta_table::meta_table(ui, meta);
-                        }
-                        QueryState::Waiting => {
-                            ui.colored_label(Color32::BLUE, "Loading..");
-                        }
-                        QueryState::Error(err) => {
-                            ui.colored_label(
-                                Color32::RED,
-                                format!("An error has occurred: {}", err),
-                            );
-                        }
-                        QueryState::Ready => {
-                            ui.with_layout(
-                                Layout::centered_and_justified(egui::Direction::TopDown),
-                                |ui| {
-                                    ui.heading("Waiting request..");
-                                },
-                            );
-                        }
-                    };
+    /// Format the query of the editor, or put it on a single line.
+    fn format_query(&mut self, compact: bool) {
+        let kind = self.config.connections[self.current_connection.unwrap()].kind;
+        let tab = self.data.tab_mut();
+
+        tab.query = if compact {
+            sql::format::compact(&tab.query, kind)
+        } else {
+            sql::format::format(&tab.query, kind, &self.config.format)
+        };
+    }
+
+    fn execute(&mut self, id: MessageID, statements: Vec<SGDBStatement>) {
+        let tab = self.data.tab_mut();
+        tab.execute_statements = statements.len();
+        tab.execute_result.query(&self.tx, id, Message::Execute(statements));
+    }
+
+    fn show_edits_toolbar(&mut self, ui: &mut Ui) {
+        let tab = &mut self.data.tabs[self.data.current_tab];
+        let res = match (&tab.fetch_result, tab.result_set) {
+            (QueryState::Success(res), Some(index)) => &res.result_sets[index],
+            _ => return,
+        };
+
+        let key = match tab.editable_key(res) {
+            Ok(key) => key,
+            Err(err) => {
+                ui.label(RichText::new(format!("Read only: {}", err)).weak());
+                return;
+            }
+        };
+
+        let mut commit = false;
+
+        ui.horizontal(|ui| {
+            let pending = tab.edits.rows.len();
+
+            ui.label(format!("{} pending rows", pending));
+
+            ui.add_enabled_ui(pending > 0, |ui| {
+                if ui.button("Commit").clicked() {
+                    commit = true;
+                }
+                if ui.button("Discard").clicked() {
+                    tab.edits.clear();
+                    tab.execute_result = QueryState::Ready;
+                }
+            });
+
+            ui.separator();
+
+            // The rows are deleted by their key like the edits, so only with the same checks
+            let selected = tab.selection.len();
+            if ui
+                .add_enabled(
+                    selected > 0,
+                    egui::Button::new(format!("{} Delete {} rows", icons::ICON_TRASH, selected)),
+                )
+                .on_hover_text("Delete the selected rows")
+                .clicked()
+            {
+                tab.confirm_delete = true;
+            }
+
+            match &tab.execute_result {
+                QueryState::Waiting(_) => {
+                    ui.label("Executing..");
+                }
+                QueryState::Success(rows) => {
+                    ui.label(format!("{} rows affected", rows));
+                }
+                QueryState::Error(err) => {
+                    ui.colored_label(Color32::RED, format!("Execution failed: {}", err));
+                }
+                QueryState::Ready => {}
+            }
+        });
+
+        let kind = self.config.connections[self.current_connection.unwrap()].kind;
+        let table = tab.edit_table.as_deref().unwrap_or_default();
+
+        if commit {
+            let statements = tab.edits.statements(kind, table, tab.table_columns(), res, &key);
+            self.execute(MessageID::CommitEdits, statements);
+        }
+    }
+
+    fn show_delete_window(&mut self, ctx: &egui::Context) {
+        let tab = &mut self.data.tabs[self.data.current_tab];
+        if !tab.confirm_delete {
+            return;
+        }
+
+        let res = match (&tab.fetch_result, tab.result_set) {
+            (QueryState::Success(res), Some(index)) => &res.result_sets[index],
+            _ => return,
+        };
+        let key = match tab.editable_key(res) {
+            Ok(key) if !tab.selection.is_empty() => key,
+            _ => {
+                tab.confirm_delete = false;
+                return;
+            }
+        };
+        let table = tab.edit_table.as_deref().unwrap_or_default();
+
+        let mut confirmed = None;
+
+        egui::Window::new("Delete rows")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0., 0.])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "The {} selected rows of {} will be deleted.",
+                    tab.selection.len(),
+                    table
+                ));
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button(format!("{} Delete", icons::ICON_TRASH)).clicked() {
+                        confirmed = Some(true);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        confirmed = Some(false);
+                    }
                 });
             });
+
+        match confirmed {
+            Some(true) => {
+                tab.confirm_delete = false;
+
+                let kind = self.config.connections[self.current_connection.unwrap()].kind;
+                let statements = tab
+                    .selection
+                    .iter()
+                    .map(|row| {
+                        SGDBStatement::delete(kind, table, tab.table_columns(), res.row_key(*row, &key))
+                    })
+                    .collect();
+                self.execute(MessageID::DeleteRows, statements);
+            }
+            Some(false) => tab.confirm_delete = false,
+            None => {}
+        }
     }
 
-    pub fn process_db_response(&mut self, message: MessageResponse<MessageID>) {
-        match message {
-            MessageResponse::FetchAllResult(_, res) => {
-                match res {
-                    Ok(res) => {
-                        let results = res.data.into_iter()
-                            .map(|(col, values)| {
-                                let meta_col = MetaColumn::default_sgdb_column(col.name(), col.r#type());
+    fn show_pager(&mut self, ui: &mut Ui) {
+        let tab = &mut self.data.tabs[self.data.current_tab];
+        let rows = tab.result_set().map_or(0, |res| res.num_rows);
+        let pager = match tab.pager.as_mut() {
+            Some(pager) => pager,
+            None => return,
+        };
 
-                                (meta_col, values)
-                            }).collect();
+        let total = match &pager.total {
+            QueryState::Success(total) => Some(*total),
+            _ => None,
+        };
+        let action = pager::pager(ui, pager.offset, rows, pager.page_size, total, pager.end);
 
-                        self.data.fetch_result = QueryState::Success(FetchResult { num_rows: res.num_rows, res: results });
-                    },
-                    Err(_) => todo!(),
+        if let QueryState::Error(err) = &pager.total {
+            ui.colored_label(Color32::RED, format!("Can't count the rows: {}", err));
+        }
+        match &pager.more {
+            QueryState::Waiting(_) => {
+                ui.label("Loading more rows..");
+            }
+            QueryState::Error(err) => {
+                ui.colored_label(Color32::RED, format!("Can't load more rows: {}", err));
+            }
+            QueryState::Success(_) | QueryState::Ready => {}
+        }
+
+        ui.separator();
+
+        let page_size = pager.page_size as u64;
+        let (offset, limit) = match action {
+            Some(PageAction::First) => (0, pager.page_size),
+            Some(PageAction::Previous) => (pager.offset.saturating_sub(page_size), pager.page_size),
+            Some(PageAction::Next) => (pager.offset + rows as u64, pager.page_size),
+            Some(PageAction::Last) => {
+                let last = total.unwrap_or_default().saturating_sub(1) / page_size * page_size;
+                (last, pager.page_size)
+            }
+            Some(PageAction::PageSize(size)) => {
+                pager.page_size = size;
+                (pager.offset, size)
+            }
+            None => return,
+        };
+
+        tab.scroll_offset = Vec2::ZERO;
+        self.fetch_page(self.data.current_tab, offset, limit);
+    }
+
+    /// The number of rows streamed so far, or why the stream ended early.
+    fn show_stream_status(&mut self, ui: &mut Ui) {
+        let tab = &self.data.tabs[self.data.current_tab];
+        let rows = tab.result_set().map_or(0, |res| res.num_rows);
+        let stream = match &tab.stream {
+            Some(stream) => stream,
+            None => return,
+        };
+        let stopped = stream.stop.load(Ordering::SeqCst);
+
+        if !stream.done {
+            // The batches arrive without any input
+            ui.ctx().request_repaint();
+            ui.colored_label(Color32::BLUE, format!("{} rows..", rows));
+
+            if ui
+                .add_enabled(!stopped, egui::Button::new(format!("{} Stop", icons::ICON_STOP)))
+                .on_hover_text("Keep the rows received so far")
+                .clicked()
+            {
+                stream.stop.store(true, Ordering::SeqCst);
+            }
+        } else if stopped {
+            ui.label(format!("Stopped after {} rows", rows));
+        } else if stream.limit == Some(rows) {
+            ui.label(format!("The first {} rows", rows))
+                .on_hover_text("Change the row limit next to the run button to fetch more rows");
+        } else {
+            return;
+        }
+
+        ui.separator();
+    }
+
+    /// Add the query of the tab at `index` to the history with its result.
+    fn push_history(&mut self, index: usize, res: &Result<ScriptResult, SGDBError>) {
+        if let Some(entry) = self.data.tabs[index].history_entry.take() {
+            let entry = entry.finish_with(res);
+            let connection = &self.config.connections[self.current_connection.unwrap()];
+            self.history.push(&connection.name, entry);
+        }
+    }
+
+    fn show_export_menu(&mut self, ui: &mut Ui) {
+        let connection = &self.config.connections[self.current_connection.unwrap()];
+        let (kind, time_zone) = (connection.kind, connection.time_zone);
+        let tab = &mut self.data.tabs[self.data.current_tab];
+        let (script, index) = match (&tab.fetch_result, tab.result_set) {
+            (QueryState::Success(script), Some(index)) => (script, index),
+            _ => return,
+        };
+        let res = &script.result_sets[index];
+
+        // Only the first result set is streamed, all the rows of a browsed table are
+        let statement = match (&tab.pager, script.statement_of(index)) {
+            (Some(pager), _) => Ok(pager.query(kind, None)),
+            (None, Some((statement, 0))) => Ok(statement.query.clone()),
+            _ => Err("Only the first result set of a statement can be exported again"),
+        };
+
+        let path = &mut self.data.export_path;
+        if path.is_empty() {
+            *path = export::default_path(&tab.name, self.config.export.format);
+        }
+
+        let mut action = None;
+        ui.menu_button(format!("{} Export", icons::ICON_DOWNLOAD), |ui| {
+            action = export_menu::export_menu(
+                ui,
+                &mut self.config.export,
+                path,
+                res.num_rows,
+                statement.as_ref().map(|_| ()).map_err(|err| *err),
+            );
+
+            if action.is_some() {
+                ui.close_menu();
+            }
+        });
+
+        match &tab.export_result {
+            QueryState::Waiting(_) => {
+                ui.label("Exporting..");
+            }
+            QueryState::Success(rows) => {
+                ui.label(format!("{} rows exported", rows));
+
+                let warnings = tab.export_warnings.lock().unwrap();
+                if !warnings.is_empty() {
+                    let mut cells = warnings.iter().take(EXPORT_WARNINGS_SHOWN).cloned().collect::<Vec<_>>();
+                    if warnings.len() > EXPORT_WARNINGS_SHOWN {
+                        cells.push(format!("and {} more", warnings.len() - EXPORT_WARNINGS_SHOWN));
+                    }
+
+                    ui.colored_label(
+                        Color32::GOLD,
+                        format!("{} values written as NULL", warnings.len()),
+                    )
+                    .on_hover_text(format!(
+                        "{} has no literal for these values:\n{}",
+                        kind.name(),
+                        cells.join("\n")
+                    ));
                 }
-            },
-            MessageResponse::TablesResult(tables) => {
-                self.data.tables = match tables {
-                    Ok(tables) => QueryState::Success(tables),
-                    Err(err) => QueryState::Error(format!("{}", err))
+            }
+            QueryState::Error(err) => {
+                ui.colored_label(Color32::RED, format!("Export failed: {}", err));
+            }
+            QueryState::Ready => {}
+        }
+
+        let options = &self.config.export;
+        match action {
+            Some(ExportAction::Result) => {
+                let res = export::Exporter::create(path.as_str(), kind, time_zone, options).and_then(|mut exporter| {
+                    tab.export_warnings = exporter.warnings();
+                    export::export_result(res, &mut exporter)
+                });
+                tab.export_result = res.map_or_else(
+                    |err| QueryState::Error(format!("{}", err)),
+                    QueryState::Success,
+                );
+            }
+            Some(ExportAction::Rerun) => match export::Exporter::create(path.as_str(), kind, time_zone, options) {
+                Ok(exporter) => {
+                    tab.export_warnings = exporter.warnings();
+                    let message = Message::Stream(statement.unwrap(), Box::new(exporter));
+                    tab.export_result.query(&self.tx, MessageID::Export, message);
                 }
+                Err(err) => tab.export_result = QueryState::Error(format!("{}", err)),
             },
+            None => {}
+        }
+    }
+
+    fn show_insert_window(&mut self, ctx: &egui::Context) {
+        let tab = &mut self.data.tabs[self.data.current_tab];
+        let mut open = tab.insert_form.is_some();
+        let mut insert = false;
+
+        egui::Window::new("Insert a new row")
+            .open(&mut open)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                if let Some(form) = tab.insert_form.as_mut() {
+                    form.show(ui);
+                }
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if ui.button("Insert").clicked() {
+                        insert = true;
+                    }
+
+                    match &tab.execute_result {
+                        QueryState::Waiting(_) => {
+                            ui.label("Inserting..");
+                        }
+                        QueryState::Error(err) => {
+                            ui.colored_label(Color32::RED, format!("Insertion failed: {}", err));
+                        }
+                        QueryState::Success(_) | QueryState::Ready => {}
+                    }
+                });
+            });
+
+        if !open {
+            tab.insert_form = None;
+        } else if insert {
+            let kind = self.config.connections[self.current_connection.unwrap()].kind;
+            let table = tab.edit_table.as_deref().unwrap_or_default();
+            let values = tab.insert_form.as_ref().unwrap().values();
+
+            let statement = SGDBStatement::insert(kind, table, tab.table_columns(), values);
+            self.execute(MessageID::InsertRow, vec![statement]);
         }
     }
+
+    fn show_central_panel(&mut self, ui: &mut Ui) {
+        egui::CentralPanel::default()
+            .frame(Frame::group(ui.style()))
+            .show_inside(ui, |ui| {
+                ui.horizontal(|ui| {
+                    self.show_pager(ui);
+                    self.show_stream_status(ui);
+                    self.show_export_menu(ui);
+                    self.show_edits_toolbar(ui);
+                });
+
+                let kind = self.config.connections[self.current_connection.unwrap()].kind;
+                let tab = &mut self.data.tabs[self.data.current_tab];
+
+                let output = egui::ScrollArea::both()
+                    .id_source(("result", self.data.current_tab))
+                    .scroll_offset(tab.scroll_offset)
+                    .show(ui, |ui| {
+                        match &tab.fetch_result {
+                            QueryState::Success(res) => {
+                                if script_result::result_sets_bar(ui, res, &mut tab.result_set) {
+                                    tab.selection.clear();
+                                    tab.cells = CellSelection::None;
+                                    tab.edits.clear();
+                                    tab.view.sort = None;
+                                    tab.view.filters.clear();
+                                    tab.shown.clear();
+                                }
+
+                                if let Some(err) = &res.error {
+                                    query_error::query_error(ui, err, &tab.executed_query);
+                                    ui.separator();
+                                }
+
+                                match tab.result_set {
+                                    Some(index) => {
+                                        let meta = &res.result_sets[index];
+                                        let editable = tab.editable_key(meta).is_ok();
+
+                                        // The selected cells are positions among the rows shown
+                                        if !tab.shown.is_current(&tab.view) {
+                                            tab.cells = CellSelection::None;
+                                        }
+                                        let rows = tab.shown.get(&tab.view, meta);
+
+                                        if !tab.view.is_empty() {
+                                            ui.horizontal(|ui| {
+                                                ui.label(format!("{} of {} rows", rows.len(), meta.num_rows));
+                                                if ui.button("Show all the rows").clicked() {
+                                                    tab.view = RowView::default();
+                                                }
+                                            });
+                                        }
+
+                                        let edits = Some(&mut tab.edits).filter(|_| editable);
+                                        let output = meta_table::meta_table(
+                                            ui,
+                                            meta,
+                                            rows,
+                                            &mut tab.view,
+                                            &mut tab.selection,
+                                            &mut tab.cells,
+                                            edits,
+                                        );
+
+                                        // The statements insert into the table of the rows
+                                        let table = tab.edit_table.as_deref().unwrap_or("table_name");
+                                        let text = output.copy.and_then(|format| {
+                                            export::copy::copy_selection(meta, rows, &tab.cells, format, kind, table)
+                                        });
+                                        if let Some(text) = text {
+                                            ui.output().copied_text = text;
+                                        }
+
+                                        // The next rows of a table are loaded as its end is
+                                        // scrolled to, unless the rows are filtered
+                                        let filtered = !tab.view.filters.is_empty()
+                                            || !tab.view.search.trim().is_empty();
+                                        if let Some(pager) = tab.pager.as_mut() {
+                                            let idle = matches!(pager.more, QueryState::Ready | QueryState::Success(_));
+                                            if output.end_visible && idle && !pager.end && !filtered {
+                                                let offset = pager.offset + meta.num_rows as u64;
+                                                let query = pager.query(kind, Some((pager.page_size, offset)));
+                                                pager.more.query(&self.tx, MessageID::MoreRows, Message::FetchStatement(query, None));
+                                            }
+                                        }
+                                    }
+                                    None => script_result::statements_ui(ui, res),
+                                }
+                            }
+                            QueryState::Waiting(seq) => {
+                                let seq = *seq;
+                                ui.horizontal(|ui| {
+                                    ui.colored_label(Color32::BLUE, "Loading..");
+
+                                    let cancel = ui.add_enabled(
+                                        !matches!(tab.cancel_result, QueryState::Waiting(_)),
+                                        egui::Button::new(format!("{} Cancel", icons::ICON_STOP)),
+                                    );
+                                    if cancel.clicked() {
+                                        tab.cancel_result.query(&self.tx, MessageID::Cancel, Message::Cancel(seq));
+                                    }
+
+                                    if let QueryState::Error(err) = &tab.cancel_result {
+                                        ui.colored_label(Color32::RED, format!("Can't cancel: {}", err));
+                                    }
+                                });
+                            }
+                            QueryState::Error(err) => {
+                                query_error::query_error(ui, err, &tab.executed_query);
+                            }
+                            QueryState::Ready => {
+                                ui.with_layout(
+                                    Layout::centered_and_justified(egui::Direction::TopDown),
+                                    |ui| {
+                                        ui.heading("Waiting request..");
+                                    },
+                                );
+                            }
+                        };
+                    });
+
+                tab.scroll_offset = output.state.offset;
+            });
+    }
 }
 
 impl<'a> View for DBView<'a> {
     fn init(&mut self) {
+        // The saved tabs may be empty if they were edited by hand
+        if self.data.tabs.is_empty() {
+            self.data.new_tab();
+        }
+        self.data.current_tab = self.data.current_tab.min(self.data.tabs.len() - 1);
+
         self.data.tables.query(
-            self.tx,
+            &self.tx,
+            MessageID::Tables,
             Message::FetchTables,
         );
     }
 
     fn show(&mut self, ui: &mut Ui) {
-        if let Ok(msg) = self.rx.try_recv() {
-            self.process_db_response(msg);
+        let (format, compact) = {
+            let mut input = ui.input_mut();
+            (
+                input.consume_key(Modifiers::COMMAND | Modifiers::SHIFT, Key::F),
+                input.consume_key(Modifiers::COMMAND | Modifiers::SHIFT, Key::M),
+            )
+        };
+        if format || compact {
+            self.format_query(compact);
         }
 
         if self.data.show_left_panel {
             self.show_left_panel(ui);
         }
 
+        self.show_tabs_panel(ui);
+
         if self.data.show_bottom_panel {
             self.show_bottom_panel(ui);
         }
 
         self.show_central_panel(ui);
+
+        self.show_insert_window(ui.ctx());
+        self.show_delete_window(ui.ctx());
     }
 
     fn show_appbar(&mut self, ui: &mut Ui) {
@@ -319,11 +1642,419 @@ impl<'a> View for DBView<'a> {
             ui.checkbox(&mut self.data.show_bottom_panel, "Show bottom panel");
         });
         ui.menu_button("Actions", |ui| {
-            ui.button("E.g: Insert a new row");
+            let tab = self.data.tab_mut();
+            let columns = match &tab.edit_columns {
+                QueryState::Success(columns) => Some(columns),
+                _ => None,
+            };
+
+            if ui
+                .add_enabled(columns.is_some(), egui::Button::new("Insert a new row"))
+                .on_disabled_hover_text("Run a SELECT on a single table to insert rows into it")
+                .clicked()
+            {
+                tab.insert_form = columns.map(|columns| RowForm::new(columns));
+                tab.execute_result = QueryState::Ready;
+                ui.close_menu();
+            }
+        });
+        ui.menu_button("Format", |ui| {
+            if ui.button("Format query (Ctrl+Shift+F)").clicked() {
+                self.format_query(false);
+                ui.close_menu();
+            }
+            if ui.button("Compact query (Ctrl+Shift+M)").clicked() {
+                self.format_query(true);
+                ui.close_menu();
+            }
+
+            ui.separator();
+
+            let style = &mut self.config.format;
+            ui.horizontal(|ui| {
+                ui.label("Indentation");
+                ui.add(egui::DragValue::new(&mut style.indent).clamp_range(0..=8));
+            });
+            ui.checkbox(&mut style.uppercase_keywords, "Uppercase keywords");
+            ui.checkbox(&mut style.break_conditions, "New line before AND and OR");
         });
         ui.with_layout(Layout::right_to_left(), |ui| {
-            ui.text_edit_singleline(&mut "Search..");
+            let search = &mut self.data.tab_mut().view.search;
+            ui.add(egui::TextEdit::singleline(search).hint_text("Search.."))
+                .on_hover_text("Show the rows of the result containing the text in any column");
             ui.separator();
         });
     }
+
+    fn process_db_response(&mut self, id: RequestID, response: MessageResponse) {
+        match response {
+            MessageResponse::FetchAllResult(res) if id.component == MessageID::RowCount => {
+                if let Some(index) = self.data.waiting_pager(id.seq, |pager| &pager.total) {
+                    let pager = self.data.tabs[index].pager.as_mut().unwrap();
+                    pager.total.resolve_result(id.seq, row_count(res));
+                }
+            }
+            MessageResponse::FetchAllResult(res) if id.component == MessageID::MoreRows => {
+                let index = match self.data.waiting_pager(id.seq, |pager| &pager.more) {
+                    Some(index) => index,
+                    None => return,
+                };
+                let tab = &mut self.data.tabs[index];
+                let pager = tab.pager.as_mut().unwrap();
+
+                let more = match ScriptResult::from_sgdb(res) {
+                    Ok(res) => match res.error {
+                        Some(err) => Err(err),
+                        None => Ok(res.result_sets.into_iter().next()),
+                    },
+                    Err(err) => Err(err),
+                };
+
+                match more {
+                    Ok(more) => {
+                        let rows = more.as_ref().map_or(0, |more| more.num_rows);
+                        pager.end = rows < pager.page_size;
+                        pager.more = QueryState::Success(());
+
+                        if let (QueryState::Success(script), Some(more)) = (&mut tab.fetch_result, more) {
+                            script.append(more);
+                            tab.shown.clear();
+                        }
+                    }
+                    Err(err) => pager.more = QueryState::Error(err.message),
+                }
+            }
+            MessageResponse::FetchAllResult(res) => {
+                let mut res = ScriptResult::from_sgdb(res);
+
+                // The timestamps are shown in the time zone of the sessions
+                let time_zone = self.config.connections[self.current_connection.unwrap()].time_zone;
+                if let (Ok(res), Some(time_zone)) = (&mut res, time_zone) {
+                    for result_set in &mut res.result_sets {
+                        result_set.set_time_zone(time_zone);
+                    }
+                }
+
+                // The tab may have been closed since
+                let index = match self.data.waiting_tab(id.seq, |tab| &tab.fetch_result) {
+                    Some(index) => index,
+                    None => return,
+                };
+
+                self.push_history(index, &res);
+                if self.data.tabs[index].user_query {
+                    self.count_statements(res.as_ref().map_or(0, |res| res.statements.len()));
+                }
+                let tab = &mut self.data.tabs[index];
+
+                // Show the statements when one failed, they tell where the script stopped
+                tab.result_set = match &res {
+                    Ok(res) if res.error.is_none() && !res.result_sets.is_empty() => Some(0),
+                    _ => None,
+                };
+                if let Some(pager) = tab.pager.as_mut() {
+                    let rows = res.as_ref().map_or(0, |res| res.num_rows());
+                    pager.end = rows < pager.limit;
+                }
+                tab.shown.clear();
+                tab.fetch_result.resolve_result(id.seq, res);
+            },
+            MessageResponse::TablesResult(tables) => self.data.tables.resolve(id.seq, tables),
+            MessageResponse::ColumnsResult(table, res) if id.component == MessageID::CompletionColumns => {
+                if let Some(columns) = self.data.columns.get_mut(&table) {
+                    columns.resolve(id.seq, res);
+                }
+            }
+            MessageResponse::ColumnsResult(_, res) => {
+                let index = match self.data.waiting_tab(id.seq, |tab| &tab.edit_columns) {
+                    Some(index) => index,
+                    None => return,
+                };
+                let tab = &mut self.data.tabs[index];
+                tab.edit_columns.resolve(id.seq, res);
+
+                // The first page of a table waits for its primary key, the rows of a table
+                // without one aren't ordered
+                if let Some(pager) = tab.pager.as_mut().filter(|pager| pager.key.is_none()) {
+                    let key = match &tab.edit_columns {
+                        QueryState::Success(columns) => primary_key(columns),
+                        _ => vec![],
+                    };
+                    pager.key = Some(key);
+
+                    let page_size = pager.page_size;
+                    self.fetch_page(index, 0, page_size);
+                }
+            }
+            MessageResponse::ExecuteResult(res) => {
+                let index = match self.data.waiting_tab(id.seq, |tab| &tab.execute_result) {
+                    Some(index) => index,
+                    None => return,
+                };
+
+                match res {
+                    Ok(rows) => {
+                        let statements = self.data.tabs[index].execute_statements;
+                        self.count_statements(statements);
+                        if let MessageID::InsertRow = id.component {
+                            self.data.tabs[index].insert_form = None;
+                        }
+
+                        self.rerun(index);
+                        self.data.tabs[index].execute_result = QueryState::Success(rows);
+                    }
+                    Err(err) => {
+                        self.data.tabs[index].execute_result = QueryState::Error(format!("{}", err));
+                    }
+                }
+            }
+            MessageResponse::TransactionResult(open, res) => {
+                // The open transaction is tracked by the app, see `AppData::take_responses`
+                let ended = !open && res.is_ok();
+                self.data.transaction_error = res.err().map(|err| format!("{}", err));
+
+                if !ended {
+                    return;
+                }
+
+                // The rolled back changes may be visible in the rows of any tab
+                for index in 0..self.data.tabs.len() {
+                    let tab = &self.data.tabs[index];
+                    // Running a script again would run its other statements again
+                    let refresh = matches!(
+                        &tab.fetch_result,
+                        QueryState::Success(res) if res.statements.len() == 1 && !res.result_sets.is_empty()
+                    );
+                    if refresh {
+                        self.rerun(index);
+                    }
+                }
+            }
+            MessageResponse::StreamRows(batch) => {
+                let time_zone = self.config.connections[self.current_connection.unwrap()].time_zone;
+                let tab = match self.data.streaming_tab(id.seq) {
+                    Some(index) => &mut self.data.tabs[index],
+                    None => return,
+                };
+                let mut batch = FetchResult::from(batch);
+                if let Some(time_zone) = time_zone {
+                    batch.set_time_zone(time_zone);
+                }
+
+                // The result is shown from the first batch, with the columns
+                if tab.fetch_result.is_waiting_for(id.seq) {
+                    let res = ScriptResult::streamed(tab.executed_query.clone(), Some(batch));
+                    tab.fetch_result = QueryState::Success(res);
+                    tab.result_set = Some(0);
+                } else if let QueryState::Success(res) = &mut tab.fetch_result {
+                    res.append(batch);
+                }
+                tab.shown.clear();
+            }
+            MessageResponse::StreamResult(res) => {
+                if let Some(index) = self.data.waiting_tab(id.seq, |tab| &tab.export_result) {
+                    self.data.tabs[index].export_result.resolve(id.seq, res);
+                    return;
+                }
+
+                let index = match self.data.streaming_tab(id.seq) {
+                    Some(index) => index,
+                    None => return,
+                };
+                let tab = &mut self.data.tabs[index];
+                let stream = tab.stream.as_mut().unwrap();
+                stream.done = true;
+
+                let start = stream.start;
+                let error = res.err().map(|err| {
+                    let mut err = SGDBError::new(&err, &tab.executed_query[start..]);
+                    err.position = err.position.map(|position| start + position);
+                    err
+                });
+
+                // The rows received before an error are kept, a statement without any has no
+                // result set
+                let mut res = match (std::mem::replace(&mut tab.fetch_result, QueryState::Ready), error) {
+                    (QueryState::Success(mut res), error) => {
+                        res.error = error;
+                        Ok(res)
+                    }
+                    (_, Some(err)) => Err(err),
+                    (_, None) => Ok(ScriptResult::streamed(tab.executed_query.clone(), None)),
+                };
+                if let Ok(res) = &mut res {
+                    for statement in &mut res.statements {
+                        statement.duration = stream.started.elapsed();
+                    }
+                }
+
+                self.push_history(index, &res);
+                if self.data.tabs[index].user_query && matches!(&res, Ok(res) if res.error.is_none()) {
+                    self.count_statements(1);
+                }
+                let tab = &mut self.data.tabs[index];
+                tab.fetch_result = match res {
+                    Ok(res) => QueryState::Success(res),
+                    Err(err) => QueryState::Error(err),
+                };
+                tab.shown.clear();
+            }
+            MessageResponse::CancelResult(res) => {
+                if let Some(index) = self.data.waiting_tab(id.seq, |tab| &tab.cancel_result) {
+                    self.data.tabs[index].cancel_result.resolve(id.seq, res);
+                }
+            }
+            MessageResponse::IndexesResult(..)
+            | MessageResponse::ForeignKeysResult(..)
+            | MessageResponse::TriggersResult(..)
+            | MessageResponse::DDLResult(..) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{tests::app, Sqlife};
+    use crate::db::{sgdb::SGDBRowValue, Response};
+
+    /// Call `action` with the tables view, then process the responses until `done`.
+    fn wait(app: &mut Sqlife, action: impl FnOnce(&mut DBView), done: impl Fn(&DBView) -> bool) {
+        let data = match &mut app.view {
+            CurrentView::DBView(data) => data,
+            _ => panic!("The tables view isn't shown"),
+        };
+        action(&mut DBView::build(&mut app.data, data, &mut app.config));
+
+        let started = Instant::now();
+        loop {
+            let responses = app.data.take_responses(ViewKind::DBView);
+            let data = match &mut app.view {
+                CurrentView::DBView(data) => data,
+                _ => unreachable!(),
+            };
+            let mut view = DBView::build(&mut app.data, data, &mut app.config);
+            for Response { id, response } in responses {
+                view.process_db_response(id, response);
+            }
+
+            if done(&view) {
+                return;
+            }
+            assert!(started.elapsed() < Duration::from_secs(10));
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Whether the current tab received its rows and the columns of its table.
+    fn fetched(view: &DBView) -> bool {
+        let tab = &view.data.tabs[view.data.current_tab];
+        !matches!(tab.fetch_result, QueryState::Waiting(_))
+            && !matches!(tab.edit_columns, QueryState::Waiting(_))
+    }
+
+    /// Run `query` in the current tab and process the responses until its result and the columns
+    /// of its table are received.
+    fn run(app: &mut Sqlife, query: &str) {
+        let query = query.to_string();
+        let run = |view: &mut DBView| {
+            let tab = view.data.current_tab;
+            view.data.tabs[tab].query = query;
+            view.run_query(tab);
+        };
+
+        wait(app, run, fetched);
+    }
+
+    /// Whether the selected rows of the current tab can be deleted: the toolbar and the
+    /// confirmation window only offer it for rows mapped to their table rows.
+    fn can_delete(app: &mut Sqlife) -> bool {
+        let data = match &mut app.view {
+            CurrentView::DBView(data) => data,
+            _ => unreachable!(),
+        };
+        let tab = &mut data.tabs[data.current_tab];
+        tab.selection = [0].into_iter().collect();
+        tab.confirm_delete = true;
+
+        let ctx = egui::Context::default();
+        ctx.begin_frame(Default::default());
+        DBView::build(&mut app.data, data, &mut app.config).show_delete_window(&ctx);
+        ctx.end_frame();
+
+        let tab = &data.tabs[data.current_tab];
+        tab.confirm_delete && tab.result_set().map_or(false, |res| tab.editable_key(res).is_ok())
+    }
+
+    #[test]
+    fn delete_is_only_offered_for_plain_columns() {
+        let mut app = app();
+        run(
+            &mut app,
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT); \
+             INSERT INTO users VALUES (1, '2'), (2, '1')",
+        );
+
+        run(&mut app, "SELECT id, name FROM users");
+        assert!(can_delete(&mut app));
+
+        let queries = [
+            "SELECT name AS id, id AS name FROM users",
+            "SELECT id, UPPER(name) AS name FROM users",
+            "SELECT id, name, 1 FROM users",
+            "SELECT id, name, CURRENT_DATE FROM users",
+        ];
+        for query in queries {
+            run(&mut app, query);
+            assert!(!can_delete(&mut app), "{}", query);
+        }
+    }
+
+    #[test]
+    fn only_the_statements_of_the_user_which_succeed_are_counted() {
+        let mut app = app();
+        run(&mut app, "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)");
+
+        let begin = |view: &mut DBView| {
+            view.tx.send(MessageID::Transaction, Message::BeginTransaction);
+        };
+        wait(&mut app, begin, |view| view.transaction.is_some());
+        assert_eq!(app.data.transaction, Some(0));
+
+        // The script stops at its failed statement
+        run(
+            &mut app,
+            "INSERT INTO users VALUES (1, 'a'); SELECT * FROM missing; INSERT INTO users VALUES (2, 'b')",
+        );
+        assert_eq!(app.data.transaction, Some(1));
+
+        run(&mut app, "SELECT id, name FROM users");
+        assert_eq!(app.data.transaction, Some(2));
+
+        // The rows are fetched again after the deletion, by the view
+        let delete = |view: &mut DBView| {
+            let statement = SGDBStatement::delete(
+                SGDBKind::Sqlite,
+                "users",
+                &[],
+                vec![("id".to_string(), SGDBRowValue::Integer(1))],
+            );
+            view.execute(MessageID::DeleteRows, vec![statement]);
+        };
+        let deleted = |view: &DBView| {
+            let tab = &view.data.tabs[view.data.current_tab];
+            matches!(tab.execute_result, QueryState::Success(1)) && fetched(view)
+        };
+        wait(&mut app, delete, deleted);
+        assert_eq!(app.data.transaction, Some(3));
+
+        // Browsing a table counts its rows and fetches its pages
+        let browse = |view: &mut DBView| view.open_table(view.data.current_tab, "users".to_string());
+        let browsed = |view: &DBView| {
+            let tab = &view.data.tabs[view.data.current_tab];
+            fetched(view) && matches!(&tab.pager, Some(pager) if matches!(pager.total, QueryState::Success(_)))
+        };
+        wait(&mut app, browse, browsed);
+        assert_eq!(app.data.transaction, Some(3));
+    }
 }
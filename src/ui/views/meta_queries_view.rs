@@ -1,5 +1,3 @@
-use flume::{Receiver, Sender};
-
 use eframe::{
     egui::{self, Frame, Layout, ScrollArea, Ui},
     emath::Align,
@@ -12,12 +10,16 @@ use serde::{Deserialize, Serialize};
 use crate::{
     app::AppData,
     config::{ConnectionConfig, SqlifeConfig},
-    db::{Message, MessageResponse},
-    meta::{FetchResult, MetaColumn, MetaParam, MetaParamType, MetaParamValue, MetaQuery},
-    ui::components::{self, icons, meta_grid, meta_table, sql_editor},
+    history::{PendingEntry, QueryHistory},
+    db::{sgdb::SGDBError, Message, MessageResponse},
+    meta::{MetaParam, MetaParamType, MetaParamValue, MetaQuery, RowView, ScriptResult, ShownRows},
+    ui::components::{
+        self, history_menu::{self, HistoryAction}, icons, meta_grid, meta_table, query_error,
+        script_result, sql_editor,
+    },
 };
 
-use super::{MessageID, QueryState, View};
+use super::{MessageID, QueryState, RequestID, Requester, View, ViewKind};
 
 #[derive(PartialEq, Eq, Serialize, Deserialize)]
 enum BottomTab {
@@ -31,8 +33,9 @@ pub struct ViewData {
     show_bottom_panel: bool,
     bottom_tab: BottomTab,
 
-    query_history: Vec<String>,
     query: String,
+    #[serde(skip)]
+    history_search: String,
 
     selected_index: usize,
 
@@ -40,7 +43,21 @@ pub struct ViewData {
     right_panel: Option<RightPanel>,
 
     #[serde(skip)]
-    fetch_result: QueryState<FetchResult>,
+    fetch_result: QueryState<ScriptResult, SGDBError>,
+    /// The result set of `fetch_result` shown, `None` for the status of its statements.
+    #[serde(skip)]
+    result_set: Option<usize>,
+    /// The query of `fetch_result`.
+    #[serde(skip)]
+    executed_query: String,
+    /// The history entry of `executed_query`, added once `fetch_result` is received.
+    #[serde(skip)]
+    history_entry: Option<PendingEntry>,
+    /// The rows of the result shown, only the search applies to them.
+    #[serde(skip)]
+    view: RowView,
+    #[serde(skip)]
+    shown: ShownRows,
 }
 
 impl Default for ViewData {
@@ -49,9 +66,14 @@ impl Default for ViewData {
             show_left_panel: true,
             show_bottom_panel: true,
             bottom_tab: BottomTab::Query,
-            query_history: vec![],
             query: String::new(),
+            history_search: String::new(),
             fetch_result: QueryState::Ready,
+            result_set: None,
+            executed_query: String::new(),
+            history_entry: None,
+            view: RowView::default(),
+            shown: ShownRows::default(),
             right_panel: None,
             selected_index: 0,
         }
@@ -64,11 +86,11 @@ pub enum RightPanel {
 }
 
 pub struct MetaQueriesView<'a> {
-    pub rx: &'a Receiver<MessageResponse<MessageID>>,
-    pub tx: &'a Sender<Message<MessageID>>,
+    pub tx: Requester<'a>,
 
     pub config: &'a mut SqlifeConfig,
     pub current_connection: Option<usize>,
+    pub history: &'a mut QueryHistory,
 
     pub data: &'a mut ViewData,
 }
@@ -84,12 +106,27 @@ impl<'a> MetaQueriesView<'a> {
 
             config,
 
-            rx: app.rx_sgdb.as_ref().unwrap(),
-            tx: app.tx_sgdb.as_ref().unwrap(),
+            tx: Requester::new(
+                app.tx_sgdb.as_ref().unwrap(),
+                &app.request_seq,
+                ViewKind::MetaQueriesView,
+            ),
             current_connection: app.current_connection,
+            history: &mut app.history,
         }
     }
 
+    /// Run a query of the editor, the meta queries aren't added to the history.
+    fn run_query(&mut self, query: String) {
+        self.data.fetch_result.query(
+            &self.tx,
+            MessageID::FetchAllResult,
+            Message::FetchAll(query.clone()),
+        );
+        self.data.history_entry = Some(PendingEntry::new(query.clone()));
+        self.data.executed_query = query;
+    }
+
     fn show_left_panel(&mut self, ui: &mut Ui) {
         egui::SidePanel::left("left_panel")
             .resizable(true)
@@ -171,13 +208,13 @@ impl<'a> MetaQueriesView<'a> {
                                         })
                                         .collect();
 
-                                    self.tx
-                                        .send(Message::FetchAll(
-                                            MessageID::FetchAllResult,
-                                            q.meta_query.query.clone(),
-                                            Some(params),
-                                        ))
-                                        .unwrap();
+                                    self.data.executed_query = q.meta_query.query.clone();
+                                    self.data.history_entry = None;
+                                    self.data.fetch_result.query(
+                                        &self.tx,
+                                        MessageID::FetchAllResult,
+                                        Message::FetchStatement(q.meta_query.query.clone(), Some(params)),
+                                    );
                                     close = true;
                                 }
                             }
@@ -217,14 +254,7 @@ impl<'a> MetaQueriesView<'a> {
 
                         ui.with_layout(Layout::right_to_left(), |ui| {
                             if ui.button(icons::ICON_RUN).clicked() {
-                                self.data.fetch_result.query(
-                                    self.tx,
-                                    Message::FetchAll(
-                                        MessageID::FetchAllResult,
-                                        self.data.query.clone(),
-                                        None,
-                                    ),
-                                );
+                                self.run_query(self.data.query.clone());
                             }
 
                             if ui.button(icons::ICON_TRASH).clicked() {
@@ -232,7 +262,29 @@ impl<'a> MetaQueriesView<'a> {
                                 self.data.query.clear();
                             }
 
-                            ui.menu_button(icons::ICON_HISTORY, |ui| {});
+                            let mut history_action = None;
+                            let connection = &self.config.connections[self.current_connection.unwrap()];
+                            ui.menu_button(icons::ICON_HISTORY, |ui| {
+                                history_action = history_menu::history_menu(
+                                    ui,
+                                    self.history,
+                                    &connection.name,
+                                    &mut self.data.history_search,
+                                );
+
+                                if history_action.is_some() {
+                                    ui.close_menu();
+                                }
+                            });
+
+                            match history_action {
+                                Some(HistoryAction::Load(query)) => self.data.query = query,
+                                Some(HistoryAction::Run(query)) => {
+                                    self.data.query = query.clone();
+                                    self.run_query(query);
+                                }
+                                None => {}
+                            }
                         });
                     });
 
@@ -243,7 +295,21 @@ impl<'a> MetaQueriesView<'a> {
                             ui.with_layout(
                                 Layout::top_down(Align::Min).with_cross_justify(true),
                                 |ui| {
-                                    sql_editor::code_view_ui(ui, &mut self.data.query);
+                                    // The position is stale once the query is edited
+                                    let error = match &self.data.fetch_result {
+                                        QueryState::Error(err) => err.position,
+                                        QueryState::Success(res) => {
+                                            res.error.as_ref().and_then(|err| err.position)
+                                        }
+                                        _ => None,
+                                    }
+                                    .filter(|_| self.data.query == self.data.executed_query);
+
+                                    sql_editor::code_view_ui_with_error(
+                                        ui,
+                                        &mut self.data.query,
+                                        error,
+                                    );
                                     ui.add_space(2.);
                                 },
                             );
@@ -271,17 +337,34 @@ impl<'a> MetaQueriesView<'a> {
             .show_inside(ui, |ui| {
                 egui::ScrollArea::both().show(ui, |ui| {
                     match &self.data.fetch_result {
-                        QueryState::Success(meta) => {
-                            meta_grid::meta_grid(ui, meta, &mut self.data.selected_index);
+                        QueryState::Success(res) => {
+                            if script_result::result_sets_bar(ui, res, &mut self.data.result_set) {
+                                self.data.shown.clear();
+                            }
+
+                            if let Some(err) = &res.error {
+                                query_error::query_error(ui, err, &self.data.executed_query);
+                                ui.separator();
+                            }
+
+                            match self.data.result_set {
+                                Some(index) => {
+                                    let meta = &res.result_sets[index];
+                                    if !self.data.shown.is_current(&self.data.view) {
+                                        self.data.selected_index = 0;
+                                    }
+                                    let rows = self.data.shown.get(&self.data.view, meta);
+
+                                    meta_grid::meta_grid(ui, meta, rows, &mut self.data.selected_index)
+                                }
+                                None => script_result::statements_ui(ui, res),
+                            }
                         }
-                        QueryState::Waiting => {
+                        QueryState::Waiting(_) => {
                             ui.colored_label(Color32::BLUE, "Loading..");
                         }
                         QueryState::Error(err) => {
-                            ui.colored_label(
-                                Color32::RED,
-                                format!("An error has occurred: {}", err),
-                            );
+                            query_error::query_error(ui, err, &self.data.executed_query);
                         }
                         QueryState::Ready => {
                             ui.with_layout(
@@ -296,32 +379,6 @@ impl<'a> MetaQueriesView<'a> {
             });
     }
 
-    pub fn process_db_response(&mut self, message: MessageResponse<MessageID>) {
-        match message {
-            MessageResponse::FetchAllResult(_, res) => match res {
-                Ok(res) => {
-                    let results = res
-                        .data
-                        .into_iter()
-                        .map(|(col, values)| {
-                            let meta_col =
-                                MetaColumn::default_sgdb_column(col.name(), col.r#type());
-
-                            (meta_col, values)
-                        })
-                        .collect();
-
-                    self.data.fetch_result = QueryState::Success(FetchResult {
-                        num_rows: res.num_rows,
-                        res: results,
-                    });
-                }
-                Err(_) => todo!(),
-            },
-            MessageResponse::TablesResult(tables) => {}
-        }
-    }
-
     pub fn process_keybindings(&mut self, ui: &mut Ui) {
         let mut input = ui.input_mut();
 
@@ -346,10 +403,6 @@ impl<'a> View for MetaQueriesView<'a> {
     fn init(&mut self) {}
 
     fn show(&mut self, ui: &mut Ui) {
-        if let Ok(msg) = self.rx.try_recv() {
-            self.process_db_response(msg);
-        }
-
         self.process_keybindings(ui);
 
         if self.data.show_left_panel {
@@ -376,10 +429,48 @@ impl<'a> View for MetaQueriesView<'a> {
             ui.button("E.g: Insert a new row");
         });
         ui.with_layout(Layout::right_to_left(), |ui| {
-            ui.text_edit_singleline(&mut "Search..");
+            ui.add(egui::TextEdit::singleline(&mut self.data.view.search).hint_text("Search.."))
+                .on_hover_text("Show the rows of the result containing the text in any column");
             ui.separator();
         });
     }
+
+    fn process_db_response(&mut self, id: RequestID, response: MessageResponse) {
+        match response {
+            MessageResponse::FetchAllResult(res) => {
+                let res = ScriptResult::from_sgdb(res);
+
+                if !self.data.fetch_result.is_waiting_for(id.seq) {
+                    return;
+                }
+
+                if let Some(entry) = self.data.history_entry.take() {
+                    let entry = entry.finish_with(&res);
+                    let connection = &self.config.connections[self.current_connection.unwrap()];
+                    self.history.push(&connection.name, entry);
+                }
+
+                // Show the statements when one failed, they tell where the script stopped
+                self.data.result_set = match &res {
+                    Ok(res) if res.error.is_none() && !res.result_sets.is_empty() => Some(0),
+                    _ => None,
+                };
+                self.data.shown.clear();
+                self.data.fetch_result.resolve_result(id.seq, res);
+            }
+            MessageResponse::TablesResult(_)
+            | MessageResponse::ColumnsResult(..)
+            | MessageResponse::IndexesResult(..)
+            | MessageResponse::ForeignKeysResult(..)
+            | MessageResponse::TriggersResult(..)
+            | MessageResponse::DDLResult(..)
+            | MessageResponse::ExecuteResult(..)
+            | MessageResponse::StreamRows(_)
+            | MessageResponse::StreamResult(..)
+            | MessageResponse::TransactionResult(..)
+            | MessageResponse::CancelResult(_) => {}
+        }
+    }
 }
 
 pub struct EditMetaQuery {
@@ -0,0 +1,411 @@
+use eframe::{
+    egui::{self, Frame, Layout, ScrollArea, Ui},
+    emath::Align,
+    epaint::Color32,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::AppData,
+    config::SqlifeConfig,
+    db::{
+        sgdb::{
+            SGDBColumnKey, SGDBTable, SGDBTableColumn, SGDBTableForeignKey, SGDBTableIndex,
+            SGDBTableTrigger,
+        },
+        Message, MessageResponse,
+    },
+    ui::components::{icons, sql_editor},
+};
+
+use super::{MessageID, QueryState, RequestID, Requester, View, ViewKind};
+
+#[derive(PartialEq, Eq, Serialize, Deserialize)]
+enum StructureTab {
+    Columns,
+    Indexes,
+    ForeignKeys,
+    Triggers,
+    DDL,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ViewData {
+    show_left_panel: bool,
+    tab: StructureTab,
+
+    table: Option<String>,
+
+    #[serde(skip)]
+    tables: QueryState<Vec<SGDBTable>>,
+    #[serde(skip)]
+    columns: QueryState<Vec<SGDBTableColumn>>,
+    #[serde(skip)]
+    indexes: QueryState<Vec<SGDBTableIndex>>,
+    #[serde(skip)]
+    foreign_keys: QueryState<Vec<SGDBTableForeignKey>>,
+    #[serde(skip)]
+    triggers: QueryState<Vec<SGDBTableTrigger>>,
+    #[serde(skip)]
+    ddl: QueryState<String>,
+}
+
+impl Default for ViewData {
+    fn default() -> Self {
+        Self {
+            show_left_panel: true,
+            tab: StructureTab::Columns,
+            table: None,
+            tables: QueryState::Ready,
+            columns: QueryState::Ready,
+            indexes: QueryState::Ready,
+            foreign_keys: QueryState::Ready,
+            triggers: QueryState::Ready,
+            ddl: QueryState::Ready,
+        }
+    }
+}
+
+impl ViewData {
+    pub fn new(table: impl Into<String>) -> Self {
+        Self {
+            table: Some(table.into()),
+            ..Default::default()
+        }
+    }
+}
+
+pub struct TableStructureView<'a> {
+    pub tx: Requester<'a>,
+
+    pub data: &'a mut ViewData,
+}
+
+impl<'a> TableStructureView<'a> {
+    pub fn build(
+        app: &'a mut AppData,
+        data: &'a mut ViewData,
+        _config: &'a mut SqlifeConfig,
+    ) -> Self {
+        TableStructureView {
+            data,
+
+            tx: Requester::new(
+                app.tx_sgdb.as_ref().unwrap(),
+                &app.request_seq,
+                ViewKind::TableStructureView,
+            ),
+        }
+    }
+
+    fn fetch_structure(&mut self) {
+        if let Some(table) = self.data.table.clone() {
+            let tx = &self.tx;
+            let component = MessageID::Structure;
+
+            self.data
+                .columns
+                .query(tx, component.clone(), Message::FetchColumns(table.clone()));
+            self.data
+                .indexes
+                .query(tx, component.clone(), Message::FetchIndexes(table.clone()));
+            self.data
+                .foreign_keys
+                .query(tx, component.clone(), Message::FetchForeignKeys(table.clone()));
+            self.data
+                .triggers
+                .query(tx, component.clone(), Message::FetchTriggers(table.clone()));
+            self.data.ddl.query(tx, component, Message::FetchDDL(table));
+        }
+    }
+
+    fn show_left_panel(&mut self, ui: &mut Ui) {
+        egui::SidePanel::left("left_panel")
+            .resizable(true)
+            .default_width(300.)
+            .max_width(400.)
+            .show_inside(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.heading("Tables");
+                    ui.with_layout(Layout::right_to_left(), |ui| {
+                        if ui.button(icons::ICON_REFRESH).clicked() {
+                            self.data.tables.query(
+                                &self.tx,
+                                MessageID::Tables,
+                                Message::FetchTables,
+                            );
+                        }
+                    });
+                });
+
+                ui.separator();
+
+                let mut selected = None;
+
+                ScrollArea::both().show(ui, |ui| match &self.data.tables {
+                    QueryState::Success(res) => {
+                        ui.vertical(|ui| {
+                            for table in res.iter() {
+                                ui.with_layout(Layout::top_down_justified(Align::Min), |ui| {
+                                    let is_selected =
+                                        self.data.table.as_ref() == Some(&table.table_name);
+
+                                    if ui
+                                        .selectable_label(
+                                            is_selected,
+                                            &format!("{} {}", icons::ICON_TABLE, table.table_name),
+                                        )
+                                        .clicked()
+                                    {
+                                        selected = Some(table.table_name.clone());
+                                    }
+                                });
+                            }
+                        });
+                    }
+                    QueryState::Waiting(_) => {
+                        ui.label("Fetching tables..");
+                    }
+                    QueryState::Ready => {
+                        ui.label("Ready to fetch tables");
+                    }
+                    QueryState::Error(_) => {
+                        ui.colored_label(Color32::RED, "An error occurred while fetching tables");
+                    }
+                });
+
+                if let Some(table) = selected {
+                    self.data.table = Some(table);
+                    self.fetch_structure();
+                }
+            });
+    }
+
+    fn show_central_panel(&mut self, ui: &mut Ui) {
+        egui::CentralPanel::default()
+            .frame(Frame::group(ui.style()))
+            .show_inside(ui, |ui| {
+                let table = match &self.data.table {
+                    Some(table) => table.clone(),
+                    None => {
+                        ui.with_layout(
+                            Layout::centered_and_justified(egui::Direction::TopDown),
+                            |ui| {
+                                ui.heading("Select a table to view its structure.");
+                            },
+                        );
+                        return;
+                    }
+                };
+
+                ui.horizontal(|ui| {
+                    ui.heading(&table);
+                    ui.separator();
+
+                    for (tab, label) in [
+                        (StructureTab::Columns, "Columns"),
+                        (StructureTab::Indexes, "Indexes"),
+                        (StructureTab::ForeignKeys, "Foreign keys"),
+                        (StructureTab::Triggers, "Triggers"),
+                        (StructureTab::DDL, "DDL"),
+                    ] {
+                        if ui.selectable_label(self.data.tab == tab, label).clicked() {
+                            self.data.tab = tab;
+                        }
+                    }
+
+                    ui.with_layout(Layout::right_to_left(), |ui| {
+                        if ui.button(icons::ICON_REFRESH).clicked() {
+                            self.fetch_structure();
+                        }
+                    });
+                });
+
+                ui.separator();
+
+                ScrollArea::both().show(ui, |ui| match self.data.tab {
+                    StructureTab::Columns => {
+                        query_state_ui(ui, &self.data.columns, |ui, columns| {
+                            columns_ui(ui, columns)
+                        });
+                    }
+                    StructureTab::Indexes => {
+                        query_state_ui(ui, &self.data.indexes, |ui, indexes| {
+                            indexes_ui(ui, indexes)
+                        });
+                    }
+                    StructureTab::ForeignKeys => {
+                        query_state_ui(ui, &self.data.foreign_keys, |ui, foreign_keys| {
+                            foreign_keys_ui(ui, foreign_keys)
+                        });
+                    }
+                    StructureTab::Triggers => {
+                        query_state_ui(ui, &self.data.triggers, |ui, triggers| {
+                            triggers_ui(ui, triggers)
+                        });
+                    }
+                    StructureTab::DDL => {
+                        query_state_ui(ui, &self.data.ddl, |ui, ddl| {
+                            ui.with_layout(
+                                Layout::top_down(Align::Min).with_cross_justify(true),
+                                |ui| {
+                                    sql_editor::code_view_ui_read_only(ui, ddl);
+                                },
+                            );
+                        });
+                    }
+                });
+            });
+    }
+}
+
+fn query_state_ui<T>(ui: &mut Ui, state: &QueryState<T>, add_contents: impl FnOnce(&mut Ui, &T)) {
+    match state {
+        QueryState::Success(res) => add_contents(ui, res),
+        QueryState::Waiting(_) => {
+            ui.colored_label(Color32::BLUE, "Loading..");
+        }
+        QueryState::Error(err) => {
+            ui.colored_label(Color32::RED, format!("An error has occurred: {}", err));
+        }
+        QueryState::Ready => {}
+    }
+}
+
+fn header_ui(ui: &mut Ui, headers: &[&str]) {
+    for header in headers {
+        ui.label(egui::RichText::new(*header).underline());
+    }
+    ui.end_row();
+}
+
+fn read_only_checkbox(ui: &mut Ui, mut checked: bool) {
+    ui.add_enabled(false, egui::Checkbox::new(&mut checked, ""));
+}
+
+fn columns_ui(ui: &mut Ui, columns: &[SGDBTableColumn]) {
+    egui::Grid::new("structure_columns")
+        .striped(true)
+        .spacing([20.0, 4.0])
+        .show(ui, |ui| {
+            header_ui(
+                ui,
+                &["Name", "Type", "Nullable", "Default", "Key", "Extra", "Comment"],
+            );
+
+            for col in columns {
+                ui.label(&col.name);
+                ui.label(&col.column_type);
+                read_only_checkbox(ui, col.nullable);
+                match &col.default {
+                    Some(default) => ui.label(default),
+                    None => ui.colored_label(Color32::LIGHT_BLUE, "null"),
+                };
+                ui.label(match col.key {
+                    SGDBColumnKey::None => "",
+                    SGDBColumnKey::Primary => "PRI",
+                    SGDBColumnKey::Unique => "UNI",
+                    SGDBColumnKey::Multiple => "MUL",
+                });
+                ui.label(&col.extra);
+                ui.label(&col.comment);
+                ui.end_row();
+            }
+        });
+}
+
+fn indexes_ui(ui: &mut Ui, indexes: &[SGDBTableIndex]) {
+    egui::Grid::new("structure_indexes")
+        .striped(true)
+        .spacing([20.0, 4.0])
+        .show(ui, |ui| {
+            header_ui(ui, &["Name", "Columns", "Unique", "Primary", "Type"]);
+
+            for index in indexes {
+                ui.label(&index.name);
+                ui.label(index.columns.join(", "));
+                read_only_checkbox(ui, index.unique);
+                read_only_checkbox(ui, index.primary);
+                ui.label(&index.index_type);
+                ui.end_row();
+            }
+        });
+}
+
+fn foreign_keys_ui(ui: &mut Ui, foreign_keys: &[SGDBTableForeignKey]) {
+    egui::Grid::new("structure_foreign_keys")
+        .striped(true)
+        .spacing([20.0, 4.0])
+        .show(ui, |ui| {
+            header_ui(
+                ui,
+                &["Name", "Columns", "References", "On update", "On delete"],
+            );
+
+            for foreign_key in foreign_keys {
+                ui.label(&foreign_key.name);
+                ui.label(foreign_key.columns.join(", "));
+                ui.label(format!(
+                    "{}.{} ({})",
+                    foreign_key.referenced_schema,
+                    foreign_key.referenced_table,
+                    foreign_key.referenced_columns.join(", ")
+                ));
+                ui.label(&foreign_key.on_update);
+                ui.label(&foreign_key.on_delete);
+                ui.end_row();
+            }
+        });
+}
+
+fn triggers_ui(ui: &mut Ui, triggers: &[SGDBTableTrigger]) {
+    if triggers.is_empty() {
+        ui.label("No triggers");
+    }
+
+    for trigger in triggers {
+        ui.horizontal(|ui| {
+            ui.strong(&trigger.name);
+            ui.label(format!("{} {}", trigger.timing, trigger.event));
+        });
+        ui.with_layout(Layout::top_down(Align::Min).with_cross_justify(true), |ui| {
+            sql_editor::code_view_ui_read_only(ui, &trigger.statement);
+        });
+        ui.separator();
+    }
+}
+
+impl<'a> View for TableStructureView<'a> {
+    fn init(&mut self) {
+        self.data.tables.query(&self.tx, MessageID::Tables, Message::FetchTables);
+        self.fetch_structure();
+    }
+
+    fn show(&mut self, ui: &mut Ui) {
+        if self.data.show_left_panel {
+            self.show_left_panel(ui);
+        }
+
+        self.show_central_panel(ui);
+    }
+
+    fn show_appbar(&mut self, ui: &mut Ui) {
+        ui.menu_button("View", |ui| {
+            ui.checkbox(&mut self.data.show_left_panel, "Show left panel");
+        });
+    }
+
+    fn process_db_response(&mut self, id: RequestID, response: MessageResponse) {
+        let seq = id.seq;
+
+        match response {
+            MessageResponse::TablesResult(tables) => self.data.tables.resolve(seq, tables),
+            MessageResponse::ColumnsResult(_, res) => self.data.columns.resolve(seq, res),
+            MessageResponse::IndexesResult(_, res) => self.data.indexes.resolve(seq, res),
+            MessageResponse::ForeignKeysResult(_, res) => self.data.foreign_keys.resolve(seq, res),
+            MessageResponse::TriggersResult(_, res) => self.data.triggers.resolve(seq, res),
+            MessageResponse::DDLResult(_, res) => self.data.ddl.resolve(seq, res),
+            _ => {}
+        }
+    }
+}
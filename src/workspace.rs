@@ -0,0 +1,67 @@
+use std::{path::PathBuf, time::Instant};
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{self, SqlifeConfig},
+    ui::views::db_view,
+};
+
+/// The query tabs of each connection, by connection name, restored when switching back to it.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Workspaces {
+    connections: IndexMap<String, db_view::ViewData>,
+    /// When the first view not saved yet was stored.
+    #[serde(skip)]
+    changed: Option<Instant>,
+}
+
+impl Workspaces {
+    fn file_path() -> PathBuf {
+        SqlifeConfig::config_file_path().with_file_name("workspace.json")
+    }
+
+    pub fn load() -> Result<Self> {
+        config::load_json(Self::file_path())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        config::save_json(Self::file_path(), self, false)
+    }
+
+    /// Save the views stored [`config::SAVE_DELAY`] ago or more, or right away if `now`.
+    pub fn save_changes(&mut self, now: bool) {
+        match self.changed {
+            Some(changed) if now || changed.elapsed() >= config::SAVE_DELAY => {}
+            _ => return,
+        }
+
+        self.changed = None;
+        if let Err(err) = self.save() {
+            warn!("Can't save the workspace: {}", err);
+        }
+    }
+
+    /// Take the view of `connection`, a new one if it has none.
+    pub fn take(&mut self, connection: &str) -> db_view::ViewData {
+        self.connections.remove(connection).unwrap_or_default()
+    }
+
+    /// Stop waiting for the responses to the requests of the view of `connection`, once it's
+    /// closed.
+    pub fn abandon_requests(&mut self, connection: &str) {
+        if let Some(data) = self.connections.get_mut(connection) {
+            data.abandon_requests();
+        }
+    }
+
+    /// Keep the view of `connection`, it's saved by [`Workspaces::save_changes`].
+    pub fn store(&mut self, connection: &str, data: db_view::ViewData) {
+        self.connections.insert(connection.to_string(), data);
+
+        self.changed.get_or_insert_with(Instant::now);
+    }
+}